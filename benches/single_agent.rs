@@ -0,0 +1,116 @@
+//! Compare l'A* espace-temps de `PbsPlanner` et `SippPlanner` sur le layout procédural,
+//! avec une table de réservations chargée par des robots déjà planifiés.
+//!
+//! `cargo bench --bench single_agent`
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use warehouse_sim::core::{
+    GridPos, HighwayGraph, HighwayMode, SpaceTimeTable, WarehouseLayout,
+};
+use warehouse_sim::systems::pbs::{PbsConfig, PbsPlanner, StaticObstacles};
+use warehouse_sim::systems::sipp::SippPlanner;
+
+const QUERIES: usize = 200;
+const BACKGROUND_ROBOTS: usize = 80;
+const PARKED_UNTIL: u64 = 70;
+
+fn main() {
+    let (grid, zones) = WarehouseLayout::default().build();
+    let highways = HighwayGraph::alternating(&grid, &zones, HighwayMode::default());
+    let config = PbsConfig::default();
+    let obstacles = StaticObstacles::default();
+
+    let starts = &zones.spawn_points;
+    let goals: Vec<GridPos> = zones
+        .storage_cells
+        .iter()
+        .chain(&zones.cargo_cells)
+        .copied()
+        .collect();
+    let pair = |i: usize| (starts[i % starts.len()], goals[(i * 37 + 11) % goals.len()]);
+
+    // Trafic de fond entre zones de stockage et cargo, réservé séquentiellement
+    // comme le repli glouton de PBS
+    let mut table = SpaceTimeTable::default();
+    for i in 0..BACKGROUND_ROBOTS {
+        let entity = Entity::from_raw_u32(10_000 + i as u32).unwrap();
+        let (start, goal) = (goals[(i * 53) % goals.len()], goals[(i * 17 + 5) % goals.len()]);
+        let planner = PbsPlanner::new(&grid, &highways, &table, &obstacles, &config);
+        if let Some(path) = planner.plan_path(start, goal, 0, entity) {
+            table.reserve_path(&path, entity);
+        }
+    }
+
+    // Même trafic, et chaque but occupé par un robot à l'arrêt jusqu'au tick PARKED_UNTIL :
+    // l'agent doit attendre longtemps quelque part avant de pouvoir finir
+    let mut parked = table.clone();
+    let blocker = Entity::from_raw_u32(20_000).unwrap();
+    for i in 0..QUERIES {
+        let (_, goal) = pair(i);
+        for tick in 0..=PARKED_UNTIL {
+            parked.reserve(goal, tick, blocker);
+        }
+    }
+
+    println!("{QUERIES} queries, {BACKGROUND_ROBOTS} background robots, horizon {}", config.horizon);
+    for (scenario, table) in [("traffic", &table), ("parked goals", &parked)] {
+        println!("\n{scenario}");
+        println!("{:<10} {:>12} {:>12} {:>10} {:>10}", "planner", "total", "per query", "reached", "avg len");
+
+        let astar = run(pair, |i, start, goal| {
+            PbsPlanner::new(&grid, &highways, table, &obstacles, &config).plan_path(start, goal, 0, agent(i))
+        });
+        report("a*", &astar);
+
+        let sipp = run(pair, |i, start, goal| {
+            SippPlanner::new(&grid, &highways, table, &obstacles, &config).plan_path(start, goal, 0, agent(i))
+        });
+        report("sipp", &sipp);
+    }
+}
+
+fn agent(i: usize) -> Entity {
+    Entity::from_raw_u32(i as u32 + 1).unwrap()
+}
+
+struct Run {
+    elapsed: Duration,
+    reached: usize,
+    total_len: usize,
+}
+
+fn run(
+    pair: impl Fn(usize) -> (GridPos, GridPos),
+    mut plan: impl FnMut(usize, GridPos, GridPos) -> Option<Vec<(GridPos, u64)>>,
+) -> Run {
+    let started = Instant::now();
+    let mut reached = 0;
+    let mut total_len = 0;
+    for i in 0..QUERIES {
+        let (start, goal) = pair(i);
+        let path = std::hint::black_box(plan(i, start, goal));
+        // Les chemins partiels ne comptent pas
+        if let Some(path) = path.filter(|p| p.last().is_some_and(|&(pos, _)| pos == goal)) {
+            reached += 1;
+            total_len += path.len();
+        }
+    }
+    Run {
+        elapsed: started.elapsed(),
+        reached,
+        total_len,
+    }
+}
+
+fn report(name: &str, run: &Run) {
+    println!(
+        "{:<10} {:>12.2?} {:>12.2?} {:>10} {:>10.1}",
+        name,
+        run.elapsed,
+        run.elapsed / QUERIES as u32,
+        run.reached,
+        run.total_len as f64 / run.reached.max(1) as f64,
+    );
+}
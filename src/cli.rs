@@ -0,0 +1,438 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::constants::{
+    AGAINST_FLOW_COST, GRID_HEIGHT, GRID_WIDTH, ORDER_MAX_LINES, PROCEDURAL_MIN_HEIGHT,
+    PROCEDURAL_MIN_WIDTH,
+};
+use crate::core::movingai::{load_map, load_scenario};
+use crate::core::{HighwayMode, WarehouseLayout};
+use crate::plugins::movingai::{MovingAiError, MovingAiPlugin};
+use crate::plugins::simulation::SimulationSettings;
+use crate::systems::allocation::AllocatorRegistry;
+use crate::systems::battery::RobotModels;
+use crate::systems::faults::{FaultConfig, ScriptedFault};
+use crate::systems::inventory::{InventoryConfig, Slot};
+use crate::systems::orders::{OrderConfig, OrderSource, OrderSpec};
+use crate::systems::pbs::LowLevelPlanner;
+use crate::systems::planner::PlannerRegistry;
+use crate::systems::snapshot::SimulationSnapshot;
+
+/// Simulateur de trafic d'entrepôt
+#[derive(Debug, Parser)]
+#[command(version, about = "Warehouse traffic simulator")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Open the simulator window (default)
+    Run(SimArgs),
+    /// Run the simulation without window or renderer, as fast as possible
+    Headless {
+        #[command(flatten)]
+        sim: SimArgs,
+        /// Number of simulation ticks
+        #[arg(long, default_value_t = crate::constants::HEADLESS_TICKS)]
+        ticks: u64,
+    },
+    /// Run the same headless simulation with each planner and compare them
+    Bench {
+        #[command(flatten)]
+        sim: SimArgs,
+        #[arg(long, default_value_t = crate::constants::HEADLESS_TICKS)]
+        ticks: u64,
+        /// Planners to compare (default: all registered)
+        #[arg(long, value_delimiter = ',', value_parser = planner_name)]
+        planners: Vec<String>,
+        /// Task allocators to compare against each planner (default: the --allocator one)
+        #[arg(long, value_delimiter = ',', value_parser = allocator_name)]
+        allocators: Vec<String>,
+    },
+    /// Play back a recorded run in the window, without any planner
+    Replay {
+        /// Replay file written by `--record` (replay.ron in the run directory)
+        file: PathBuf,
+    },
+}
+
+/// Surcharges communes à toutes les sous-commandes
+#[derive(Debug, Clone, Args)]
+pub struct SimArgs {
+    /// Number of robots to spawn
+    #[arg(long)]
+    pub robots: Option<u32>,
+    /// Layout file (RON)
+    #[arg(long, conflicts_with_all = ["width", "height"], value_parser = layout_file)]
+    pub layout: Option<PathBuf>,
+    /// Width of the procedural layout
+    #[arg(long, value_parser = clap::value_parser!(u32).range(PROCEDURAL_MIN_WIDTH as i64..))]
+    pub width: Option<u32>,
+    /// Height of the procedural layout
+    #[arg(long, value_parser = clap::value_parser!(u32).range(PROCEDURAL_MIN_HEIGHT as i64..))]
+    pub height: Option<u32>,
+    /// One-way aisles: strict forbids moves against the flow, soft only makes them costlier
+    #[arg(long, value_enum)]
+    pub highways: Option<HighwayArg>,
+    /// Extra cost added to a step against the flow with --highways soft (at least 0)
+    #[arg(long, requires = "highways")]
+    pub against_flow_cost: Option<f32>,
+    /// Random seed
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Multi-agent planner (pbs, cbs, ecbs, pibt)
+    #[arg(long, value_parser = planner_name)]
+    pub planner: Option<String>,
+    /// Batch task allocator (nearest, hungarian, auction); without it a free robot takes
+    /// the first available storage cell
+    #[arg(long, value_parser = allocator_name)]
+    pub allocator: Option<String>,
+    /// Ticks between two batch task allocations
+    #[arg(long, requires = "allocator")]
+    pub allocation_interval: Option<u64>,
+    /// Low-level single-agent planner
+    #[arg(long, value_enum)]
+    pub low_level: Option<LowLevelArg>,
+    /// Planning horizon, in ticks
+    #[arg(long)]
+    pub horizon: Option<u64>,
+    /// Ticks between two replans
+    #[arg(long)]
+    pub replan_interval: Option<u64>,
+    /// A* heuristic weight
+    #[arg(long)]
+    pub heuristic_weight: Option<f32>,
+    /// Search tree budget before greedy fallback
+    #[arg(long)]
+    pub max_tree_nodes: Option<usize>,
+    /// ECBS suboptimality factor
+    #[arg(long)]
+    pub suboptimality: Option<f32>,
+    /// Pickup duration, in seconds
+    #[arg(long)]
+    pub pickup: Option<f32>,
+    /// Dropoff duration, in seconds
+    #[arg(long)]
+    pub dropoff: Option<f32>,
+    /// Battery level (0-1) below which an unloaded robot goes to a charger
+    #[arg(long)]
+    pub charge_threshold: Option<f32>,
+    /// Battery level (0-1) at which a robot leaves the charger
+    #[arg(long)]
+    pub charge_resume: Option<f32>,
+    /// Robot battery models (RON list), assigned in turn to spawned robots
+    #[arg(long, value_name = "FILE", value_parser = robot_models)]
+    pub robot_models: Option<RobotModels>,
+    /// Mean time between random breakdowns of a working robot, in seconds
+    #[arg(long, value_name = "SECS", value_parser = positive_secs)]
+    pub mtbf: Option<f32>,
+    /// Mean repair time of a breakdown, in seconds
+    #[arg(long, value_name = "SECS", value_parser = positive_secs)]
+    pub mttr: Option<f32>,
+    /// Scripted breakdowns (RON list of `(tick, robot, repair_secs)`)
+    // Chemin complet : clap lirait `Vec` comme une option à plusieurs valeurs
+    #[arg(long, value_name = "FILE", value_parser = fault_script)]
+    pub faults: Option<std::vec::Vec<ScriptedFault>>,
+    /// Generate missions from random orders arriving at this rate, in orders per hour
+    #[arg(long, value_name = "PER_HOUR", value_parser = positive_rate, conflicts_with = "orders")]
+    pub order_rate: Option<f32>,
+    /// Maximum number of lines of a random order
+    #[arg(long, requires = "order_rate")]
+    pub order_lines: Option<u32>,
+    /// Generate missions from the orders of this file (RON list of `(tick, lines, dock)`)
+    #[arg(long, value_name = "FILE", value_parser = order_script)]
+    pub orders: Option<std::vec::Vec<OrderSpec>>,
+    /// Number of SKUs spread over the storage cells (default: one per cell)
+    #[arg(long)]
+    pub skus: Option<u32>,
+    /// Zipf exponent of SKU popularity in random orders (0 for uniform demand)
+    #[arg(long)]
+    pub sku_skew: Option<f32>,
+    /// Units held by each storage slot
+    #[arg(long)]
+    pub slot_capacity: Option<u32>,
+    /// Initial stock of each slot, as a fraction (0-1) of its capacity
+    #[arg(long)]
+    pub initial_fill: Option<f32>,
+    /// Explicit slotting (RON list of `(cell, face, sku, quantity, capacity)`; a slot without
+    /// face stocks the first rack face of its cell)
+    #[arg(long, value_name = "FILE", value_parser = inventory_slots)]
+    pub inventory: Option<std::vec::Vec<Slot>>,
+    /// Write the KPI summary (RON) to this file at the end of the run
+    #[arg(long, value_name = "FILE")]
+    pub metrics_out: Option<PathBuf>,
+    /// Record trajectories and events as CSV in this run directory
+    #[arg(long, value_name = "DIR")]
+    pub record: Option<PathBuf>,
+    /// Also write the recording in the compact binary columnar format
+    #[arg(long, requires = "record")]
+    pub record_binary: bool,
+    /// Resume from a snapshot; layout and robots come from it, --planner, --allocator and
+    /// --seed override it to branch a what-if run
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_parser = snapshot_file,
+        conflicts_with_all = ["layout", "width", "height", "robots"]
+    )]
+    pub resume: Option<PathBuf>,
+    /// Save a snapshot of the whole simulation to this file at the end of the run
+    #[arg(long, value_name = "FILE")]
+    pub snapshot_out: Option<PathBuf>,
+    /// Check every tick for collisions, swaps and illegal moves (always on in debug builds)
+    #[arg(long)]
+    pub validate: bool,
+    /// Pause the window on the first motion violation (implies --validate)
+    #[arg(long)]
+    pub pause_on_violation: bool,
+    /// Replace the warehouse with a MovingAI benchmark map (.map)
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_parser = movingai_map,
+        requires = "movingai_scen",
+        conflicts_with_all = ["layout", "width", "height", "robots", "resume"]
+    )]
+    pub movingai_map: Option<PathBuf>,
+    /// MovingAI scenario (.scen) whose agents are spawned on the map
+    #[arg(long, value_name = "FILE", value_parser = movingai_scenario, requires = "movingai_map")]
+    pub movingai_scen: Option<PathBuf>,
+    /// Number of scenario agents to spawn, in file order (default: all)
+    #[arg(long, requires = "movingai_scen", value_parser = clap::value_parser!(u64).range(1..))]
+    pub agents: Option<u64>,
+}
+
+/// Nom d'un solveur du registre par défaut
+fn planner_name(name: &str) -> Result<String, String> {
+    let registry = PlannerRegistry::default();
+    if registry.names().any(|n| n == name) {
+        Ok(name.to_string())
+    } else {
+        let known: Vec<&str> = registry.names().collect();
+        Err(format!("unknown planner (available: {})", known.join(", ")))
+    }
+}
+
+/// Nom d'un allocateur du registre par défaut
+fn allocator_name(name: &str) -> Result<String, String> {
+    let registry = AllocatorRegistry::default();
+    if registry.names().any(|n| n == name) {
+        Ok(name.to_string())
+    } else {
+        let known: Vec<&str> = registry.names().collect();
+        Err(format!("unknown allocator (available: {})", known.join(", ")))
+    }
+}
+
+/// Fichier de layout lisible et cohérent
+fn layout_file(path: &str) -> Result<PathBuf, String> {
+    WarehouseLayout::load(path).map_err(|e| e.to_string())?;
+    Ok(PathBuf::from(path))
+}
+
+/// Snapshot lisible, dans la version courante
+fn snapshot_file(path: &str) -> Result<PathBuf, String> {
+    SimulationSnapshot::load(path).map_err(|e| e.to_string())?;
+    Ok(PathBuf::from(path))
+}
+
+/// Carte MovingAI lisible
+fn movingai_map(path: &str) -> Result<PathBuf, String> {
+    load_map(path).map_err(|e| e.to_string())?;
+    Ok(PathBuf::from(path))
+}
+
+/// Scénario MovingAI lisible ; sa cohérence avec la carte est vérifiée au chargement du plugin
+fn movingai_scenario(path: &str) -> Result<PathBuf, String> {
+    load_scenario(path).map_err(|e| e.to_string())?;
+    Ok(PathBuf::from(path))
+}
+
+/// Fichier de modèles de batterie, au moins un modèle de capacité non nulle
+fn robot_models(path: &str) -> Result<RobotModels, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let models = RobotModels::from_ron(&text).map_err(|e| e.to_string())?;
+    if models.models.is_empty() {
+        return Err("no robot model defined".to_string());
+    }
+    if let Some(model) = models.models.iter().find(|m| m.capacity_wh <= 0.0) {
+        return Err(format!("model `{}` has no battery capacity", model.name));
+    }
+    Ok(models)
+}
+
+/// Durée strictement positive, en secondes
+fn positive_secs(text: &str) -> Result<f32, String> {
+    match text.parse::<f32>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => Ok(secs),
+        Ok(_) => Err("must be a positive number of seconds".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Débit strictement positif
+fn positive_rate(text: &str) -> Result<f32, String> {
+    match text.parse::<f32>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        Ok(_) => Err("must be a positive rate".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Fichier de commandes datées, trié ensuite par `OrderSource::script`
+fn order_script(path: &str) -> Result<Vec<OrderSpec>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    OrderConfig::script_from_ron(&text).map_err(|e| e.to_string())
+}
+
+/// Fichier de plan de stockage, un emplacement par storage
+fn inventory_slots(path: &str) -> Result<Vec<Slot>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let slots = InventoryConfig::slots_from_ron(&text).map_err(|e| e.to_string())?;
+    InventoryConfig::check_slots(&slots).map_err(|e| e.to_string())?;
+    Ok(slots)
+}
+
+/// Fichier de pannes scriptées
+fn fault_script(path: &str) -> Result<Vec<ScriptedFault>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    FaultConfig::script_from_ron(&text).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum HighwayArg {
+    Strict,
+    Soft,
+    Open,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LowLevelArg {
+    Astar,
+    Sipp,
+}
+
+impl From<LowLevelArg> for LowLevelPlanner {
+    fn from(arg: LowLevelArg) -> Self {
+        match arg {
+            LowLevelArg::Astar => Self::SpaceTimeAStar,
+            LowLevelArg::Sipp => Self::Sipp,
+        }
+    }
+}
+
+impl SimArgs {
+    /// Instance MovingAI demandée, à ajouter après `SimulationCorePlugin`
+    pub fn movingai(&self) -> Result<Option<MovingAiPlugin>, MovingAiError> {
+        let (Some(map), Some(scenario)) = (&self.movingai_map, &self.movingai_scen) else {
+            return Ok(None);
+        };
+        let agents = self.agents.map_or(usize::MAX, |n| n as usize);
+        MovingAiPlugin::load(map, scenario, agents).map(Some)
+    }
+
+    /// Paramètres par défaut, surchargés par les options fournies
+    pub fn settings(&self) -> SimulationSettings {
+        let mut settings = SimulationSettings {
+            layout: self.layout.clone(),
+            seed: self.seed,
+            planner: self.planner.clone(),
+            allocator: self.allocator.clone(),
+            metrics_out: self.metrics_out.clone(),
+            record: self.record.clone(),
+            record_binary: self.record_binary,
+            resume: self.resume.clone(),
+            snapshot_out: self.snapshot_out.clone(),
+            pause_on_violation: self.pause_on_violation,
+            ..Default::default()
+        };
+        settings.validate |= self.validate || self.pause_on_violation;
+        if self.width.is_some() || self.height.is_some() {
+            settings.grid_size = Some((
+                self.width.unwrap_or(GRID_WIDTH),
+                self.height.unwrap_or(GRID_HEIGHT),
+            ));
+        }
+        match self.highways {
+            Some(HighwayArg::Strict) => settings.highway_mode = HighwayMode::Strict,
+            Some(HighwayArg::Open) => settings.highway_mode = HighwayMode::Open,
+            Some(HighwayArg::Soft) => {
+                let against_flow_cost = self.against_flow_cost.map_or(AGAINST_FLOW_COST, |cost| cost.max(0.0));
+                settings.highway_mode = HighwayMode::Soft { against_flow_cost };
+            }
+            None => {}
+        }
+        if let Some(interval) = self.allocation_interval {
+            settings.allocation.interval = interval.max(1);
+        }
+        if let Some(robots) = self.robots {
+            settings.robots = robots;
+        }
+        if let Some(pickup) = self.pickup {
+            settings.timings.pickup = pickup;
+        }
+        if let Some(dropoff) = self.dropoff {
+            settings.timings.dropoff = dropoff;
+        }
+        if let Some(threshold) = self.charge_threshold {
+            settings.charging.threshold = threshold.clamp(0.0, 1.0);
+        }
+        if let Some(level) = self.charge_resume {
+            settings.charging.resume_level = level.clamp(0.0, 1.0);
+        }
+        if let Some(models) = &self.robot_models {
+            settings.robot_models = models.clone();
+        }
+        settings.faults.mtbf = self.mtbf;
+        if let Some(mttr) = self.mttr {
+            settings.faults.mttr = mttr;
+        }
+        if let Some(script) = &self.faults {
+            settings.faults.script = script.clone();
+        }
+        if let Some(rate) = self.order_rate {
+            let max_lines = self.order_lines.unwrap_or(ORDER_MAX_LINES).max(1);
+            settings.orders.source = OrderSource::Poisson { rate, max_lines };
+        }
+        if let Some(specs) = &self.orders {
+            settings.orders.source = OrderSource::script(specs.clone());
+        }
+        settings.orders.skus = self.skus.filter(|&skus| skus > 0);
+        if let Some(skew) = self.sku_skew {
+            settings.orders.sku_skew = skew.max(0.0);
+        }
+        if let Some(capacity) = self.slot_capacity {
+            settings.inventory.capacity = capacity;
+        }
+        if let Some(fill) = self.initial_fill {
+            settings.inventory.initial_fill = fill.clamp(0.0, 1.0);
+        }
+        if let Some(slots) = &self.inventory {
+            settings.inventory.slots = Some(slots.clone());
+        }
+
+        let pbs = &mut settings.pbs;
+        if let Some(low_level) = self.low_level {
+            pbs.low_level = low_level.into();
+        }
+        if let Some(horizon) = self.horizon {
+            pbs.horizon = horizon;
+        }
+        if let Some(interval) = self.replan_interval {
+            pbs.replan_interval = interval.max(1);
+        }
+        if let Some(weight) = self.heuristic_weight {
+            pbs.heuristic_weight = weight;
+        }
+        if let Some(nodes) = self.max_tree_nodes {
+            pbs.max_tree_nodes = nodes;
+        }
+        if let Some(w) = self.suboptimality {
+            pbs.suboptimality = w.max(1.0);
+        }
+        settings
+    }
+}
@@ -1,13 +1,40 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::constants::TICK_RATE_HZ;
 use crate::core::GridPos;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum MissionPhase {
     #[default]
     GoingToStorage,
     PickingUp,
     GoingToCargo,
     DroppingOff,
+    GoingToCharger,
+    Charging,
+}
+
+impl MissionPhase {
+    pub const ALL: [Self; 6] = [
+        Self::GoingToStorage,
+        Self::PickingUp,
+        Self::GoingToCargo,
+        Self::DroppingOff,
+        Self::GoingToCharger,
+        Self::Charging,
+    ];
+}
+
+/// Référence d'article
+pub type Sku = u32;
+
+/// Ligne de commande : une référence à porter jusqu'à un quai
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderLine {
+    pub order: u64,
+    pub sku: Sku,
+    /// Index du quai dans `WarehouseZones::docks`
+    pub dock: usize,
 }
 
 #[derive(Component)]
@@ -15,35 +42,60 @@ pub struct Mission {
     pub phase: MissionPhase,
     pub storage_target: GridPos,
     pub cargo_target: GridPos,
+    /// Chargeur réservé, pendant les phases de recharge
+    pub charger: Option<GridPos>,
+    /// Tick d'attribution, pour mesurer le temps de cycle
+    pub started_at: u64,
+    /// Ligne de commande servie, absente hors flux de commandes
+    pub order: Option<OrderLine>,
 }
 
 impl Mission {
-    pub fn new(storage: GridPos, cargo: GridPos) -> Self {
+    pub fn new(storage: GridPos, cargo: GridPos, started_at: u64) -> Self {
         Self {
             phase: MissionPhase::GoingToStorage,
             storage_target: storage,
             cargo_target: cargo,
+            charger: None,
+            started_at,
+            order: None,
         }
     }
 }
 
-/// Timer pour les actions de chargement/déchargement
+/// Tâche affectée à l'avance par l'allocateur, storage et cargo déjà réservés ; le robot la
+/// prend dès qu'il est libre
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NextTask {
+    pub storage: GridPos,
+    pub cargo: GridPos,
+    pub order: Option<OrderLine>,
+}
+
+/// Timer pour les actions de chargement/déchargement, compté en ticks de simulation
 #[derive(Component)]
 pub struct ActionTimer {
-    pub remaining: f32,
+    pub remaining: u64,
+    pub total: u64,
 }
 
 impl ActionTimer {
-    pub fn new(duration: f32) -> Self {
-        Self { remaining: duration }
+    pub fn new(ticks: u64) -> Self {
+        Self { remaining: ticks, total: ticks }
     }
 
-    pub fn tick(&mut self, delta: f32) -> bool {
-        self.remaining -= delta;
-        self.remaining <= 0.0
+    /// Durée en secondes convertie en ticks (au moins un)
+    pub fn from_secs(secs: f32) -> Self {
+        Self::new(((secs as f64 * TICK_RATE_HZ).round() as u64).max(1))
     }
 
-    pub fn progress(&self, total: f32) -> f32 {
-        1.0 - (self.remaining / total).clamp(0.0, 1.0)
+    /// Avance d'un tick ; `true` une fois l'action terminée
+    pub fn tick(&mut self) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.remaining == 0
     }
-}
\ No newline at end of file
+
+    pub fn progress(&self) -> f32 {
+        1.0 - self.remaining as f32 / self.total.max(1) as f32
+    }
+}
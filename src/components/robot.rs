@@ -1,8 +1,13 @@
+use crate::constants::{
+    BATTERY_CAPACITY_WH, BATTERY_FADE_PER_CYCLE, BATTERY_LOADED_EXTRA, BATTERY_STANDBY_W,
+    BATTERY_WH_PER_CELL, CHARGE_CUTOFF, CHARGE_CV_LEVEL, CHARGE_POWER_W,
+};
 use crate::core::GridPos;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// État opérationnel du robot
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum RobotState {
     #[default]
     Idle,
@@ -14,6 +19,15 @@ pub enum RobotState {
 }
 
 impl RobotState {
+    pub const ALL: [Self; 6] = [
+        Self::Idle,
+        Self::Moving,
+        Self::Loading,
+        Self::Unloading,
+        Self::Charging,
+        Self::Fault,
+    ];
+
     /// Priorité de base selon l'état (plus haut = plus prioritaire)
     pub fn base_priority(&self) -> u8 {
         match self {
@@ -28,9 +42,13 @@ impl RobotState {
 
 /// Marqueur principal robot
 #[derive(Component)]
-#[require(GridPosition, State, Priority, Loaded, Battery, PlannedPath, Velocity)]
+#[require(GridPosition, State, Priority, Loaded, Battery, BatteryModel, BatteryUsage, PlannedPath, Velocity)]
 pub struct Robot;
 
+/// Numéro du robot dans l'ordre de mise en service, affiché `#n` et conservé par les snapshots
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RobotId(pub u32);
+
 /// Position actuelle sur la grille
 #[derive(Component, Default, Clone, Copy)]
 pub struct GridPosition(pub GridPos);
@@ -61,6 +79,116 @@ impl Default for Battery {
     }
 }
 
+/// Paramètres de batterie d'un modèle de robot ; les champs absents d'un fichier de
+/// modèles prennent les valeurs de `constants.rs`
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryModel {
+    pub name: String,
+    /// Capacité nominale, en Wh
+    pub capacity_wh: f32,
+    /// Énergie par cellule parcourue à vide, en Wh
+    pub wh_per_cell: f32,
+    /// Surconsommation en charge, en fraction de `wh_per_cell`
+    pub loaded_extra: f32,
+    /// Consommation permanente hors charge, en W
+    pub standby_w: f32,
+    /// Puissance de charge à courant constant, en W
+    pub charge_power_w: f32,
+    /// Niveau à partir duquel la puissance de charge décroît (tension constante)
+    pub cv_level: f32,
+    /// Puissance de fin de charge, en fraction de `charge_power_w`
+    pub cutoff: f32,
+    /// Capacité perdue par cycle complet équivalent
+    pub fade_per_cycle: f32,
+}
+
+impl Default for BatteryModel {
+    fn default() -> Self {
+        Self {
+            name: "standard".to_string(),
+            capacity_wh: BATTERY_CAPACITY_WH,
+            wh_per_cell: BATTERY_WH_PER_CELL,
+            loaded_extra: BATTERY_LOADED_EXTRA,
+            standby_w: BATTERY_STANDBY_W,
+            charge_power_w: CHARGE_POWER_W,
+            cv_level: CHARGE_CV_LEVEL,
+            cutoff: CHARGE_CUTOFF,
+            fade_per_cycle: BATTERY_FADE_PER_CYCLE,
+        }
+    }
+}
+
+impl BatteryModel {
+    /// Capacité restante après usure, en Wh ; jamais nulle
+    pub fn capacity(&self, usage: &BatteryUsage) -> f32 {
+        let health = 1.0 - self.fade_per_cycle * usage.cycles(self) as f32;
+        self.capacity_wh * health.max(0.01)
+    }
+
+    /// Énergie d'un déplacement d'une cellule, en Wh
+    pub fn move_energy(&self, loaded: bool) -> f32 {
+        if loaded {
+            self.wh_per_cell * (1.0 + self.loaded_extra)
+        } else {
+            self.wh_per_cell
+        }
+    }
+
+    /// Puissance de charge à ce niveau : constante, puis décroissante jusqu'à `cutoff`
+    pub fn charge_power(&self, level: f32) -> f32 {
+        if level < self.cv_level || self.cv_level >= 1.0 {
+            return self.charge_power_w;
+        }
+        let taper = (1.0 - level) / (1.0 - self.cv_level);
+        self.charge_power_w * taper.max(self.cutoff)
+    }
+}
+
+/// Usage cumulé de la batterie d'un robot
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatteryUsage {
+    /// Cellule au tick précédent, pour compter les déplacements
+    pub last_cell: Option<GridPos>,
+    /// Énergie déchargée depuis la mise en service, en Wh
+    pub discharged_wh: f64,
+}
+
+impl BatteryUsage {
+    /// Cycles complets équivalents, rapportés à la capacité nominale
+    pub fn cycles(&self, model: &BatteryModel) -> f64 {
+        self.discharged_wh / model.capacity_wh as f64
+    }
+}
+
+/// Origine d'une panne
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FaultCause {
+    /// Tirage aléatoire selon le MTBF
+    Random,
+    Scripted,
+    /// Demandée depuis l'interface
+    Manual,
+    /// Batterie vide : la réparation remplace la batterie par une pleine
+    BatteryDepleted,
+}
+
+/// Panne en cours, réparable
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Breakdown {
+    pub cause: FaultCause,
+    pub since: u64,
+    /// Tick où la réparation se termine
+    pub repair_at: u64,
+    /// Cellule de la mission sur laquelle le robot est arrêté, gardée réservée
+    pub blocked: Option<GridPos>,
+}
+
+/// Tick de la prochaine panne aléatoire, tiré une fois selon le MTBF à la mise en service
+/// et après chaque réparation
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NextFailure(pub u64);
+
 /// Trajectoire planifiée (positions + ticks)
 #[derive(Component, Default)]
 pub struct PlannedPath {
@@ -3,6 +3,11 @@ pub const GRID_WIDTH: u32 = 80;
 pub const GRID_HEIGHT: u32 = 60;
 pub const CELL_SIZE: f32 = 1.0;
 
+// === LAYOUT ===
+pub const LAYOUT_PATH: &str = "assets/layouts/warehouse.ron";
+/// Dossier des snapshots sauvegardés depuis l'interface
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
 // === ZONES ===
 pub const SPAWN_ZONE_WIDTH: u32 = 8;
 pub const CARGO_ZONE_WIDTH: u32 = 8;
@@ -11,10 +16,23 @@ pub const CARGO_ZONE_WIDTH: u32 = 8;
 pub const RACK_LENGTH: u32 = 12;
 pub const RACK_SPACING: u32 = 3;
 pub const AISLE_WIDTH: u32 = 2;
+/// Pénalité ajoutée au coût d'un pas à contre-sens dans un couloir en mode souple
+pub const AGAINST_FLOW_COST: f32 = 3.0;
+/// Plus petit layout procédural qui garde au moins une rangée de racks
+pub const PROCEDURAL_MIN_WIDTH: u32 = SPAWN_ZONE_WIDTH + CARGO_ZONE_WIDTH + 8;
+pub const PROCEDURAL_MIN_HEIGHT: u32 = RACK_LENGTH + 6;
 
 // === SIMULATION ===
 pub const TICK_RATE_HZ: f64 = 60.0;
 pub const TICK_DELTA: f32 = 1.0 / TICK_RATE_HZ as f32;
+/// Graine utilisée quand aucune n'est fournie
+pub const DEFAULT_SEED: u64 = 42;
+/// Durée d'une exécution headless (1 minute de simulation)
+pub const HEADLESS_TICKS: u64 = 3600;
+/// Multiplicateurs proposés pour la vitesse de simulation, du plus lent au plus rapide
+pub const SPEED_PRESETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0];
+/// Temps de calcul accordé aux ticks à chaque frame en vitesse max, en millisecondes
+pub const MAX_SPEED_FRAME_BUDGET_MS: u64 = 12;
 
 // === ROBOT ===
 pub const ROBOT_COUNT: u32 = 150;
@@ -26,6 +44,59 @@ pub const ROBOT_DECELERATION: f32 = 3.0;
 pub const PICKUP_DURATION: f32 = 4.0;
 pub const DROPOFF_DURATION: f32 = 3.0;
 
+// === BATTERIE (fraction de la capacité) ===
+/// Niveau sous lequel un robot sans charge part se recharger
+pub const CHARGE_THRESHOLD: f32 = 0.2;
+/// Niveau auquel un robot quitte le chargeur
+pub const CHARGE_RESUME_LEVEL: f32 = 0.9;
+
+// === MODÈLE DE BATTERIE PAR DÉFAUT ===
+/// Capacité nominale, en Wh (24 V, 40 Ah)
+pub const BATTERY_CAPACITY_WH: f32 = 960.0;
+/// Énergie par cellule parcourue à vide, en Wh
+pub const BATTERY_WH_PER_CELL: f32 = 0.05;
+/// Surconsommation en charge, en fraction de l'énergie à vide
+pub const BATTERY_LOADED_EXTRA: f32 = 0.6;
+/// Consommation permanente (électronique, capteurs), en W
+pub const BATTERY_STANDBY_W: f32 = 25.0;
+/// Puissance de charge en phase à courant constant, en W
+pub const CHARGE_POWER_W: f32 = 480.0;
+/// Niveau à partir duquel la charge passe à tension constante
+pub const CHARGE_CV_LEVEL: f32 = 0.8;
+/// Puissance de fin de charge, en fraction de `CHARGE_POWER_W`
+pub const CHARGE_CUTOFF: f32 = 0.05;
+/// Capacité perdue par cycle complet équivalent
+pub const BATTERY_FADE_PER_CYCLE: f32 = 0.0002;
+
+// === PANNES (en secondes) ===
+/// Durée moyenne d'une réparation
+pub const FAULT_MTTR: f32 = 30.0;
+/// Remplacement sur place de la batterie d'un robot tombé à vide
+pub const BATTERY_SWAP_SECS: f32 = 120.0;
+
+// === COMMANDES ET STOCK ===
+/// Nombre maximal de lignes d'une commande aléatoire
+pub const ORDER_MAX_LINES: u32 = 3;
+/// Exposant de Zipf de la popularité des références
+pub const SKU_SKEW: f32 = 1.0;
+/// Unités par emplacement de stockage
+pub const SLOT_CAPACITY: u32 = 100;
+
+// === AFFECTATION DES TÂCHES ===
+/// Ticks entre deux affectations groupées
+pub const ALLOCATION_INTERVAL: u64 = 30;
+/// Tâches examinées par robot à affecter : lignes en attente les plus anciennes, ou hors
+/// flux de commandes storages libres les plus proches
+pub const ALLOCATION_LOOKAHEAD: usize = 2;
+/// Champs de distance gardés en cache par l'allocation, les moins récemment utilisés
+/// sont évincés au-delà
+pub const ALLOCATION_DISTANCE_CACHE: usize = 256;
+
+// === PIBT ===
+/// Champs de distance par but gardés en cache par PIBT, au-delà les moins récemment
+/// utilisés sont évincés ; les buts du tick courant restent toujours en cache
+pub const PIBT_DISTANCE_CACHE: usize = 256;
+
 // === PBS CONFIG ===
 pub const PBS_HORIZON_TICKS: u64 = 100;
 pub const PBS_REPLAN_INTERVAL: u64 = 3;
\ No newline at end of file
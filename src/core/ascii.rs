@@ -0,0 +1,240 @@
+use super::layout::{CellOverride, LAYOUT_VERSION};
+use super::zones::Rack;
+use super::{CellType, GridPos, LayoutError, WarehouseLayout};
+
+impl WarehouseLayout {
+    /// Format texte compact, un caractère par cellule :
+    ///
+    /// | car. | rôle           |
+    /// |------|----------------|
+    /// | `.`  | sol            |
+    /// | `R`  | rack           |
+    /// | `@`  | bloqué         |
+    /// | `S`  | spawn          |
+    /// | `A`  | accès stockage |
+    /// | `C`  | cargo          |
+    /// | `E`  | chargeur       |
+    ///
+    /// La première ligne non vide donne `y = 0`. Les espaces en début et fin de ligne
+    /// sont ignorés, ce qui permet d'indenter le texte dans un test.
+    pub fn from_ascii(text: &str) -> Result<Self, LayoutError> {
+        // Ligne du texte, indentation retirée (en caractères) et cellules
+        let mut rows: Vec<(usize, usize, Vec<char>)> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() {
+                let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+                rows.push((i, indent, trimmed.chars().collect()));
+            }
+        }
+
+        let Some((_, _, first)) = rows.first() else {
+            return Err(LayoutError::Parse {
+                line: 1,
+                column: 1,
+                message: "empty layout".into(),
+            });
+        };
+        let width = first.len();
+
+        let mut layout = Self {
+            version: LAYOUT_VERSION,
+            width: width as u32,
+            height: rows.len() as u32,
+            cells: Vec::new(),
+            racks: Vec::new(),
+            spawn_points: Vec::new(),
+            storage_cells: Vec::new(),
+            cargo_cells: Vec::new(),
+            charger_cells: Vec::new(),
+        };
+        let mut is_rack = vec![vec![false; width]; rows.len()];
+
+        for (y, (line_index, indent, row)) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(LayoutError::Parse {
+                    line: line_index + 1,
+                    column: indent + 1,
+                    message: format!("row has {} cells, expected {width}", row.len()),
+                });
+            }
+
+            for (x, &c) in row.iter().enumerate() {
+                let pos = GridPos::new(x as i32, y as i32);
+                match c {
+                    '.' => {}
+                    'R' => is_rack[y][x] = true,
+                    '@' => layout.cells.push(CellOverride { pos, kind: CellType::Blocked }),
+                    'S' => layout.spawn_points.push(pos),
+                    'A' => layout.storage_cells.push(pos),
+                    'C' => layout.cargo_cells.push(pos),
+                    'E' => layout.charger_cells.push(pos),
+                    other => {
+                        return Err(LayoutError::Parse {
+                            line: line_index + 1,
+                            column: indent + x + 1,
+                            message: format!("unknown cell `{other}`"),
+                        });
+                    }
+                }
+            }
+        }
+
+        layout.racks = rack_rectangles(&is_rack);
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// Rendu texte du layout, dans le même format que `from_ascii`
+    pub fn to_ascii(&self) -> String {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let mut chars = vec![vec!['.'; w]; h];
+        let mut put = |pos: GridPos, c: char| {
+            if let Some(cell) = chars
+                .get_mut(pos.y as usize)
+                .and_then(|row| row.get_mut(pos.x as usize))
+            {
+                *cell = c;
+            }
+        };
+
+        for cell in &self.cells {
+            match cell.kind {
+                CellType::Floor => {}
+                CellType::Rack => put(cell.pos, 'R'),
+                CellType::Blocked => put(cell.pos, '@'),
+            }
+        }
+        for rack in &self.racks {
+            for y in rack.start.y..=rack.end.y {
+                for x in rack.start.x..=rack.end.x {
+                    put(GridPos::new(x, y), 'R');
+                }
+            }
+        }
+        for (cells, c) in [
+            (&self.spawn_points, 'S'),
+            (&self.storage_cells, 'A'),
+            (&self.cargo_cells, 'C'),
+            (&self.charger_cells, 'E'),
+        ] {
+            for &pos in cells {
+                put(pos, c);
+            }
+        }
+
+        let mut out = String::with_capacity((w + 1) * h);
+        for row in chars {
+            out.extend(row);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Découpe les cellules rack en rectangles : chaque rectangle s'étend d'abord
+/// en largeur sur sa première rangée, puis en hauteur tant que la bande est pleine.
+fn rack_rectangles(is_rack: &[Vec<bool>]) -> Vec<Rack> {
+    let height = is_rack.len();
+    let width = is_rack.first().map_or(0, Vec::len);
+    let mut used = vec![vec![false; width]; height];
+    let mut racks = Vec::new();
+    let free = |used: &[Vec<bool>], x: usize, y: usize| is_rack[y][x] && !used[y][x];
+
+    for y in 0..height {
+        for x in 0..width {
+            if !free(&used, x, y) {
+                continue;
+            }
+
+            let mut x_end = x;
+            while x_end + 1 < width && free(&used, x_end + 1, y) {
+                x_end += 1;
+            }
+
+            let mut y_end = y;
+            while y_end + 1 < height && (x..=x_end).all(|cx| free(&used, cx, y_end + 1)) {
+                y_end += 1;
+            }
+
+            for row in used.iter_mut().take(y_end + 1).skip(y) {
+                row[x..=x_end].fill(true);
+            }
+
+            racks.push(Rack {
+                start: GridPos::new(x as i32, y as i32),
+                end: GridPos::new(x_end as i32, y_end as i32),
+            });
+        }
+    }
+
+    racks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> (usize, usize, String) {
+        match WarehouseLayout::from_ascii(text) {
+            Err(LayoutError::Parse { line, column, message }) => (line, column, message),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_layout_round_trips() {
+        let text = "S.RR.A\n@.RR.C\nE....C\n";
+        let layout = WarehouseLayout::from_ascii(
+            "
+            S.RR.A
+            @.RR.C
+            E....C
+            ",
+        )
+        .unwrap();
+        assert_eq!((layout.width, layout.height), (6, 3));
+        assert_eq!(layout.racks, [Rack { start: GridPos::new(2, 0), end: GridPos::new(3, 1) }]);
+        assert_eq!(layout.spawn_points, [GridPos::new(0, 0)]);
+        assert_eq!(layout.storage_cells, [GridPos::new(5, 0)]);
+        assert_eq!(layout.cargo_cells, [GridPos::new(5, 1), GridPos::new(5, 2)]);
+        assert_eq!(layout.charger_cells, [GridPos::new(0, 2)]);
+        assert_eq!(layout.cells, [CellOverride { pos: GridPos::new(0, 1), kind: CellType::Blocked }]);
+        assert_eq!(layout.to_ascii(), text);
+    }
+
+    #[test]
+    fn unknown_cells_are_located() {
+        let (line, column, message) = parse_error("S..A\n..x.\n...C");
+        assert_eq!((line, column), (2, 3));
+        assert_eq!(message, "unknown cell `x`");
+
+        // Colonnes comptées sur la ligne d'origine, indentation comprise
+        let (line, column, _) = parse_error(
+            "
+            S..A
+            ..x.
+            ...C
+            ",
+        );
+        assert_eq!((line, column), (3, 15));
+        let (line, column, _) = parse_error("S..A\n\t..x.\n...C");
+        assert_eq!((line, column), (2, 4));
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        // Les lignes vides comptent dans la numérotation
+        let (line, column, message) = parse_error("S..A\n\n...C.\n");
+        assert_eq!((line, column), (3, 1));
+        assert_eq!(message, "row has 5 cells, expected 4");
+    }
+
+    #[test]
+    fn empty_layout_is_rejected() {
+        for text in ["", "\n  \n\t\n"] {
+            let (line, column, message) = parse_error(text);
+            assert_eq!((line, column, message.as_str()), (1, 1, "empty layout"));
+        }
+    }
+}
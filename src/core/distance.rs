@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use super::{Direction, GridPos, HighwayGraph, WarehouseGrid};
+
+/// Nombre de pas jusqu'à une cellule cible depuis chaque cellule de la grille,
+/// en respectant les sens de circulation légaux
+pub struct DistanceField {
+    width: u32,
+    height: u32,
+    target: GridPos,
+    distances: Vec<u32>,
+}
+
+impl DistanceField {
+    /// BFS inverse depuis `target`
+    pub fn towards(target: GridPos, grid: &WarehouseGrid, highways: &HighwayGraph) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        let mut field = Self {
+            width,
+            height,
+            target,
+            distances: vec![u32::MAX; (width * height) as usize],
+        };
+
+        let Some(start) = field.index(target).filter(|_| grid.is_passable(target)) else {
+            return field;
+        };
+        field.distances[start] = 0;
+
+        let mut queue = VecDeque::from([target]);
+        while let Some(pos) = queue.pop_front() {
+            let next = field.distances[field.index(pos).unwrap()] + 1;
+            for dir in Direction::CARDINALS {
+                let from = pos.neighbor(dir);
+                if !grid.is_passable(from) || !highways.is_move_legal(from, pos) {
+                    continue;
+                }
+                let i = field.index(from).unwrap();
+                if field.distances[i] == u32::MAX {
+                    field.distances[i] = next;
+                    queue.push_back(from);
+                }
+            }
+        }
+
+        field
+    }
+
+    #[inline]
+    fn index(&self, pos: GridPos) -> Option<usize> {
+        let inside = pos.x >= 0
+            && pos.y >= 0
+            && (pos.x as u32) < self.width
+            && (pos.y as u32) < self.height;
+        inside.then(|| (pos.y as u32 * self.width + pos.x as u32) as usize)
+    }
+
+    pub fn target(&self) -> GridPos {
+        self.target
+    }
+
+    /// Distance en pas, `None` si la cible est inatteignable depuis `pos`
+    #[inline]
+    pub fn get(&self, pos: GridPos) -> Option<u32> {
+        self.index(pos)
+            .map(|i| self.distances[i])
+            .filter(|&d| d != u32::MAX)
+    }
+}
@@ -1,8 +1,10 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use super::GridPos;
 use crate::constants::{CELL_SIZE, GRID_HEIGHT, GRID_WIDTH};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum CellType {
     #[default]
     Floor,
@@ -17,11 +19,20 @@ impl CellType {
     }
 }
 
-#[derive(Resource)]
+/// Nouveau numéro de révision, unique dans le processus : deux grilles (ou deux états d'une
+/// même grille) n'en partagent jamais, même à dimensions égales
+pub(crate) fn next_revision() -> u64 {
+    static REVISION: AtomicU64 = AtomicU64::new(0);
+    REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Resource, Clone)]
 pub struct WarehouseGrid {
     width: u32,
     height: u32,
     cells: Vec<CellType>,
+    /// Change à chaque modification des cellules, pour invalider les caches dérivés
+    revision: u64,
 }
 
 impl Default for WarehouseGrid {
@@ -37,6 +48,7 @@ impl WarehouseGrid {
             width,
             height,
             cells: vec![CellType::Floor; size],
+            revision: next_revision(),
         }
     }
 
@@ -59,6 +71,7 @@ impl WarehouseGrid {
     pub fn set(&mut self, pos: GridPos, cell: CellType) {
         if let Some(i) = self.index(pos) {
             self.cells[i] = cell;
+            self.revision = next_revision();
         }
     }
 
@@ -74,6 +87,10 @@ impl WarehouseGrid {
         self.height
     }
 
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     #[inline]
     pub fn grid_to_world(&self, pos: GridPos) -> (f32, f32) {
         (
@@ -1,4 +1,5 @@
-use super::{Direction, GridPos};
+use super::grid::next_revision;
+use super::{Direction, GridPos, WarehouseGrid, WarehouseZones};
 use crate::constants::{GRID_HEIGHT, GRID_WIDTH, SPAWN_ZONE_WIDTH};
 use bevy::prelude::*;
 
@@ -9,10 +10,68 @@ pub enum ZoneType {
     Storage,
 }
 
+/// Ensemble de directions de sortie autorisées depuis une cellule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DirectionMask(u8);
+
+impl DirectionMask {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(0b1111);
+
+    #[inline]
+    const fn bit(dir: Direction) -> u8 {
+        match dir {
+            Direction::North => 0b0001,
+            Direction::East => 0b0010,
+            Direction::South => 0b0100,
+            Direction::West => 0b1000,
+            Direction::None => 0,
+        }
+    }
+
+    #[inline]
+    pub const fn from_direction(dir: Direction) -> Self {
+        Self(Self::bit(dir))
+    }
+
+    #[inline]
+    pub const fn with(self, dir: Direction) -> Self {
+        Self(self.0 | Self::bit(dir))
+    }
+
+    #[inline]
+    pub const fn contains(&self, dir: Direction) -> bool {
+        let bit = Self::bit(dir);
+        bit != 0 && self.0 & bit == bit
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Direction> {
+        Direction::CARDINALS.into_iter().filter(move |&d| self.contains(d))
+    }
+}
+
+/// Façon dont les sens de circulation sont appliqués
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HighwayMode {
+    /// Aucune contrainte, les masques sont ignorés
+    Open,
+    /// Les mouvements à contre-sens sont interdits
+    #[default]
+    Strict,
+    /// Les mouvements à contre-sens restent possibles mais coûtent plus cher :
+    /// `against_flow_cost` s'ajoute au pas
+    Soft { against_flow_cost: f32 },
+}
+
 #[derive(Resource)]
 pub struct HighwayGraph {
     width: u32,
     height: u32,
+    zones: Vec<ZoneType>,
+    masks: Vec<DirectionMask>,
+    mode: HighwayMode,
+    /// Change à chaque modification des sens ou du mode, comme pour la grille
+    revision: u64,
 }
 
 impl Default for HighwayGraph {
@@ -22,44 +81,214 @@ impl Default for HighwayGraph {
 }
 
 impl HighwayGraph {
+    /// Graphe sans sens de circulation
     pub fn new(width: u32, height: u32) -> Self {
-        Self { width, height }
+        let size = (width * height) as usize;
+        let zones = (0..size)
+            .map(|i| {
+                if (i as u32 % width) < SPAWN_ZONE_WIDTH {
+                    ZoneType::FreeZone
+                } else {
+                    ZoneType::Highway
+                }
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            zones,
+            masks: vec![DirectionMask::ALL; size],
+            mode: HighwayMode::Open,
+            revision: next_revision(),
+        }
+    }
+
+    /// Couloirs à sens unique alternés : les rangées paires vont vers l'est, les impaires
+    /// vers l'ouest ; les colonnes paires vers le nord, les impaires vers le sud.
+    /// Spawn, cargo, chargeurs et accès stockage restent libres dans toutes les directions.
+    pub fn alternating(grid: &WarehouseGrid, zones: &WarehouseZones, mode: HighwayMode) -> Self {
+        let mut graph = Self::new(grid.width(), grid.height());
+        graph.mode = mode;
+
+        for y in 0..grid.height() as i32 {
+            for x in 0..grid.width() as i32 {
+                let pos = GridPos::new(x, y);
+                let i = graph.index(pos).unwrap();
+                let horizontal = if y % 2 == 0 { Direction::East } else { Direction::West };
+                let vertical = if x % 2 == 0 { Direction::North } else { Direction::South };
+                graph.zones[i] = ZoneType::Highway;
+                graph.masks[i] = DirectionMask::from_direction(horizontal).with(vertical);
+            }
+        }
+
+        let free_cells = zones
+            .spawn_points
+            .iter()
+            .chain(&zones.cargo_cells)
+            .chain(&zones.charger_cells);
+        for &pos in free_cells {
+            graph.set_cell(pos, ZoneType::FreeZone, DirectionMask::ALL);
+        }
+        for &pos in &zones.storage_cells {
+            graph.set_cell(pos, ZoneType::Storage, DirectionMask::ALL);
+        }
+
+        // Une cellule dont toutes les sorties autorisées sont bloquées deviendrait un cul-de-sac
+        for y in 0..grid.height() as i32 {
+            for x in 0..grid.width() as i32 {
+                let pos = GridPos::new(x, y);
+                let i = graph.index(pos).unwrap();
+                let has_exit = graph.masks[i]
+                    .iter()
+                    .any(|dir| grid.is_passable(pos.neighbor(dir)));
+                if !has_exit {
+                    graph.masks[i] = DirectionMask::ALL;
+                }
+            }
+        }
+
+        // En mode strict, tout doit rester atteignable depuis tout le reste : les cellules
+        // hors de la composante fortement connexe du premier spawn repassent à double sens,
+        // avec leurs voisines pour qu'on puisse aussi y entrer, jusqu'à stabilité
+        let first_cell = (0..grid.height() as i32)
+            .flat_map(|y| (0..grid.width() as i32).map(move |x| GridPos::new(x, y)))
+            .find(|&pos| grid.is_passable(pos));
+        if let Some(root) = zones.spawn_points.first().copied().or(first_cell) {
+            loop {
+                let forward = graph.reachable(grid, root, false);
+                let backward = graph.reachable(grid, root, true);
+                let mut changed = false;
+                for i in 0..graph.masks.len() {
+                    let pos = GridPos::new((i as u32 % graph.width) as i32, (i as u32 / graph.width) as i32);
+                    if !grid.is_passable(pos) || (forward[i] && backward[i]) {
+                        continue;
+                    }
+                    let around = Direction::CARDINALS.iter().map(|&dir| pos.neighbor(dir));
+                    for cell in std::iter::once(pos).chain(around) {
+                        if let Some(j) = graph.index(cell).filter(|_| grid.is_passable(cell)) {
+                            changed |= graph.masks[j] != DirectionMask::ALL;
+                            graph.masks[j] = DirectionMask::ALL;
+                        }
+                    }
+                }
+                if !changed {
+                    break;
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Cellules passables atteignables depuis `root` en suivant les sens de circulation,
+    /// ou celles depuis lesquelles on atteint `root` si `reverse`
+    fn reachable(&self, grid: &WarehouseGrid, root: GridPos, reverse: bool) -> Vec<bool> {
+        let mut seen = vec![false; self.masks.len()];
+        let Some(start) = self.index(root) else {
+            return seen;
+        };
+        seen[start] = true;
+        let mut queue = std::collections::VecDeque::from([root]);
+        while let Some(pos) = queue.pop_front() {
+            for &dir in &Direction::CARDINALS {
+                let next = pos.neighbor(dir);
+                let Some(j) = self.index(next).filter(|_| grid.is_passable(next)) else {
+                    continue;
+                };
+                // En arrière, on remonte les mouvements qui entrent dans `pos`
+                let legal = if reverse {
+                    self.masks[j].contains(dir.opposite())
+                } else {
+                    self.allowed_directions(pos).contains(dir)
+                };
+                if legal && !seen[j] {
+                    seen[j] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen
     }
 
     #[inline]
-    pub fn zone_type(&self, pos: GridPos) -> ZoneType {
-        let x = pos.x as u32;
+    fn index(&self, pos: GridPos) -> Option<usize> {
+        self.in_bounds(pos)
+            .then(|| (pos.y as u32 * self.width + pos.x as u32) as usize)
+    }
 
-        if x < SPAWN_ZONE_WIDTH {
-            return ZoneType::FreeZone;
+    fn set_cell(&mut self, pos: GridPos, zone: ZoneType, mask: DirectionMask) {
+        if let Some(i) = self.index(pos) {
+            self.zones[i] = zone;
+            self.masks[i] = mask;
+            self.revision = next_revision();
         }
+    }
+
+    pub fn mode(&self) -> HighwayMode {
+        self.mode
+    }
 
-        ZoneType::Highway
+    pub fn set_mode(&mut self, mode: HighwayMode) {
+        self.mode = mode;
+        self.revision = next_revision();
     }
 
-    /// Toutes directions autorisées pour l'instant (on ajoutera les contraintes plus tard)
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    #[inline]
+    pub fn zone_type(&self, pos: GridPos) -> ZoneType {
+        self.index(pos)
+            .map_or(ZoneType::FreeZone, |i| self.zones[i])
+    }
+
+    /// Directions de sortie dans le sens de circulation
     #[inline]
-    pub fn allowed_directions(&self, _pos: GridPos) -> &'static [Direction] {
-        &[
-            Direction::North,
-            Direction::South,
-            Direction::East,
-            Direction::West,
-        ]
+    pub fn allowed_directions(&self, pos: GridPos) -> DirectionMask {
+        self.index(pos).map_or(DirectionMask::NONE, |i| self.masks[i])
+    }
+
+    /// Le mouvement suit-il le sens de circulation de la cellule de départ ?
+    #[inline]
+    pub fn is_with_flow(&self, from: GridPos, to: GridPos) -> bool {
+        let dir = match (to.x - from.x, to.y - from.y) {
+            (0, 1) => Direction::North,
+            (0, -1) => Direction::South,
+            (1, 0) => Direction::East,
+            (-1, 0) => Direction::West,
+            _ => return false,
+        };
+        self.allowed_directions(from).contains(dir)
     }
 
     #[inline]
     pub fn is_move_legal(&self, from: GridPos, to: GridPos) -> bool {
         let dx = to.x - from.x;
         let dy = to.y - from.y;
-        dx.abs() + dy.abs() == 1 && self.in_bounds(to)
+        if dx.abs() + dy.abs() != 1 || !self.in_bounds(to) {
+            return false;
+        }
+        !matches!(self.mode, HighwayMode::Strict) || self.is_with_flow(from, to)
+    }
+
+    /// Coût d'un déplacement légal : 1, plus la pénalité de contre-sens en mode `Soft`
+    #[inline]
+    pub fn move_cost(&self, from: GridPos, to: GridPos) -> f32 {
+        match self.mode {
+            HighwayMode::Soft { against_flow_cost } if !self.is_with_flow(from, to) => {
+                1.0 + against_flow_cost
+            }
+            _ => 1.0,
+        }
     }
 
     pub fn legal_neighbors(&self, pos: GridPos) -> impl Iterator<Item = GridPos> + '_ {
         Direction::CARDINALS
             .iter()
             .map(move |&dir| pos.neighbor(dir))
-            .filter(|&n| self.in_bounds(n))
+            .filter(move |&n| self.is_move_legal(pos, n))
     }
 
     #[inline]
@@ -67,3 +296,75 @@ impl HighwayGraph {
         pos.x >= 0 && pos.y >= 0 && (pos.x as u32) < self.width && (pos.y as u32) < self.height
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{PROCEDURAL_MIN_HEIGHT, PROCEDURAL_MIN_WIDTH};
+    use crate::core::WarehouseLayout;
+
+    /// Cellules passables atteignables depuis `from` par des mouvements légaux
+    fn reachable_count(grid: &WarehouseGrid, graph: &HighwayGraph, from: GridPos) -> usize {
+        let mut seen = rustc_hash::FxHashSet::from_iter([from]);
+        let mut queue = vec![from];
+        while let Some(pos) = queue.pop() {
+            for next in graph.legal_neighbors(pos) {
+                if grid.is_passable(next) && seen.insert(next) {
+                    queue.push(next);
+                }
+            }
+        }
+        seen.len()
+    }
+
+    #[test]
+    fn strict_highways_keep_every_cell_reachable() {
+        let layouts = [
+            WarehouseLayout::procedural(GRID_WIDTH, GRID_HEIGHT),
+            WarehouseLayout::procedural(PROCEDURAL_MIN_WIDTH, PROCEDURAL_MIN_HEIGHT),
+            WarehouseLayout::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/layouts/small.ron")).unwrap(),
+            WarehouseLayout::from_ascii("S..A...C").unwrap(),
+            WarehouseLayout::from_ascii(
+                "
+                S.......
+                .RRR.RR.
+                ..A.....
+                C.RR.RRE
+                ",
+            )
+            .unwrap(),
+        ];
+        for layout in layouts {
+            let (grid, zones) = layout.build();
+            let graph = HighwayGraph::alternating(&grid, &zones, HighwayMode::Strict);
+            let cells: Vec<GridPos> = (0..grid.height() as i32)
+                .flat_map(|y| (0..grid.width() as i32).map(move |x| GridPos::new(x, y)))
+                .filter(|&pos| grid.is_passable(pos))
+                .collect();
+            for &from in &cells {
+                assert_eq!(
+                    reachable_count(&grid, &graph, from),
+                    cells.len(),
+                    "{}x{}: cells unreachable from {from:?}",
+                    grid.width(),
+                    grid.height()
+                );
+            }
+        }
+    }
+
+    /// En mode souple, le coût à contre-sens est le pas plus la pénalité
+    #[test]
+    fn against_flow_cost_is_added_to_the_step() {
+        let (grid, zones) = WarehouseLayout::procedural(PROCEDURAL_MIN_WIDTH, PROCEDURAL_MIN_HEIGHT).build();
+        let mut graph = HighwayGraph::alternating(&grid, &zones, HighwayMode::Soft { against_flow_cost: 2.5 });
+        // Rangée paire, loin des zones libres : vers l'est avec le flux, vers l'ouest à contre-sens
+        let (west, east) = (GridPos::new(10, 0), GridPos::new(11, 0));
+        assert!(graph.is_with_flow(west, east) && !graph.is_with_flow(east, west));
+        assert_eq!(graph.move_cost(west, east), 1.0);
+        assert_eq!(graph.move_cost(east, west), 3.5);
+        graph.set_mode(HighwayMode::Strict);
+        assert_eq!(graph.move_cost(east, west), 1.0);
+        assert!(!graph.is_move_legal(east, west));
+    }
+}
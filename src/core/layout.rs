@@ -0,0 +1,526 @@
+use std::fmt;
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::zones::Rack;
+use super::{CellType, GridPos, WarehouseGrid, WarehouseZones};
+use crate::constants::{GRID_HEIGHT, GRID_WIDTH};
+
+/// Version courante du format de fichier
+pub const LAYOUT_VERSION: u32 = 1;
+
+/// Cellule dont le type diffère du sol par défaut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CellOverride {
+    pub pos: GridPos,
+    pub kind: CellType,
+}
+
+/// Description complète d'un entrepôt, sérialisée en RON.
+///
+/// ```ron
+/// (
+///     version: 1,
+///     width: 20,
+///     height: 10,
+///     cells: [(pos: (x: 0, y: 0), kind: Blocked)],
+///     racks: [(start: (x: 8, y: 2), end: (x: 9, y: 7))],
+///     spawn_points: [(x: 1, y: 2)],
+///     storage_cells: [(x: 7, y: 2)],
+///     cargo_cells: [(x: 18, y: 2)],
+///     charger_cells: [(x: 1, y: 8)],
+/// )
+/// ```
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WarehouseLayout {
+    pub version: u32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub cells: Vec<CellOverride>,
+    #[serde(default)]
+    pub racks: Vec<Rack>,
+    pub spawn_points: Vec<GridPos>,
+    pub storage_cells: Vec<GridPos>,
+    pub cargo_cells: Vec<GridPos>,
+    #[serde(default)]
+    pub charger_cells: Vec<GridPos>,
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(std::io::Error),
+    Parse { line: usize, column: usize, message: String },
+    UnsupportedVersion(u32),
+    /// Champ incohérent ; `line` est celle où il apparaît, quand le layout vient d'un texte
+    Invalid { field: String, line: Option<usize>, message: String },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "cannot read layout file: {e}"),
+            Self::Parse { line, column, message } => {
+                write!(f, "line {line}, column {column}: {message}")
+            }
+            Self::UnsupportedVersion(v) => {
+                write!(f, "unsupported layout version {v} (expected {LAYOUT_VERSION})")
+            }
+            Self::Invalid { field, line: Some(line), message } => {
+                write!(f, "line {line}, field `{field}`: {message}")
+            }
+            Self::Invalid { field, line: None, message } => write!(f, "field `{field}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LayoutError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for LayoutError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        Self::Parse {
+            line: e.position.line,
+            column: e.position.col,
+            message: e.code.to_string(),
+        }
+    }
+}
+
+/// Lecture de la seule version, avant de parser le reste du format
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u32,
+}
+
+impl Default for WarehouseLayout {
+    fn default() -> Self {
+        Self::procedural(GRID_WIDTH, GRID_HEIGHT)
+    }
+}
+
+impl WarehouseLayout {
+    /// Layout procédural historique, aux dimensions données
+    pub fn procedural(width: u32, height: u32) -> Self {
+        let zones = WarehouseZones::procedural(width, height);
+        Self {
+            version: LAYOUT_VERSION,
+            width,
+            height,
+            cells: Vec::new(),
+            racks: zones.racks.clone(),
+            spawn_points: zones.spawn_points.clone(),
+            storage_cells: zones.storage_cells.clone(),
+            cargo_cells: zones.cargo_cells.clone(),
+            charger_cells: zones.charger_cells.clone(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_ron(&text)
+    }
+
+    /// Charge le fichier s'il existe, sinon retombe sur le layout procédural
+    pub fn load_or_procedural(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        if !path.exists() {
+            info!("No layout file at {}, using procedural layout", path.display());
+            return Self::default();
+        }
+
+        match Self::load(path) {
+            Ok(layout) => {
+                info!("Loaded layout {} ({}x{})", path.display(), layout.width, layout.height);
+                layout
+            }
+            Err(e) => {
+                error!("Invalid layout {}: {e}. Using procedural layout", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, LayoutError> {
+        let probe: VersionProbe = ron::from_str(text)?;
+        if probe.version != LAYOUT_VERSION {
+            return Err(LayoutError::UnsupportedVersion(probe.version));
+        }
+
+        let layout: Self = ron::from_str(text)?;
+        layout.validate().map_err(|e| match e {
+            LayoutError::Invalid { field, message, .. } => {
+                LayoutError::Invalid { line: field_line(text, &field), field, message }
+            }
+            e => e,
+        })?;
+        Ok(layout)
+    }
+
+    pub fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("layout is always serializable")
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LayoutError> {
+        std::fs::write(path, self.to_ron())?;
+        Ok(())
+    }
+
+    /// Vérifie la cohérence du layout (bornes, cellules accessibles, listes non vides)
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(invalid("width", "grid dimensions must be non-zero"));
+        }
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            self.check_bounds(&format!("cells[{i}].pos"), cell.pos)?;
+        }
+
+        for (i, rack) in self.racks.iter().enumerate() {
+            self.check_bounds(&format!("racks[{i}].start"), rack.start)?;
+            self.check_bounds(&format!("racks[{i}].end"), rack.end)?;
+            if rack.start.x > rack.end.x || rack.start.y > rack.end.y {
+                return Err(invalid(&format!("racks[{i}]"), "start must be <= end"));
+            }
+        }
+
+        let grid = self.build_grid();
+        for (name, cells, required) in [
+            ("spawn_points", &self.spawn_points, true),
+            ("storage_cells", &self.storage_cells, true),
+            ("cargo_cells", &self.cargo_cells, true),
+            ("charger_cells", &self.charger_cells, false),
+        ] {
+            if required && cells.is_empty() {
+                return Err(invalid(name, "at least one cell is required"));
+            }
+            for (i, &pos) in cells.iter().enumerate() {
+                let field = format!("{name}[{i}]");
+                self.check_bounds(&field, pos)?;
+                if !grid.is_passable(pos) {
+                    return Err(invalid(
+                        &field,
+                        &format!("cell ({}, {}) is not passable", pos.x, pos.y),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_bounds(&self, field: &str, pos: GridPos) -> Result<(), LayoutError> {
+        let inside = pos.x >= 0
+            && pos.y >= 0
+            && (pos.x as u32) < self.width
+            && (pos.y as u32) < self.height;
+        if inside {
+            Ok(())
+        } else {
+            Err(invalid(
+                field,
+                &format!(
+                    "({}, {}) is outside the {}x{} grid",
+                    pos.x, pos.y, self.width, self.height
+                ),
+            ))
+        }
+    }
+
+    fn build_grid(&self) -> WarehouseGrid {
+        let mut grid = WarehouseGrid::new(self.width, self.height);
+        for cell in &self.cells {
+            grid.set(cell.pos, cell.kind);
+        }
+        grid.apply_racks(&self.racks);
+        grid
+    }
+
+    pub fn build(&self) -> (WarehouseGrid, WarehouseZones) {
+        let zones = WarehouseZones::new(
+            self.spawn_points.clone(),
+            self.storage_cells.clone(),
+            self.cargo_cells.clone(),
+            self.racks.clone(),
+        )
+        .with_chargers(self.charger_cells.clone());
+        (self.build_grid(), zones)
+    }
+}
+
+fn invalid(field: &str, message: &str) -> LayoutError {
+    LayoutError::Invalid {
+        field: field.to_string(),
+        line: None,
+        message: message.to_string(),
+    }
+}
+
+/// Lexème RON réduit à ce qu'il faut pour retrouver un champ : identifiants et nombres,
+/// ponctuation, le reste (chaînes) ignoré
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Punct(char),
+}
+
+/// Lexèmes du texte avec leur ligne et leur profondeur d'imbrication ; commentaires et
+/// chaînes sont sautés
+fn tokens(text: &str) -> Vec<(Token<'_>, usize, usize)> {
+    let bytes = text.as_bytes();
+    let (mut i, mut line, mut depth) = (0, 1, 0usize);
+    let mut tokens = Vec::new();
+    while i < bytes.len() {
+        // Caractère entier : `i` reste sur une frontière UTF-8
+        let Some(c) = text[i..].chars().next() else {
+            break;
+        };
+        match c {
+            '\n' => line += 1,
+            _ if text[i..].starts_with("//") => {
+                i += text[i..].find('\n').unwrap_or(text.len() - i);
+                continue;
+            }
+            _ if text[i..].starts_with("/*") => {
+                let end = text[i + 2..].find("*/").map_or(text.len(), |end| i + end + 4);
+                line += text[i..end].matches('\n').count();
+                i = end;
+                continue;
+            }
+            '"' => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != b'"' {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                line += text[i..j.min(text.len())].matches('\n').count();
+                i = j + 1;
+                continue;
+            }
+            '(' | '[' | '{' => {
+                tokens.push((Token::Punct(c), line, depth));
+                depth += 1;
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                tokens.push((Token::Punct(c), line, depth));
+            }
+            _ if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let len = text[i..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.'))
+                    .unwrap_or(text.len() - i);
+                tokens.push((Token::Word(&text[i..i + len]), line, depth));
+                i += len;
+                continue;
+            }
+            _ if c.is_whitespace() => {}
+            _ => tokens.push((Token::Punct(c), line, depth)),
+        }
+        i += c.len_utf8();
+    }
+    tokens
+}
+
+/// Ligne d'un champ signalé par `validate` (`width`, `storage_cells[2]`, `racks[0].end`)
+/// dans le texte RON dont vient le layout
+fn field_line(text: &str, field: &str) -> Option<usize> {
+    let (head, sub) = match field.split_once('.') {
+        Some((head, sub)) => (head, Some(sub)),
+        None => (field, None),
+    };
+    let (name, index) = match head.split_once('[') {
+        Some((name, index)) => (name, Some(index.strip_suffix(']')?.parse::<usize>().ok()?)),
+        None => (head, None),
+    };
+    let tokens = tokens(text);
+
+    // Première clé `key:` à la profondeur donnée, à partir de `from`
+    let key = |from: usize, key: &str, depth: usize| {
+        (from..tokens.len().saturating_sub(1)).find(|&k| {
+            tokens[k].0 == Token::Word(key) && tokens[k].2 == depth && tokens[k + 1].0 == Token::Punct(':')
+        })
+    };
+    let k = key(0, name, 1)?;
+    let Some(index) = index else {
+        return Some(tokens[k].1);
+    };
+
+    // `index`-ième élément de la liste qui suit
+    if tokens.get(k + 2)?.0 != Token::Punct('[') {
+        return None;
+    }
+    let depth = tokens[k + 2].2 + 1;
+    let mut element = 0;
+    let mut start = None;
+    for (j, &(token, _, d)) in tokens.iter().enumerate().skip(k + 3) {
+        match token {
+            Token::Punct(']') if d + 1 == depth => break,
+            Token::Punct(',') if d == depth => element += 1,
+            _ if element == index && d == depth => {
+                start = Some(j);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let start = start?;
+    let line = tokens[start].1;
+    let sub_line = sub.and_then(|sub| {
+        let end = tokens[start..]
+            .iter()
+            .position(|&(token, _, d)| d < depth || (d == depth && token == Token::Punct(',')))
+            .map_or(tokens.len(), |end| start + end);
+        key(start, sub, depth + 1).filter(|&s| s < end).map(|s| tokens[s].1)
+    });
+    Some(sub_line.unwrap_or(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: &str = "// Entrepôt de test
+(
+    version: 1,
+    width: 12,
+    height: 6,
+    cells: [(pos: (x: 6, y: 0), kind: Blocked)],
+    racks: [
+        (start: (x: 4, y: 1), end: (x: 5, y: 4)),
+        (start: (x: 8, y: 1), end: (x: 9, y: 4)),
+    ],
+    spawn_points: [(x: 0, y: 1), (x: 0, y: 4)],
+    storage_cells: [
+        (x: 3, y: 2),
+        (x: 7, y: 2),
+    ],
+    cargo_cells: [(x: 11, y: 1)],
+)
+";
+
+    fn error(text: &str) -> LayoutError {
+        match WarehouseLayout::from_ron(text) {
+            Err(e) => e,
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    fn invalid_field(text: &str) -> (String, Option<usize>) {
+        match error(text) {
+            LayoutError::Invalid { field, line, .. } => (field, line),
+            e => panic!("expected an invalid field, got {e:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_file_round_trips() {
+        let layout = WarehouseLayout::from_ron(LAYOUT).unwrap();
+        assert_eq!((layout.width, layout.height), (12, 6));
+        assert_eq!(layout.racks.len(), 2);
+        assert_eq!(layout.storage_cells, [GridPos::new(3, 2), GridPos::new(7, 2)]);
+        assert!(layout.charger_cells.is_empty());
+        assert_eq!(WarehouseLayout::from_ron(&layout.to_ron()).unwrap(), layout);
+
+        let demo = WarehouseLayout::load("assets/layouts/small.ron").unwrap();
+        assert_eq!((demo.width, demo.height), (24, 12));
+    }
+
+    #[test]
+    fn syntax_errors_are_located() {
+        // Virgule manquante en fin de ligne 5, signalée au champ suivant
+        let text = LAYOUT.replace("height: 6,", "height: 6");
+        match error(&text) {
+            LayoutError::Parse { line, .. } => assert_eq!(line, 6),
+            e => panic!("expected a parse error, got {e:?}"),
+        }
+
+        // Champ inconnu, nommé dans le message
+        let text = LAYOUT.replace("height: 6,", "heigth: 6,");
+        match error(&text) {
+            LayoutError::Parse { line, message, .. } => {
+                assert_eq!(line, 5);
+                assert!(message.contains("heigth"), "{message}");
+            }
+            e => panic!("expected a parse error, got {e:?}"),
+        }
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let text = LAYOUT.replace("version: 1,", "version: 2,");
+        assert!(matches!(error(&text), LayoutError::UnsupportedVersion(2)));
+        assert_eq!(
+            error(&text).to_string(),
+            format!("unsupported layout version 2 (expected {LAYOUT_VERSION})")
+        );
+    }
+
+    #[test]
+    fn invalid_fields_point_at_their_line() {
+        let text = LAYOUT.replace("width: 12,", "width: 0,");
+        assert_eq!(invalid_field(&text), ("width".to_string(), Some(4)));
+
+        let text = LAYOUT.replace("(x: 7, y: 2)", "(x: 7, y: 9)");
+        assert_eq!(invalid_field(&text), ("storage_cells[1]".to_string(), Some(14)));
+        assert_eq!(
+            error(&text).to_string(),
+            "line 14, field `storage_cells[1]`: (7, 9) is outside the 12x6 grid"
+        );
+
+        // Cellule sur un rack, deuxième élément d'une liste sur une seule ligne
+        let text = LAYOUT.replace("(x: 0, y: 4)]", "(x: 4, y: 2)]");
+        assert_eq!(invalid_field(&text), ("spawn_points[1]".to_string(), Some(11)));
+
+        let text = LAYOUT.replace("end: (x: 9, y: 4)", "end: (x: 9, y: 7)");
+        assert_eq!(invalid_field(&text), ("racks[1].end".to_string(), Some(9)));
+
+        let text = LAYOUT.replace("(pos: (x: 6, y: 0)", "(pos: (x: 16, y: 0)");
+        assert_eq!(invalid_field(&text), ("cells[0].pos".to_string(), Some(6)));
+
+        let text = LAYOUT.replace("cargo_cells: [(x: 11, y: 1)]", "cargo_cells: []");
+        assert_eq!(invalid_field(&text), ("cargo_cells".to_string(), Some(16)));
+    }
+
+    /// Les caractères non ASCII hors chaînes et commentaires ne coupent pas la recherche du
+    /// champ au milieu d'un caractère
+    #[test]
+    fn non_ascii_identifiers_do_not_break_field_lookup() {
+        let text = "(\n    entrepôt_€𝄞: 1,\n    width: 0,\n)";
+        assert_eq!(field_line(text, "width"), Some(3));
+        assert_eq!(field_line(text, "height"), None);
+
+        let text = LAYOUT.replace(
+            "width: 12,",
+            "width: 0, // largeur nulle, déjà signalée\n    nœud€: 1,",
+        );
+        assert!(WarehouseLayout::from_ron(&text).is_err());
+    }
+
+    #[test]
+    fn unusable_files_fall_back_to_the_procedural_layout() {
+        assert_eq!(WarehouseLayout::load_or_procedural("/nonexistent/layout.ron"), WarehouseLayout::default());
+
+        let path = std::env::temp_dir().join(format!("warehouse_sim_layout_{}.ron", std::process::id()));
+        std::fs::write(&path, LAYOUT.replace("width: 12,", "width: 0,")).unwrap();
+        assert_eq!(WarehouseLayout::load_or_procedural(&path), WarehouseLayout::default());
+        std::fs::write(&path, LAYOUT).unwrap();
+        assert_eq!(WarehouseLayout::load_or_procedural(&path), WarehouseLayout::from_ron(LAYOUT).unwrap());
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -1,11 +1,19 @@
+pub mod ascii;
+pub mod distance;
 pub mod grid;
 pub mod highways;
+pub mod layout;
+pub mod movingai;
+pub mod rng;
 pub mod spacetime;
 pub mod types;
 pub mod zones;
 
+pub use distance::DistanceField;
 pub use grid::{CellType, WarehouseGrid};
-pub use highways::HighwayGraph;
-pub use spacetime::SpaceTimeTable;
+pub use highways::{DirectionMask, HighwayGraph, HighwayMode, ZoneType};
+pub use layout::{LayoutError, WarehouseLayout};
+pub use rng::SimRng;
+pub use spacetime::{EdgeKey, ReservationLog, SpaceTimeTable};
 pub use types::{Direction, GridPos};
 pub use zones::WarehouseZones;
@@ -0,0 +1,251 @@
+use std::path::Path;
+
+use super::{CellType, GridPos, LayoutError, WarehouseGrid};
+
+/// Paire départ/arrivée d'une ligne `.scen`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScenarioAgent {
+    pub start: GridPos,
+    pub goal: GridPos,
+    pub optimal_length: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MovingAiScenario {
+    pub map_name: String,
+    pub agents: Vec<ScenarioAgent>,
+}
+
+pub fn load_map(path: impl AsRef<Path>) -> Result<WarehouseGrid, LayoutError> {
+    parse_map(&std::fs::read_to_string(path)?)
+}
+
+pub fn load_scenario(path: impl AsRef<Path>) -> Result<MovingAiScenario, LayoutError> {
+    parse_scenario(&std::fs::read_to_string(path)?)
+}
+
+/// Parse un fichier `.map` MovingAI (<https://movingai.com/benchmarks/mapf.html>).
+/// `.`/`G`/`S` sont du sol, `@`/`O`/`T`/`W` sont bloqués ; la ligne `i` de la carte donne `y = i`.
+pub fn parse_map(text: &str) -> Result<WarehouseGrid, LayoutError> {
+    let mut lines = text.lines().enumerate();
+    let mut width = None;
+    let mut height = None;
+
+    // En-tête : type, height, width puis "map"
+    for (i, line) in lines.by_ref() {
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("type") | None => {}
+            Some("height") => height = Some(parse_header_value(parts.next(), i, "height")?),
+            Some("width") => width = Some(parse_header_value(parts.next(), i, "width")?),
+            Some("map") => break,
+            Some(other) => {
+                return Err(parse_error(i, 1, format!("unexpected header `{other}`")));
+            }
+        }
+    }
+
+    let (Some(width), Some(height)) = (width, height) else {
+        return Err(parse_error(0, 1, "missing `width` or `height` header".into()));
+    };
+
+    let mut grid = WarehouseGrid::new(width, height);
+    let mut rows = 0;
+
+    for (i, line) in lines {
+        if rows == height {
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Err(parse_error(i, 1, format!("more than {height} map rows")));
+        }
+
+        let row: Vec<char> = line.trim_end().chars().collect();
+        if row.len() != width as usize {
+            return Err(parse_error(
+                i,
+                1,
+                format!("row has {} cells, expected {width}", row.len()),
+            ));
+        }
+
+        for (x, c) in row.into_iter().enumerate() {
+            let cell = match c {
+                '.' | 'G' | 'S' => CellType::Floor,
+                '@' | 'O' | 'T' | 'W' => CellType::Blocked,
+                other => {
+                    return Err(parse_error(i, x + 1, format!("unknown terrain `{other}`")));
+                }
+            };
+            grid.set(GridPos::new(x as i32, rows as i32), cell);
+        }
+        rows += 1;
+    }
+
+    if rows != height {
+        return Err(parse_error(0, 1, format!("found {rows} map rows, expected {height}")));
+    }
+
+    Ok(grid)
+}
+
+/// Parse un fichier `.scen` (version 1) :
+/// `bucket map width height start_x start_y goal_x goal_y optimal_length`
+pub fn parse_scenario(text: &str) -> Result<MovingAiScenario, LayoutError> {
+    let mut scenario = MovingAiScenario::default();
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with("version") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 9 {
+            return Err(parse_error(
+                i,
+                1,
+                format!("expected 9 tab-separated fields, found {}", fields.len()),
+            ));
+        }
+
+        let int = |idx: usize, name: &str| -> Result<i32, LayoutError> {
+            fields[idx].trim().parse().map_err(|_| {
+                parse_error(i, field_column(&fields, idx), format!("invalid {name} `{}`", fields[idx]))
+            })
+        };
+
+        let start = GridPos::new(int(4, "start_x")?, int(5, "start_y")?);
+        let goal = GridPos::new(int(6, "goal_x")?, int(7, "goal_y")?);
+        let optimal_length = fields[8].trim().parse().map_err(|_| {
+            parse_error(i, field_column(&fields, 8), format!("invalid optimal_length `{}`", fields[8]))
+        })?;
+
+        if scenario.map_name.is_empty() {
+            scenario.map_name = fields[1].to_string();
+        }
+        scenario.agents.push(ScenarioAgent { start, goal, optimal_length });
+    }
+
+    Ok(scenario)
+}
+
+impl MovingAiScenario {
+    /// Vérifie que départs et arrivées sont sur des cellules libres de la grille
+    pub fn validate(&self, grid: &WarehouseGrid) -> Result<(), LayoutError> {
+        for (i, agent) in self.agents.iter().enumerate() {
+            for (name, pos) in [("start", agent.start), ("goal", agent.goal)] {
+                if !grid.is_passable(pos) {
+                    return Err(LayoutError::Invalid {
+                        field: format!("agents[{i}].{name}"),
+                        line: None,
+                        message: format!("cell ({}, {}) is not passable", pos.x, pos.y),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_header_value(value: Option<&str>, line: usize, name: &str) -> Result<u32, LayoutError> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| parse_error(line, 1, format!("invalid `{name}` header")))
+}
+
+fn field_column(fields: &[&str], idx: usize) -> usize {
+    fields[..idx].iter().map(|f| f.len() + 1).sum::<usize>() + 1
+}
+
+fn parse_error(line_index: usize, column: usize, message: String) -> LayoutError {
+    LayoutError::Parse {
+        line: line_index + 1,
+        column,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "type octile\nheight 3\nwidth 4\nmap\n....\n.@T.\nG..S\n";
+
+    fn parse_error<T>(result: Result<T, LayoutError>) -> (usize, String) {
+        match result {
+            Err(LayoutError::Parse { line, message, .. }) => (line, message),
+            Err(e) => panic!("expected a parse error, got {e:?}"),
+            Ok(_) => panic!("expected a parse error"),
+        }
+    }
+
+    fn scen_row(start: (i32, i32), goal: (i32, i32)) -> String {
+        format!("0\tmap.map\t4\t3\t{}\t{}\t{}\t{}\t3.0", start.0, start.1, goal.0, goal.1)
+    }
+
+    #[test]
+    fn terrain_maps_to_cells() {
+        let grid = parse_map(MAP).unwrap();
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid.get(GridPos::new(1, 1)), Some(CellType::Blocked));
+        assert_eq!(grid.get(GridPos::new(2, 1)), Some(CellType::Blocked));
+        for pos in [GridPos::new(0, 0), GridPos::new(3, 1), GridPos::new(0, 2), GridPos::new(3, 2)] {
+            assert_eq!(grid.get(pos), Some(CellType::Floor), "{pos:?}");
+        }
+    }
+
+    #[test]
+    fn bad_headers_and_sizes_are_parse_errors() {
+        assert_eq!(parse_error(parse_map("type octile\nheight three\nwidth 4\nmap\n")).0, 2);
+        assert_eq!(parse_error(parse_map("type octile\ndepth 3\nmap\n")).1, "unexpected header `depth`");
+        assert_eq!(parse_error(parse_map("type octile\nheight 3\nmap\n....\n")).1, "missing `width` or `height` header");
+
+        // Ligne trop courte, ligne en trop, lignes manquantes
+        let (line, message) = parse_error(parse_map("height 2\nwidth 4\nmap\n....\n...\n"));
+        assert_eq!((line, message.as_str()), (5, "row has 3 cells, expected 4"));
+        assert_eq!(parse_error(parse_map("height 1\nwidth 2\nmap\n..\n..\n")).1, "more than 1 map rows");
+        assert_eq!(parse_error(parse_map("height 3\nwidth 2\nmap\n..\n")).1, "found 1 map rows, expected 3");
+        assert_eq!(parse_error(parse_map("height 1\nwidth 2\nmap\n.x\n")).1, "unknown terrain `x`");
+    }
+
+    #[test]
+    fn scenario_rows_are_parsed() {
+        let text = format!("version 1\n{}\n{}\n", scen_row((0, 0), (3, 2)), scen_row((3, 0), (0, 2)));
+        let scenario = parse_scenario(&text).unwrap();
+        assert_eq!(scenario.map_name, "map.map");
+        assert_eq!(
+            scenario.agents,
+            [
+                ScenarioAgent { start: GridPos::new(0, 0), goal: GridPos::new(3, 2), optimal_length: 3.0 },
+                ScenarioAgent { start: GridPos::new(3, 0), goal: GridPos::new(0, 2), optimal_length: 3.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_scenario_rows_are_parse_errors() {
+        let (line, message) = parse_error(parse_scenario("version 1\n0\tmap.map\t4\t3\t0\t0\t3\t2\n"));
+        assert_eq!((line, message.as_str()), (2, "expected 9 tab-separated fields, found 8"));
+
+        let row = scen_row((0, 0), (3, 2)).replace("\t3\t2\t", "\t3\ttwo\t");
+        assert_eq!(parse_error(parse_scenario(&row)).1, "invalid goal_y `two`");
+        let row = scen_row((0, 0), (3, 2)).replace("3.0", "long");
+        assert_eq!(parse_error(parse_scenario(&row)).1, "invalid optimal_length `long`");
+    }
+
+    #[test]
+    fn agents_must_start_and_end_on_free_cells() {
+        let grid = parse_map(MAP).unwrap();
+        let scenario = |start, goal| parse_scenario(&scen_row(start, goal)).unwrap();
+        scenario((0, 0), (3, 2)).validate(&grid).unwrap();
+
+        for (start, goal, field) in [((1, 1), (3, 2), "agents[0].start"), ((0, 0), (2, 1), "agents[0].goal")] {
+            match scenario(start, goal).validate(&grid) {
+                Err(LayoutError::Invalid { field: f, .. }) => assert_eq!(f, field),
+                other => panic!("expected an invalid {field}, got {other:?}"),
+            }
+        }
+        // Hors de la grille
+        assert!(scenario((0, 0), (4, 0)).validate(&grid).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::constants::DEFAULT_SEED;
+
+/// Unique source d'aléa de la simulation : génération des tâches, pannes, départages.
+/// ChaCha8 donne la même suite sur toutes les plateformes pour une graine donnée.
+#[derive(Resource, Deref, DerefMut)]
+pub struct SimRng {
+    seed: u64,
+    #[deref]
+    rng: ChaCha8Rng,
+}
+
+impl Default for SimRng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Position dans la suite, en mots de 32 bits consommés depuis la graine
+    pub fn word_pos(&self) -> u128 {
+        self.rng.get_word_pos()
+    }
+
+    /// Générateur de graine `seed`, repris à la position `word_pos`
+    pub fn resume(seed: u64, word_pos: u128) -> Self {
+        let mut rng = Self::new(seed);
+        rng.rng.set_word_pos(word_pos);
+        rng
+    }
+
+    /// Élément tiré uniformément, `None` si la liste est vide
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> Option<T> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(items[self.rng.random_range(0..items.len())])
+    }
+}
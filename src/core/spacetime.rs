@@ -15,9 +15,41 @@ impl SpaceTimeKey {
     }
 }
 
-#[derive(Resource, Default)]
+/// Déplacement orienté de `from` (au tick `tick`) vers `to` (au tick `tick + 1`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EdgeKey {
+    pub from: GridPos,
+    pub to: GridPos,
+    pub tick: u64,
+}
+
+impl EdgeKey {
+    #[inline]
+    pub const fn new(from: GridPos, to: GridPos, tick: u64) -> Self {
+        Self { from, to, tick }
+    }
+
+    /// Le même déplacement en sens inverse, au même tick
+    #[inline]
+    pub const fn reversed(&self) -> Self {
+        Self::new(self.to, self.from, self.tick)
+    }
+}
+
+/// Réservations ajoutées à une table, dans l'ordre, pour les retirer avec
+/// [`SpaceTimeTable::undo`] sans recopier toute la table
+#[derive(Default)]
+pub struct ReservationLog {
+    cells: Vec<SpaceTimeKey>,
+    edges: Vec<(EdgeKey, Option<Entity>)>,
+}
+
+#[derive(Resource, Default, Clone)]
 pub struct SpaceTimeTable {
     reservations: FxHashMap<SpaceTimeKey, Entity>,
+    edges: FxHashMap<EdgeKey, Entity>,
+    /// Nombre de réservations par cellule, tous ticks confondus
+    cell_counts: FxHashMap<GridPos, u32>,
     current_tick: u64,
 }
 
@@ -29,27 +61,123 @@ impl SpaceTimeTable {
                 return false;
             }
         }
-        self.reservations.insert(key, entity);
+        self.insert(key, entity);
+        true
+    }
+
+    fn insert(&mut self, key: SpaceTimeKey, entity: Entity) {
+        if self.reservations.insert(key, entity).is_none() {
+            *self.cell_counts.entry(key.pos).or_default() += 1;
+        }
+    }
+
+    fn remove(&mut self, key: &SpaceTimeKey) {
+        if self.reservations.remove(key).is_some() {
+            if let Some(count) = self.cell_counts.get_mut(&key.pos) {
+                *count -= 1;
+                if *count == 0 {
+                    self.cell_counts.remove(&key.pos);
+                }
+            }
+        }
+    }
+
+    /// Comme `reserve`, en notant dans `log` ce qui a été ajouté
+    pub fn reserve_logged(&mut self, pos: GridPos, tick: u64, entity: Entity, log: &mut ReservationLog) -> bool {
+        let key = SpaceTimeKey::new(pos, tick);
+        match self.reservations.get(&key) {
+            Some(&existing) => existing == entity,
+            None => {
+                self.insert(key, entity);
+                log.cells.push(key);
+                true
+            }
+        }
+    }
+
+    /// Comme `reserve_edge`, en notant dans `log` l'occupant remplacé
+    pub fn reserve_edge_logged(
+        &mut self,
+        from: GridPos,
+        to: GridPos,
+        tick: u64,
+        entity: Entity,
+        log: &mut ReservationLog,
+    ) -> bool {
+        if !self.is_edge_reservable(from, to, tick, entity) {
+            return false;
+        }
+        let key = EdgeKey::new(from, to, tick);
+        log.edges.push((key, self.edges.insert(key, entity)));
         true
     }
 
+    /// Annule les réservations notées dans `log`, la table revient à son état d'avant
+    pub fn undo(&mut self, log: ReservationLog) {
+        for key in &log.cells {
+            self.remove(key);
+        }
+        for (key, previous) in log.edges.into_iter().rev() {
+            match previous {
+                Some(entity) => self.edges.insert(key, entity),
+                None => self.edges.remove(&key),
+            };
+        }
+    }
+
+    /// Retire les réservations pour lesquelles `keep` renvoie `false`
+    fn retain(&mut self, mut keep: impl FnMut(&SpaceTimeKey, Entity) -> bool) {
+        let counts = &mut self.cell_counts;
+        self.reservations.retain(|key, &mut e| {
+            let kept = keep(key, e);
+            if !kept {
+                if let Some(count) = counts.get_mut(&key.pos) {
+                    *count -= 1;
+                    if *count == 0 {
+                        counts.remove(&key.pos);
+                    }
+                }
+            }
+            kept
+        });
+    }
+
+    /// Réserve les cellules du chemin et les déplacements entre deux waypoints consécutifs
     pub fn reserve_path(&mut self, path: &[(GridPos, u64)], entity: Entity) -> bool {
         // Vérifie d'abord
         for &(pos, tick) in path {
-            let key = SpaceTimeKey::new(pos, tick);
-            if let Some(&occupant) = self.reservations.get(&key) {
-                if occupant != entity {
-                    return false;
-                }
+            if !self.is_free(pos, tick, Some(entity)) {
+                return false;
+            }
+        }
+        for w in path.windows(2) {
+            let ((from, tick), (to, _)) = (w[0], w[1]);
+            if from != to && !self.is_edge_reservable(from, to, tick, entity) {
+                return false;
             }
         }
         // Puis réserve
         for &(pos, tick) in path {
-            self.reservations.insert(SpaceTimeKey::new(pos, tick), entity);
+            self.insert(SpaceTimeKey::new(pos, tick), entity);
+        }
+        for w in path.windows(2) {
+            let ((from, tick), (to, _)) = (w[0], w[1]);
+            if from != to {
+                self.edges.insert(EdgeKey::new(from, to, tick), entity);
+            }
         }
         true
     }
 
+    /// Réserve le déplacement `from -> to` entre `tick` et `tick + 1`
+    pub fn reserve_edge(&mut self, from: GridPos, to: GridPos, tick: u64, entity: Entity) -> bool {
+        if !self.is_edge_reservable(from, to, tick, entity) {
+            return false;
+        }
+        self.edges.insert(EdgeKey::new(from, to, tick), entity);
+        true
+    }
+
     #[inline]
     pub fn is_free(&self, pos: GridPos, tick: u64, exclude: Option<Entity>) -> bool {
         match self.reservations.get(&SpaceTimeKey::new(pos, tick)) {
@@ -58,46 +186,133 @@ impl SpaceTimeTable {
         }
     }
 
+    /// La cellule a-t-elle au moins une réservation, à n'importe quel tick ?
+    #[inline]
+    pub fn has_reservations(&self, pos: GridPos) -> bool {
+        self.cell_counts.contains_key(&pos)
+    }
+
+    /// Entité qui a réservé la cellule à ce tick
+    #[inline]
+    pub fn occupant(&self, pos: GridPos, tick: u64) -> Option<Entity> {
+        self.reservations.get(&SpaceTimeKey::new(pos, tick)).copied()
+    }
+
+    /// Le déplacement `from -> to` au tick `tick` est-il possible sans échange de cellules ?
+    /// Il y a conflit si un autre robot a réservé exactement le déplacement inverse au même tick.
     pub fn is_edge_free(&self, from: GridPos, to: GridPos, tick: u64, exclude: Option<Entity>) -> bool {
-        // Vérifie qu'aucun robot ne fait le mouvement inverse (swap)
-        let key_to_at_tick = SpaceTimeKey::new(to, tick);
-        let key_from_at_next = SpaceTimeKey::new(from, tick + 1);
-
-        let check = |key: SpaceTimeKey| -> bool {
-            match self.reservations.get(&key) {
-                None => true,
-                Some(&e) => exclude.is_some_and(|ex| ex == e),
-            }
-        };
+        match self.edges.get(&EdgeKey::new(from, to, tick).reversed()) {
+            None => true,
+            Some(&e) => exclude.is_some_and(|ex| ex == e),
+        }
+    }
 
-        check(key_to_at_tick) && check(key_from_at_next)
+    /// Le déplacement n'est réservé par personne d'autre, ni dans ce sens ni à l'inverse
+    fn is_edge_reservable(&self, from: GridPos, to: GridPos, tick: u64, entity: Entity) -> bool {
+        self.edge_occupant(from, to, tick).is_none_or(|e| e == entity)
+            && self.is_edge_free(from, to, tick, Some(entity))
+    }
+
+    /// Entité qui a réservé ce déplacement
+    #[inline]
+    pub fn edge_occupant(&self, from: GridPos, to: GridPos, tick: u64) -> Option<Entity> {
+        self.edges.get(&EdgeKey::new(from, to, tick)).copied()
     }
 
     pub fn clear_entity(&mut self, entity: Entity) {
-        self.reservations.retain(|_, &mut e| e != entity);
+        self.retain(|_, e| e != entity);
+        self.edges.retain(|_, &mut e| e != entity);
     }
 
-    /// Efface les réservations d'une entité sauf sa position actuelle
-    pub fn clear_entity_except_pos(&mut self, entity: Entity, current_pos: GridPos, current_tick: u64) {
-        self.reservations.retain(|key, &mut e| {
+    /// Efface les réservations d'une entité sauf sa position actuelle, gardée sur
+    /// `[current_tick, current_tick + hold]`
+    pub fn clear_entity_except_pos(&mut self, entity: Entity, current_pos: GridPos, current_tick: u64, hold: u64) {
+        self.retain(|key, e| {
             if e != entity {
                 return true;
             }
             // Garde les réservations de la position actuelle pour les prochains ticks
-            key.pos == current_pos && key.tick >= current_tick && key.tick < current_tick + 3
+            key.pos == current_pos && key.tick >= current_tick && key.tick <= current_tick + hold
         });
+        self.edges.retain(|_, &mut e| e != entity);
     }
 
     pub fn cleanup(&mut self, current_tick: u64) {
-        self.reservations.retain(|key, _| key.tick >= current_tick.saturating_sub(1));
+        self.retain(|key, _| key.tick >= current_tick.saturating_sub(1));
+        self.edges.retain(|key, _| key.tick >= current_tick.saturating_sub(1));
         self.current_tick = current_tick;
     }
 
+    /// Réservations de cellules `(position, tick, entité)`, sans ordre particulier
+    pub fn reservations(&self) -> impl Iterator<Item = (GridPos, u64, Entity)> + '_ {
+        self.reservations.iter().map(|(key, &entity)| (key.pos, key.tick, entity))
+    }
+
+    /// Réservations d'arêtes orientées, sans ordre particulier
+    pub fn edge_reservations(&self) -> impl Iterator<Item = (EdgeKey, Entity)> + '_ {
+        self.edges.iter().map(|(&key, &entity)| (key, entity))
+    }
+
     pub fn current_tick(&self) -> u64 {
         self.current_tick
     }
 
+    /// Place l'horloge sur `tick` sans toucher aux réservations (relecture, snapshots)
+    pub fn set_current_tick(&mut self, tick: u64) {
+        self.current_tick = tick;
+    }
+
     pub fn advance_tick(&mut self) {
         self.current_tick += 1;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_restores_the_table() {
+        let (a, b) = (Entity::from_raw_u32(1).unwrap(), Entity::from_raw_u32(2).unwrap());
+        let (p, q, r) = (GridPos::new(0, 0), GridPos::new(1, 0), GridPos::new(2, 0));
+        let mut table = SpaceTimeTable::default();
+        table.reserve(p, 0, a);
+        table.reserve_edge(p, q, 0, a);
+        let before: Vec<_> = table.reservations().collect();
+
+        let mut log = ReservationLog::default();
+        assert!(table.reserve_logged(p, 0, a, &mut log));
+        assert!(!table.reserve_logged(p, 0, b, &mut log));
+        assert!(table.reserve_logged(r, 3, b, &mut log));
+        assert!(table.reserve_edge_logged(p, q, 0, a, &mut log));
+        assert!(table.reserve_edge_logged(q, r, 1, b, &mut log));
+        table.undo(log);
+
+        assert_eq!(table.reservations().collect::<Vec<_>>(), before);
+        assert_eq!(table.occupant(p, 0), Some(a));
+        assert!(!table.has_reservations(r));
+        assert_eq!(table.edge_occupant(p, q, 0), Some(a));
+        assert_eq!(table.edge_occupant(q, r, 1), None);
+    }
+
+    #[test]
+    fn edges_keep_their_first_owner() {
+        let (a, b) = (Entity::from_raw_u32(1).unwrap(), Entity::from_raw_u32(2).unwrap());
+        let (p, q) = (GridPos::new(0, 0), GridPos::new(1, 0));
+        let mut table = SpaceTimeTable::default();
+        assert!(table.reserve_edge(p, q, 0, a));
+        assert!(table.reserve_edge(p, q, 0, a));
+
+        // Même déplacement ou déplacement inverse au même tick : refusé à un autre robot
+        let mut log = ReservationLog::default();
+        assert!(!table.reserve_edge(p, q, 0, b));
+        assert!(!table.reserve_edge_logged(p, q, 0, b, &mut log));
+        assert!(!table.reserve_edge_logged(q, p, 0, b, &mut log));
+        assert!(!table.reserve_path(&[(p, 0), (q, 1)], b));
+        assert!(table.reserve_edge_logged(p, q, 1, b, &mut log));
+        table.undo(log);
+
+        assert_eq!(table.edge_occupant(p, q, 0), Some(a));
+        assert_eq!(table.edge_occupant(p, q, 1), None);
+    }
+}
@@ -1,12 +1,13 @@
 use bevy::prelude::*;
 use rustc_hash::FxHashSet;
-use super::GridPos;
+use super::{Direction, GridPos, SimRng};
+use serde::{Deserialize, Serialize};
 use crate::constants::{
     GRID_WIDTH, GRID_HEIGHT, SPAWN_ZONE_WIDTH, CARGO_ZONE_WIDTH,
     RACK_LENGTH, AISLE_WIDTH,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Rack {
     pub start: GridPos,
     pub end: GridPos,
@@ -24,44 +25,82 @@ pub struct WarehouseZones {
     pub spawn_points: Vec<GridPos>,
     pub storage_cells: Vec<GridPos>,
     pub cargo_cells: Vec<GridPos>,
+    pub charger_cells: Vec<GridPos>,
     pub racks: Vec<Rack>,
 
     // Réservations actives
     reserved_storage: FxHashSet<GridPos>,
     reserved_cargo: FxHashSet<GridPos>,
+    reserved_chargers: FxHashSet<GridPos>,
 
     spawn_index: usize,
-    storage_index: usize,
-    cargo_index: usize,
 }
 
 impl Default for WarehouseZones {
     fn default() -> Self {
+        Self::procedural(GRID_WIDTH, GRID_HEIGHT)
+    }
+}
+
+impl WarehouseZones {
+    pub fn new(
+        spawn_points: Vec<GridPos>,
+        storage_cells: Vec<GridPos>,
+        cargo_cells: Vec<GridPos>,
+        racks: Vec<Rack>,
+    ) -> Self {
+        Self {
+            spawn_points,
+            storage_cells,
+            cargo_cells,
+            charger_cells: Vec::new(),
+            racks,
+            reserved_storage: FxHashSet::default(),
+            reserved_cargo: FxHashSet::default(),
+            reserved_chargers: FxHashSet::default(),
+            spawn_index: 0,
+        }
+    }
+
+    pub fn with_chargers(mut self, charger_cells: Vec<GridPos>) -> Self {
+        self.charger_cells = charger_cells;
+        self
+    }
+
+    /// Layout procédural historique : spawn à gauche, cargo à droite, racks au centre.
+    /// Sous `PROCEDURAL_MIN_WIDTH` x `PROCEDURAL_MIN_HEIGHT` certaines zones restent vides.
+    pub fn procedural(width: u32, height: u32) -> Self {
         let mut spawn_points = Vec::new();
         let mut storage_cells = Vec::new();
         let mut cargo_cells = Vec::new();
+        let mut charger_cells = Vec::new();
         let mut racks = Vec::new();
 
         // Zone de spawn (gauche)
         for x in 1..SPAWN_ZONE_WIDTH as i32 - 1 {
-            for y in (2..GRID_HEIGHT as i32 - 2).step_by(4) {
+            for y in (2..height as i32 - 2).step_by(4) {
                 spawn_points.push(GridPos::new(x, y));
             }
         }
 
+        // Chargeurs contre le bord gauche, entre les rangées de spawn
+        for y in (4..height as i32 - 2).step_by(4) {
+            charger_cells.push(GridPos::new(0, y));
+        }
+
         // Zone de cargo (droite)
-        let cargo_start_x = (GRID_WIDTH - CARGO_ZONE_WIDTH + 1) as i32;
-        for x in cargo_start_x..(GRID_WIDTH as i32 - 1) {
-            for y in (2..GRID_HEIGHT as i32 - 2).step_by(3) {
+        let cargo_start_x = (width.saturating_sub(CARGO_ZONE_WIDTH) + 1) as i32;
+        for x in cargo_start_x..(width as i32 - 1) {
+            for y in (2..height as i32 - 2).step_by(3) {
                 cargo_cells.push(GridPos::new(x, y));
             }
         }
 
         // Zone de stockage avec longs couloirs
         let storage_start_x = SPAWN_ZONE_WIDTH as i32 + 3;
-        let storage_end_x = (GRID_WIDTH - CARGO_ZONE_WIDTH - 3) as i32;
+        let storage_end_x = width as i32 - CARGO_ZONE_WIDTH as i32 - 3;
         let storage_start_y = 3i32;
-        let storage_end_y = (GRID_HEIGHT - 3) as i32;
+        let storage_end_y = height as i32 - 3;
 
         let row_spacing = RACK_LENGTH as i32 + AISLE_WIDTH as i32 + 1;
         let mut current_y = storage_start_y;
@@ -98,59 +137,119 @@ impl Default for WarehouseZones {
             current_y += row_spacing;
         }
 
-        storage_cells.sort_by(|a, b| (a.x, a.y).cmp(&(b.x, b.y)));
+        storage_cells.sort_by_key(|pos| (pos.x, pos.y));
         storage_cells.dedup();
         storage_cells.retain(|pos| !racks.iter().any(|r| r.contains(*pos)));
 
-        Self {
-            spawn_points,
-            storage_cells,
-            cargo_cells,
-            racks,
-            reserved_storage: FxHashSet::default(),
-            reserved_cargo: FxHashSet::default(),
-            spawn_index: 0,
-            storage_index: 0,
-            cargo_index: 0,
-        }
+        Self::new(spawn_points, storage_cells, cargo_cells, racks).with_chargers(charger_cells)
     }
-}
 
-impl WarehouseZones {
-    pub fn next_spawn(&mut self) -> GridPos {
+    /// Position du tourniquet des points de spawn
+    pub fn spawn_index(&self) -> usize {
+        self.spawn_index
+    }
+
+    pub fn set_spawn_index(&mut self, index: usize) {
+        self.spawn_index = index;
+    }
+
+    /// Prochain point de spawn du tourniquet, None si le layout n'en a aucun
+    pub fn next_spawn(&mut self) -> Option<GridPos> {
+        if self.spawn_points.is_empty() {
+            return None;
+        }
         let pos = self.spawn_points[self.spawn_index % self.spawn_points.len()];
         self.spawn_index += 1;
-        pos
-    }
-
-    /// Réserve un storage libre, retourne None si tous occupés
-    pub fn reserve_storage(&mut self) -> Option<GridPos> {
-        let len = self.storage_cells.len();
-        for i in 0..len {
-            let idx = (self.storage_index + i) % len;
-            let pos = self.storage_cells[idx];
-            if !self.reserved_storage.contains(&pos) {
-                self.reserved_storage.insert(pos);
-                self.storage_index = idx + 1;
-                return Some(pos);
+        Some(pos)
+    }
+
+    /// Réserve un storage libre tiré au hasard, retourne None si tous occupés
+    pub fn reserve_storage(&mut self, rng: &mut SimRng) -> Option<GridPos> {
+        self.reserve_storage_where(rng, |_| true)
+    }
+
+    /// Réserve un storage libre tiré au hasard parmi ceux qui vérifient `accept`
+    pub fn reserve_storage_where(
+        &mut self,
+        rng: &mut SimRng,
+        accept: impl Fn(GridPos) -> bool,
+    ) -> Option<GridPos> {
+        let free: Vec<GridPos> = self
+            .storage_cells
+            .iter()
+            .copied()
+            .filter(|pos| !self.reserved_storage.contains(pos) && accept(*pos))
+            .collect();
+        let pos = rng.pick(&free)?;
+        self.reserved_storage.insert(pos);
+        Some(pos)
+    }
+
+    /// Réserve un cargo libre tiré au hasard, retourne None si tous occupés
+    pub fn reserve_cargo(&mut self, rng: &mut SimRng) -> Option<GridPos> {
+        let free: Vec<GridPos> = self
+            .cargo_cells
+            .iter()
+            .copied()
+            .filter(|pos| !self.reserved_cargo.contains(pos))
+            .collect();
+        let pos = rng.pick(&free)?;
+        self.reserved_cargo.insert(pos);
+        Some(pos)
+    }
+
+    /// Réserve le chargeur libre le plus proche de `from`, retourne None si tous occupés
+    pub fn reserve_charger(&mut self, from: GridPos) -> Option<GridPos> {
+        let pos = self
+            .charger_cells
+            .iter()
+            .copied()
+            .filter(|pos| !self.reserved_chargers.contains(pos))
+            .min_by_key(|pos| (pos.manhattan_distance(&from), pos.x, pos.y))?;
+        self.reserved_chargers.insert(pos);
+        Some(pos)
+    }
+
+    /// Réserve, parmi `cells`, le storage libre le plus proche de `from`
+    pub fn reserve_storage_near(&mut self, cells: &[GridPos], from: GridPos) -> Option<GridPos> {
+        let pos = nearest_free(cells, &self.reserved_storage, from)?;
+        self.reserved_storage.insert(pos);
+        Some(pos)
+    }
+
+    /// Réserve, parmi `cells`, le cargo libre le plus proche de `from`
+    pub fn reserve_cargo_near(&mut self, cells: &[GridPos], from: GridPos) -> Option<GridPos> {
+        let pos = nearest_free(cells, &self.reserved_cargo, from)?;
+        self.reserved_cargo.insert(pos);
+        Some(pos)
+    }
+
+    /// Quais : groupes de cargos contigus, chacun trié, dans l'ordre de leur première cellule
+    pub fn docks(&self) -> Vec<Vec<GridPos>> {
+        let cells: FxHashSet<GridPos> = self.cargo_cells.iter().copied().collect();
+        let mut seen = FxHashSet::default();
+        let mut docks = Vec::new();
+        let mut sorted = self.cargo_cells.clone();
+        sorted.sort_by_key(|p| (p.x, p.y));
+        for start in sorted {
+            if !seen.insert(start) {
+                continue;
             }
-        }
-        None
-    }
-
-    /// Réserve un cargo libre, retourne None si tous occupés
-    pub fn reserve_cargo(&mut self) -> Option<GridPos> {
-        let len = self.cargo_cells.len();
-        for i in 0..len {
-            let idx = (self.cargo_index + i) % len;
-            let pos = self.cargo_cells[idx];
-            if !self.reserved_cargo.contains(&pos) {
-                self.reserved_cargo.insert(pos);
-                self.cargo_index = idx + 1;
-                return Some(pos);
+            let mut dock = vec![start];
+            let mut stack = vec![start];
+            while let Some(pos) = stack.pop() {
+                for dir in Direction::CARDINALS {
+                    let next = pos.neighbor(dir);
+                    if cells.contains(&next) && seen.insert(next) {
+                        dock.push(next);
+                        stack.push(next);
+                    }
+                }
             }
+            dock.sort_by_key(|p| (p.x, p.y));
+            docks.push(dock);
         }
-        None
+        docks
     }
 
     /// Libère un storage
@@ -163,7 +262,100 @@ impl WarehouseZones {
         self.reserved_cargo.remove(&pos);
     }
 
+    /// Libère un chargeur
+    pub fn release_charger(&mut self, pos: GridPos) {
+        self.reserved_chargers.remove(&pos);
+    }
+
+    /// Libère une cellule quel que soit son type
+    pub fn release(&mut self, pos: GridPos) {
+        self.release_storage(pos);
+        self.release_cargo(pos);
+        self.release_charger(pos);
+    }
+
+    /// Storages réservés, triés
+    pub fn reserved_storage(&self) -> Vec<GridPos> {
+        let mut cells: Vec<_> = self.reserved_storage.iter().copied().collect();
+        cells.sort_by_key(|p| (p.x, p.y));
+        cells
+    }
+
+    /// Cargos réservés, triés
+    pub fn reserved_cargo(&self) -> Vec<GridPos> {
+        let mut cells: Vec<_> = self.reserved_cargo.iter().copied().collect();
+        cells.sort_by_key(|p| (p.x, p.y));
+        cells
+    }
+
+    /// Chargeurs réservés, triés
+    pub fn reserved_chargers(&self) -> Vec<GridPos> {
+        let mut cells: Vec<_> = self.reserved_chargers.iter().copied().collect();
+        cells.sort_by_key(|p| (p.x, p.y));
+        cells
+    }
+
+    pub fn is_reserved(&self, pos: GridPos) -> bool {
+        self.reserved_storage.contains(&pos)
+            || self.reserved_cargo.contains(&pos)
+            || self.reserved_chargers.contains(&pos)
+    }
+
+    /// Remplace toutes les réservations (relecture d'un enregistrement)
+    pub fn set_reservations(&mut self, storage: &[GridPos], cargo: &[GridPos], chargers: &[GridPos]) {
+        self.reserved_storage = storage.iter().copied().collect();
+        self.reserved_cargo = cargo.iter().copied().collect();
+        self.reserved_chargers = chargers.iter().copied().collect();
+    }
+
     pub fn is_rack(&self, pos: GridPos) -> bool {
         self.racks.iter().any(|r| r.contains(pos))
     }
-}
\ No newline at end of file
+}
+
+fn nearest_free(cells: &[GridPos], reserved: &FxHashSet<GridPos>, from: GridPos) -> Option<GridPos> {
+    cells
+        .iter()
+        .copied()
+        .filter(|pos| !reserved.contains(pos))
+        .min_by_key(|pos| (pos.manhattan_distance(&from), pos.x, pos.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{PROCEDURAL_MIN_HEIGHT, PROCEDURAL_MIN_WIDTH, RACK_LENGTH};
+    use crate::core::WarehouseLayout;
+
+    #[test]
+    fn default_grid_keeps_its_historical_zones() {
+        let zones = WarehouseZones::default();
+        // 6 colonnes de spawn sur 14 rangées, 6 colonnes de cargo sur 19 rangées
+        assert_eq!(zones.spawn_points.len(), 84);
+        assert_eq!(zones.cargo_cells.len(), 114);
+        // 3 rangées de 12 racks, desservis des deux côtés sur toute leur longueur
+        assert_eq!(zones.racks.len(), 36);
+        assert_eq!(zones.storage_cells.len(), 36 * 2 * RACK_LENGTH as usize);
+        assert_eq!(zones.charger_cells.len(), 14);
+        assert_eq!(zones.spawn_points[0], GridPos::new(1, 2));
+        assert_eq!(zones.cargo_cells[0], GridPos::new(73, 2));
+    }
+
+    #[test]
+    fn tiny_procedural_layouts_do_not_underflow() {
+        for (width, height) in [(1, 1), (5, 4), (11, 30), (40, 10)] {
+            let mut zones = WarehouseZones::procedural(width, height);
+            if zones.spawn_points.is_empty() {
+                assert_eq!(zones.next_spawn(), None);
+            }
+            assert!(WarehouseLayout::procedural(width, height).validate().is_err(), "{width}x{height}");
+        }
+    }
+
+    #[test]
+    fn smallest_procedural_layout_is_valid() {
+        let layout = WarehouseLayout::procedural(PROCEDURAL_MIN_WIDTH, PROCEDURAL_MIN_HEIGHT);
+        layout.validate().unwrap();
+        assert!(!layout.racks.is_empty());
+    }
+}
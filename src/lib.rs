@@ -1,3 +1,4 @@
+pub mod cli;
 pub mod components;
 pub mod constants;
 pub mod core;
@@ -1,37 +1,180 @@
-use bevy::{prelude::*, text::FontSmoothing};
-use bevy_dev_tools::fps_overlay::{FpsOverlayConfig, FpsOverlayPlugin, FrameTimeGraphConfig};
+use bevy::prelude::*;
+use clap::Parser;
+use warehouse_sim::cli::{Cli, Command, SimArgs};
+use warehouse_sim::components::RobotState;
+#[cfg(not(feature = "gui"))]
+use warehouse_sim::constants::HEADLESS_TICKS;
+use warehouse_sim::plugins::movingai::{MovingAiError, MovingAiPlugin};
+use warehouse_sim::plugins::simulation::{
+    run_headless, HeadlessPlugin, SimulationCorePlugin, SimulationSettings,
+};
+#[cfg(feature = "gui")]
+use warehouse_sim::plugins::replay::ReplayPlugins;
+#[cfg(feature = "gui")]
 use warehouse_sim::plugins::warehouse::WarehousePlugins;
+use warehouse_sim::systems::planner::PlannerRegistry;
+use warehouse_sim::systems::replay::Replay;
 
-fn main() {
+fn main() -> AppExit {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Run(sim)) => match sim.movingai() {
+            Ok(movingai) => run_gui(sim.settings(), movingai),
+            Err(e) => movingai_error(e),
+        },
+        Some(Command::Headless { sim, ticks }) => match sim.movingai() {
+            Ok(movingai) => headless_app(sim.settings(), movingai, ticks, true).run(),
+            Err(e) => movingai_error(e),
+        },
+        Some(Command::Bench { sim, ticks, planners, allocators }) => bench(&sim, ticks, planners, allocators),
+        Some(Command::Replay { file }) => run_replay(&file),
+        #[cfg(feature = "gui")]
+        None => run_gui(SimulationSettings::default(), None),
+        #[cfg(not(feature = "gui"))]
+        None => headless_app(SimulationSettings::default(), None, HEADLESS_TICKS, true).run(),
+    }
+}
+
+fn movingai_error(error: MovingAiError) -> AppExit {
+    eprintln!("{error}");
+    AppExit::error()
+}
+
+#[cfg(feature = "gui")]
+fn run_gui(settings: SimulationSettings, movingai: Option<MovingAiPlugin>) -> AppExit {
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Warehouse Simulator".into(),
+            resolution: (1280, 720).into(),
+            ..default()
+        }),
+        ..default()
+    }))
+    .add_plugins(WarehousePlugins { settings });
+    if let Some(movingai) = movingai {
+        app.add_plugins(movingai);
+    }
+    app.run()
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui(_settings: SimulationSettings, _movingai: Option<MovingAiPlugin>) -> AppExit {
+    eprintln!("this build has no window: rebuild with `--features gui`, or use `headless`");
+    AppExit::error()
+}
+
+fn run_replay(file: &std::path::Path) -> AppExit {
+    let replay = match Replay::load(file) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("cannot load replay {}: {e}", file.display());
+            return AppExit::error();
+        }
+    };
+    replay_window(replay)
+}
+
+#[cfg(feature = "gui")]
+fn replay_window(replay: Replay) -> AppExit {
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                title: "Warehouse Simulator".into(),
+                title: "Warehouse Simulator - Replay".into(),
                 resolution: (1280, 720).into(),
                 ..default()
             }),
             ..default()
         }))
-        .add_plugins(WarehousePlugins)
-        /*
-        .add_plugins(FpsOverlayPlugin {
-            config: FpsOverlayConfig {
-                text_config: TextFont {
-                    font_size: 42.0,
-                    font: default(),
-                    font_smoothing: FontSmoothing::default(),
-                    ..default()
-                },
-                refresh_interval: core::time::Duration::from_millis(100),
-                enabled: true,
-                frame_time_graph_config: FrameTimeGraphConfig {
-                    enabled: true,
-                    min_fps: 30.0,
-                    target_fps: 144.0,
-                },
-                text_color: Default::default(),
-            },
-        })
-         */
-        .run();
+        .add_plugins(ReplayPlugins { replay })
+        .run()
+}
+
+#[cfg(not(feature = "gui"))]
+fn replay_window(_replay: Replay) -> AppExit {
+    eprintln!("this build has no window: rebuild with `--features gui` to play back a replay");
+    AppExit::error()
+}
+
+/// Sans rendu : simulation seule, aussi vite que possible
+fn headless_app(settings: SimulationSettings, movingai: Option<MovingAiPlugin>, ticks: u64, log: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    if log {
+        app.add_plugins(bevy::log::LogPlugin::default());
+    }
+    app.add_plugins(SimulationCorePlugin { settings });
+    // Écrase la grille et les zones du coeur, d'où l'ordre
+    if let Some(movingai) = movingai {
+        app.add_plugins(movingai);
+    }
+    app.add_plugins(HeadlessPlugin { ticks });
+    app
+}
+
+/// Même simulation pour chaque solveur, bilans côte à côte
+fn bench(sim: &SimArgs, ticks: u64, planners: Vec<String>, allocators: Vec<String>) -> AppExit {
+    let planners = if planners.is_empty() {
+        PlannerRegistry::default().names().map(str::to_string).collect()
+    } else {
+        planners
+    };
+    let allocators: Vec<Option<String>> = if allocators.is_empty() {
+        vec![sim.allocator.clone()]
+    } else {
+        allocators.into_iter().map(Some).collect()
+    };
+    let movingai = match sim.movingai() {
+        Ok(movingai) => movingai,
+        Err(e) => return movingai_error(e),
+    };
+    let runs = planners
+        .iter()
+        .flat_map(|planner| allocators.iter().map(move |allocator| (planner, allocator)));
+
+    println!(
+        "{:<8} {:<10} {:>8} {:>12} {:>10} {:>9} {:>7} {:>7} {:>10} {:>6} {:>10}",
+        "planner", "allocator", "ticks", "wall", "ticks/s", "spawned", "moving", "idle", "missions/h", "util",
+        "steps/task"
+    );
+    for (planner, allocator) in runs {
+        // Un fichier d'indicateurs et un dossier d'enregistrement par combinaison
+        let name = match allocator {
+            Some(allocator) => format!("{planner}-{allocator}"),
+            None => planner.clone(),
+        };
+        let settings = SimulationSettings {
+            planner: Some(planner.clone()),
+            allocator: allocator.clone(),
+            metrics_out: sim
+                .metrics_out
+                .as_ref()
+                .map(|path| path.with_extension(format!("{name}.ron"))),
+            record: sim.record.as_ref().map(|dir| dir.join(&name)),
+            ..sim.settings()
+        };
+        let report = run_headless(headless_app(settings, movingai.clone(), ticks, false), ticks);
+        let count = |state| {
+            report
+                .states
+                .iter()
+                .find(|(s, _)| *s == state)
+                .map_or(0, |(_, n)| *n)
+        };
+        println!(
+            "{:<8} {:<10} {:>8} {:>12.2?} {:>10.0} {:>9} {:>7} {:>7} {:>10.0} {:>5.1}% {:>10}",
+            planner,
+            allocator.as_deref().unwrap_or("-"),
+            report.ticks,
+            report.elapsed,
+            report.ticks_per_second(),
+            report.robots_spawned,
+            count(RobotState::Moving),
+            count(RobotState::Idle),
+            report.metrics.missions_per_hour,
+            report.metrics.utilization * 100.0,
+            report.metrics.assignment_steps_mean.map_or("-".to_string(), |s| format!("{s:.1}")),
+        );
+    }
+    AppExit::Success
 }
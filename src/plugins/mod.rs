@@ -1,5 +1,17 @@
+pub mod movingai;
 pub mod navigation;
+pub mod recorder;
+#[cfg(feature = "gui")]
+pub mod replay;
+pub mod simulation;
+#[cfg(feature = "gui")]
 pub mod warehouse;
 
+pub use movingai::*;
 pub use navigation::*;
+pub use recorder::*;
+#[cfg(feature = "gui")]
+pub use replay::*;
+pub use simulation::*;
+#[cfg(feature = "gui")]
 pub use warehouse::*;
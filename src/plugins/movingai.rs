@@ -0,0 +1,91 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::core::movingai::{load_map, load_scenario, MovingAiScenario};
+use crate::core::{HighwayGraph, LayoutError, WarehouseGrid, WarehouseZones};
+use crate::systems::inventory::Inventory;
+use crate::systems::navigation::path_execution_system;
+use crate::systems::scenario::{
+    scenario_arrival_system, spawn_scenario_agents, ScenarioQueue, ScenarioResults,
+};
+use crate::systems::spawner::SpawnQueue;
+
+#[derive(Debug)]
+pub enum MovingAiError {
+    Map { path: PathBuf, error: LayoutError },
+    Scenario { path: PathBuf, error: LayoutError },
+}
+
+impl fmt::Display for MovingAiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Map { path, error } => {
+                write!(f, "cannot load MovingAI map {}: {error}", path.display())
+            }
+            Self::Scenario { path, error } => {
+                write!(f, "invalid MovingAI scenario {}: {error}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MovingAiError {}
+
+/// Remplace l'entrepôt par une carte MovingAI et ses agents `.scen`.
+/// À ajouter après `SimulationCorePlugin` (ou `WarehousePlugins`), dont il écrase la grille,
+/// les zones et l'inventaire.
+#[derive(Clone)]
+pub struct MovingAiPlugin {
+    pub grid: WarehouseGrid,
+    /// Agents déjà tronqués et validés sur la grille
+    pub scenario: MovingAiScenario,
+}
+
+impl MovingAiPlugin {
+    /// Charge la carte et les `agent_count` premiers agents du scénario, placés sur du sol
+    pub fn load(
+        map_path: impl AsRef<Path>,
+        scenario_path: impl AsRef<Path>,
+        agent_count: usize,
+    ) -> Result<Self, MovingAiError> {
+        let (map_path, scenario_path) = (map_path.as_ref(), scenario_path.as_ref());
+        let scenario_error =
+            |error| MovingAiError::Scenario { path: scenario_path.to_path_buf(), error };
+
+        let grid = load_map(map_path)
+            .map_err(|error| MovingAiError::Map { path: map_path.to_path_buf(), error })?;
+        let mut scenario = load_scenario(scenario_path).map_err(scenario_error)?;
+        scenario.agents.truncate(agent_count);
+        scenario.validate(&grid).map_err(scenario_error)?;
+        Ok(Self { grid, scenario })
+    }
+}
+
+impl Plugin for MovingAiPlugin {
+    fn build(&self, app: &mut App) {
+        let grid = &self.grid;
+        info!(
+            "MovingAI instance {} ({}x{}), {} agents",
+            self.scenario.map_name,
+            grid.width(),
+            grid.height(),
+            self.scenario.agents.len()
+        );
+
+        let highways = HighwayGraph::new(grid.width(), grid.height());
+
+        app.insert_resource(grid.clone())
+            .insert_resource(highways)
+            .insert_resource(WarehouseZones::new(Vec::new(), Vec::new(), Vec::new(), Vec::new()))
+            // Le stock de l'entrepôt remplacé disparaît avec ses storages
+            .insert_resource(Inventory::default())
+            // Pas de spawn séquentiel : tous les agents partent au tick 0
+            .insert_resource(SpawnQueue { total: 0, ..default() })
+            .insert_resource(ScenarioQueue { agents: self.scenario.agents.clone() })
+            .init_resource::<ScenarioResults>()
+            .add_systems(Startup, spawn_scenario_agents)
+            .add_systems(FixedUpdate, scenario_arrival_system.after(path_execution_system));
+    }
+}
@@ -1,13 +1,23 @@
 use bevy::prelude::*;
 
 use crate::constants::TICK_DELTA;
-use crate::core::{HighwayGraph, SpaceTimeTable};
+use crate::core::{HighwayGraph, SimRng, SpaceTimeTable};
+use crate::systems::allocation::{task_allocation_system, AllocationConfig, AllocatorRegistry};
+use crate::systems::battery::{battery_consumption_system, RobotModels};
+use crate::systems::events::SimEvent;
+use crate::systems::faults::{fault_injection_system, FaultConfig, FaultRequests};
+use crate::systems::inventory::Inventory;
+use crate::systems::orders::{order_arrival_system, OrderBook, OrderConfig};
+use crate::systems::metrics::{metrics_sampling_system, SimMetrics};
 use crate::systems::navigation::{
-    battery_consumption_system, deadlock_detection_system, path_execution_system,
-    simulation_tick_system, visual_interpolation_system,
+    deadlock_detection_system, path_execution_system, simulation_tick_system,
+};
+use crate::systems::pbs::{update_priorities_system, PbsConfig};
+use crate::systems::planner::{planning_system, PlannerRegistry};
+use crate::systems::spawner::{
+    mission_progression_system, sequential_spawn_system, ChargingConfig, MissionTimings,
+    SpawnQueue,
 };
-use crate::systems::pbs::{pbs_planning_system, update_priorities_system, PbsConfig};
-use crate::systems::spawner::{mission_progression_system, sequential_spawn_system, SpawnQueue};
 
 pub struct NavigationPlugin;
 
@@ -16,24 +26,39 @@ impl Plugin for NavigationPlugin {
         app.init_resource::<SpaceTimeTable>()
             .init_resource::<HighwayGraph>()
             .init_resource::<PbsConfig>()
+            .init_resource::<PlannerRegistry>()
+            .init_resource::<AllocatorRegistry>()
+            .init_resource::<AllocationConfig>()
             .init_resource::<SpawnQueue>()
+            .init_resource::<MissionTimings>()
+            .init_resource::<ChargingConfig>()
+            .init_resource::<RobotModels>()
+            .init_resource::<FaultConfig>()
+            .init_resource::<FaultRequests>()
+            .init_resource::<OrderConfig>()
+            .init_resource::<OrderBook>()
+            .init_resource::<Inventory>()
+            .init_resource::<SimRng>()
+            .init_resource::<SimMetrics>()
+            .add_message::<SimEvent>()
             .add_systems(
                 FixedUpdate,
                 (
                     simulation_tick_system,
+                    order_arrival_system,
                     sequential_spawn_system,
+                    task_allocation_system,
                     mission_progression_system,
                     update_priorities_system,
-                    pbs_planning_system,
+                    planning_system,
                     path_execution_system,
+                    battery_consumption_system,
+                    fault_injection_system,
                     deadlock_detection_system,
+                    metrics_sampling_system,
                 )
                     .chain(),
             )
-            .add_systems(
-                Update,
-                (visual_interpolation_system, battery_consumption_system),
-            )
             .insert_resource(Time::<Fixed>::from_seconds(TICK_DELTA as f64));
     }
 }
\ No newline at end of file
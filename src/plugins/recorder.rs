@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::core::{SimRng, WarehouseLayout};
+use crate::systems::metrics::metrics_sampling_system;
+use crate::systems::recorder::{record_events_system, record_trajectories_system, Recorder};
+use crate::systems::replay::{capture_replay_system, ReplayCapture, REPLAY_FILE};
+
+/// Enregistre trajectoires, événements et relecture (`replay.ron`) de l'exécution dans `dir`.
+/// À ajouter après `SimulationCorePlugin`, dont il lit le layout et l'état en fin de tick.
+pub struct RecorderPlugin {
+    pub dir: PathBuf,
+    /// Écrit aussi le format binaire colonnaire
+    pub binary: bool,
+}
+
+impl RecorderPlugin {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), binary: false }
+    }
+
+    pub fn with_binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+}
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        let recorder = Recorder::new(&self.dir, self.binary).unwrap_or_else(|e| {
+            panic!("cannot create run directory {}: {e}", self.dir.display())
+        });
+
+        app.insert_resource(recorder).add_systems(
+            FixedUpdate,
+            (record_trajectories_system, record_events_system)
+                .chain()
+                .after(metrics_sampling_system)
+                .run_if(resource_exists::<Recorder>),
+        );
+
+        // La relecture a besoin du layout pour redessiner l'entrepôt
+        let world = app.world();
+        match world.get_resource::<WarehouseLayout>() {
+            Some(layout) => {
+                let seed = world.get_resource::<SimRng>().map_or(0, SimRng::seed);
+                let capture = ReplayCapture::new(self.dir.join(REPLAY_FILE), layout.clone(), seed);
+                app.insert_resource(capture).add_systems(
+                    FixedUpdate,
+                    capture_replay_system.after(metrics_sampling_system),
+                );
+            }
+            None => warn!("no warehouse layout, the run is recorded without replay"),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use crate::core::SpaceTimeTable;
+use crate::plugins::warehouse::WarehouseRenderPlugin;
+use crate::systems::metrics::SimMetrics;
+use crate::systems::planner::PlannerRegistry;
+use crate::systems::replay::{
+    apply_playback_system, playback_clock_system, spawn_playback_robots, Playback, Replay,
+};
+use crate::systems::spawner::SpawnQueue;
+use crate::systems::visualization::visual_interpolation_system;
+
+/// Relecture d'un enregistrement dans la fenêtre : rendu seul, aucun solveur ni mission.
+/// Remplace `WarehousePlugins`.
+pub struct ReplayPlugins {
+    pub replay: Replay,
+}
+
+impl Plugin for ReplayPlugins {
+    fn build(&self, app: &mut App) {
+        let replay = self.replay.clone();
+        let (grid, zones) = replay.layout.build();
+        let robots = replay.robots.len() as u32;
+
+        info!(
+            "Replay of {} robots, ticks {}..={} (seed {})",
+            robots,
+            replay.first_tick(),
+            replay.last_tick,
+            replay.seed
+        );
+
+        // Ressources lues par le rendu et le panneau, figées pendant la relecture
+        app.insert_resource(grid)
+            .insert_resource(zones)
+            .insert_resource(SpawnQueue { total: robots, spawned_count: robots, ..default() })
+            .init_resource::<SpaceTimeTable>()
+            .init_resource::<SimMetrics>()
+            .init_resource::<PlannerRegistry>()
+            .insert_resource(Playback::new(replay))
+            .add_plugins(WarehouseRenderPlugin)
+            .add_systems(Startup, spawn_playback_robots)
+            .add_systems(
+                Update,
+                (playback_clock_system, apply_playback_system)
+                    .chain()
+                    .before(visual_interpolation_system),
+            );
+    }
+}
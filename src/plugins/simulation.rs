@@ -0,0 +1,255 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use bevy::app::PluginsState;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use crate::constants::{DEFAULT_SEED, LAYOUT_PATH, ROBOT_COUNT, TICK_DELTA};
+use crate::core::{HighwayGraph, HighwayMode, SimRng, SpaceTimeTable, WarehouseLayout};
+use crate::plugins::navigation::NavigationPlugin;
+use crate::plugins::recorder::RecorderPlugin;
+use crate::systems::allocation::{AllocationConfig, AllocatorRegistry};
+use crate::systems::battery::RobotModels;
+use crate::systems::faults::FaultConfig;
+use crate::systems::inventory::InventoryConfig;
+use crate::systems::orders::OrderConfig;
+use crate::systems::metrics::{export_metrics, MetricsExport};
+use crate::systems::navigation::path_execution_system;
+use crate::systems::pbs::PbsConfig;
+use crate::systems::planner::PlannerRegistry;
+use crate::systems::report::SimulationReport;
+use crate::systems::replay::export_replay;
+use crate::systems::snapshot::{export_snapshot, SimulationSnapshot, SnapshotExport};
+use crate::systems::spawner::{ChargingConfig, MissionTimings, SpawnQueue};
+use crate::systems::validation::{motion_validation_system, MotionValidation};
+
+/// Paramètres d'une exécution, surchargent les valeurs de `constants.rs`
+#[derive(Debug, Clone)]
+pub struct SimulationSettings {
+    /// Fichier de layout ; sinon `LAYOUT_PATH` ou le layout procédural
+    pub layout: Option<PathBuf>,
+    /// Dimensions du layout procédural, ignorées si `layout` est fourni
+    pub grid_size: Option<(u32, u32)>,
+    /// Sens de circulation des couloirs, stricts par défaut
+    pub highway_mode: HighwayMode,
+    pub robots: u32,
+    /// Graine de `SimRng`, `DEFAULT_SEED` si absente
+    pub seed: Option<u64>,
+    pub timings: MissionTimings,
+    pub charging: ChargingConfig,
+    /// Modèles de batterie, attribués à tour de rôle
+    pub robot_models: RobotModels,
+    /// Pannes aléatoires et scriptées
+    pub faults: FaultConfig,
+    /// Flux de commandes, à défaut missions tirées au hasard
+    pub orders: OrderConfig,
+    /// Stock initial des emplacements
+    pub inventory: InventoryConfig,
+    pub pbs: PbsConfig,
+    /// Solveur à activer, sinon celui par défaut du registre
+    pub planner: Option<String>,
+    /// Allocateur de tâches à activer, sinon chaque robot libre prend le premier storage libre
+    pub allocator: Option<String>,
+    pub allocation: AllocationConfig,
+    /// Fichier où exporter les indicateurs en fin d'exécution
+    pub metrics_out: Option<PathBuf>,
+    /// Dossier où enregistrer trajectoires et événements
+    pub record: Option<PathBuf>,
+    /// Enregistre aussi au format binaire colonnaire
+    pub record_binary: bool,
+    /// Snapshot à reprendre : layout, robots et ressources en viennent ; `planner`,
+    /// `allocator` et `seed`, s'ils sont fournis, l'emportent pour brancher une variante
+    pub resume: Option<PathBuf>,
+    /// Fichier où écrire un snapshot en fin d'exécution
+    pub snapshot_out: Option<PathBuf>,
+    /// Vérifie collisions et déplacements à chaque tick, actif par défaut en debug
+    pub validate: bool,
+    /// Met la fenêtre en pause à la première violation
+    pub pause_on_violation: bool,
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        Self {
+            layout: None,
+            grid_size: None,
+            highway_mode: HighwayMode::default(),
+            robots: ROBOT_COUNT,
+            seed: None,
+            timings: MissionTimings::default(),
+            charging: ChargingConfig::default(),
+            robot_models: RobotModels::default(),
+            faults: FaultConfig::default(),
+            orders: OrderConfig::default(),
+            inventory: InventoryConfig::default(),
+            pbs: PbsConfig::default(),
+            planner: None,
+            allocator: None,
+            allocation: AllocationConfig::default(),
+            metrics_out: None,
+            record: None,
+            record_binary: false,
+            resume: None,
+            snapshot_out: None,
+            validate: cfg!(debug_assertions),
+            pause_on_violation: false,
+        }
+    }
+}
+
+impl SimulationSettings {
+    fn warehouse_layout(&self) -> WarehouseLayout {
+        match (&self.layout, self.grid_size) {
+            (Some(path), _) => WarehouseLayout::load(path).unwrap_or_else(|e| {
+                panic!("cannot load layout {}: {e}", path.display())
+            }),
+            (None, Some((width, height))) => {
+                let layout = WarehouseLayout::procedural(width, height);
+                if let Err(e) = layout.validate() {
+                    panic!("procedural layout {width}x{height} is unusable: {e}");
+                }
+                layout
+            }
+            (None, None) => WarehouseLayout::load_or_procedural(LAYOUT_PATH),
+        }
+    }
+}
+
+/// Logique de simulation seule : layout, missions, planification et exécution des chemins.
+/// Aucune dépendance au rendu ni à egui, la même logique tourne avec ou sans fenêtre.
+#[derive(Default)]
+pub struct SimulationCorePlugin {
+    pub settings: SimulationSettings,
+}
+
+impl Plugin for SimulationCorePlugin {
+    fn build(&self, app: &mut App) {
+        let settings = &self.settings;
+
+        let snapshot = settings.resume.as_ref().map(|path| {
+            SimulationSnapshot::load(path).unwrap_or_else(|e| {
+                panic!("cannot load snapshot {}: {e}", path.display())
+            })
+        });
+
+        // Charge le layout (snapshot, fichier ou procédural), grille avec racks comme
+        // obstacles, puis couloirs à sens unique
+        let layout = match &snapshot {
+            Some(snapshot) => snapshot.layout.clone(),
+            None => settings.warehouse_layout(),
+        };
+        let (grid, zones) = layout.build();
+        let highways = HighwayGraph::alternating(&grid, &zones, settings.highway_mode);
+        let inventory = settings
+            .inventory
+            .build(&zones, settings.orders.sku_count(&zones))
+            .unwrap_or_else(|e| panic!("invalid inventory: {e}"));
+        if let Err(e) = settings.orders.validate(&zones) {
+            panic!("invalid order script: {e}");
+        }
+
+        app.insert_resource(layout)
+            .insert_resource(zones)
+            .insert_resource(inventory)
+            .insert_resource(grid)
+            .insert_resource(highways)
+            .insert_resource(SpawnQueue { total: settings.robots, ..default() })
+            .insert_resource(settings.timings)
+            .insert_resource(settings.charging)
+            .insert_resource(settings.robot_models.clone())
+            .insert_resource(settings.faults.clone())
+            .insert_resource(settings.orders.clone())
+            .insert_resource(settings.pbs.clone())
+            .insert_resource(settings.allocation)
+            .insert_resource(SimRng::new(settings.seed.unwrap_or(DEFAULT_SEED)))
+            .add_plugins(NavigationPlugin);
+
+        if let Some(path) = &settings.metrics_out {
+            app.insert_resource(MetricsExport { path: path.clone() });
+        }
+        if let Some(path) = &settings.snapshot_out {
+            app.insert_resource(SnapshotExport { path: path.clone() });
+        }
+        if settings.validate || settings.pause_on_violation {
+            app.insert_resource(MotionValidation {
+                pause_on_violation: settings.pause_on_violation,
+                ..default()
+            })
+            .add_systems(FixedUpdate, motion_validation_system.after(path_execution_system));
+        }
+        if let Some(dir) = &settings.record {
+            app.add_plugins(RecorderPlugin::new(dir).with_binary(settings.record_binary));
+        }
+
+        if let Some(snapshot) = &snapshot {
+            if let Err(e) = snapshot.restore(app.world_mut()) {
+                panic!("cannot restore snapshot: {e}");
+            }
+            if let Some(seed) = settings.seed {
+                app.insert_resource(SimRng::new(seed));
+            }
+            info!("Resumed {} robots at tick {}", snapshot.robots.len(), snapshot.tick);
+        }
+
+        if let Some(name) = &settings.planner {
+            let mut registry = app.world_mut().resource_mut::<PlannerRegistry>();
+            if !registry.select(name) {
+                let known: Vec<&str> = registry.names().collect();
+                panic!("unknown planner `{name}` (available: {})", known.join(", "));
+            }
+        }
+        if let Some(name) = &settings.allocator {
+            let mut registry = app.world_mut().resource_mut::<AllocatorRegistry>();
+            if !registry.select(Some(name)) {
+                let known: Vec<&str> = registry.names().collect();
+                panic!("unknown allocator `{name}` (available: {})", known.join(", "));
+            }
+        }
+    }
+}
+
+/// Exécution sans fenêtre ni renderer : chaque update avance le temps d'exactement un tick
+/// fixe, sans attendre l'horloge, pendant `ticks` ticks de simulation.
+/// À ajouter après `MinimalPlugins`, dont il remplace le runner.
+pub struct HeadlessPlugin {
+    pub ticks: u64,
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        let ticks = self.ticks;
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            TICK_DELTA as f64,
+        )))
+        .set_runner(move |app| {
+            println!("{}", run_headless(app, ticks));
+            AppExit::Success
+        });
+    }
+}
+
+/// Fait tourner une app configurée avec `HeadlessPlugin` pendant `ticks` ticks de plus
+/// (ou jusqu'à une demande de sortie) et renvoie son bilan
+pub fn run_headless(mut app: App, ticks: u64) -> SimulationReport {
+    while app.plugins_state() == PluginsState::Adding {
+        bevy::tasks::tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+
+    let end = app.world().resource::<SpaceTimeTable>().current_tick() + ticks;
+    let started = Instant::now();
+    while app.world().resource::<SpaceTimeTable>().current_tick() < end {
+        app.update();
+        if app.should_exit().is_some() {
+            break;
+        }
+    }
+
+    let report = SimulationReport::from_world(app.world_mut(), started.elapsed());
+    export_metrics(app.world());
+    export_snapshot(app.world_mut());
+    export_replay(app.world_mut());
+    report
+}
@@ -1,32 +1,54 @@
 use bevy::prelude::*;
-use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+use bevy::time::TimeSystems;
+use bevy_egui::{EguiContexts, EguiPlugin, EguiPrimaryContextPass};
 
-use crate::constants::{CELL_SIZE, GRID_HEIGHT, GRID_WIDTH};
-use crate::core::{WarehouseGrid, WarehouseZones};
-use crate::plugins::navigation::NavigationPlugin;
+use crate::constants::CELL_SIZE;
+use crate::core::{CellType, GridPos, WarehouseGrid, WarehouseZones};
+use crate::plugins::simulation::{SimulationCorePlugin, SimulationSettings};
+use crate::systems::inventory::Inventory;
+use crate::systems::report::report_on_exit_system;
+use crate::systems::speed::{apply_sim_speed_system, manual_ticks_system, SimSpeed};
 use crate::systems::ui::{supervisor_panel, UiState};
-use crate::systems::visualization::{draw_robot_paths, robot_color_system};
+use crate::systems::visualization::{
+    attach_robot_visuals, draw_robot_paths, robot_color_system, visual_interpolation_system,
+};
 
-pub struct WarehousePlugins;
+/// Simulation et rendu : l'application fenêtrée complète
+#[derive(Default)]
+pub struct WarehousePlugins {
+    pub settings: SimulationSettings,
+}
 
 impl Plugin for WarehousePlugins {
     fn build(&self, app: &mut App) {
-        // Créer zones d'abord
-        let zones = WarehouseZones::default();
+        app.add_plugins((
+            SimulationCorePlugin { settings: self.settings.clone() },
+            WarehouseRenderPlugin,
+        ))
+        .init_resource::<SimSpeed>()
+        .add_systems(First, apply_sim_speed_system.before(TimeSystems))
+        .add_systems(
+            RunFixedMainLoop,
+            manual_ticks_system.in_set(RunFixedMainLoopSystems::FixedMainLoop),
+        )
+        .add_systems(Update, speed_shortcuts)
+        .add_systems(Last, report_on_exit_system);
+    }
+}
 
-        // Créer grille avec racks comme obstacles
-        let mut grid = WarehouseGrid::default();
-        grid.apply_racks(&zones.racks);
+/// Rendu seul (caméra, meshes, gizmos, panneau egui), au-dessus de `SimulationCorePlugin`
+pub struct WarehouseRenderPlugin;
 
+impl Plugin for WarehouseRenderPlugin {
+    fn build(&self, app: &mut App) {
         app.add_plugins(EguiPlugin::default())
-            .insert_resource(zones)
-            .insert_resource(grid)
             .init_resource::<UiState>()
             .insert_resource(ClearColor(Color::srgb(0.92, 0.92, 0.92)))
-            .add_plugins(NavigationPlugin)
-            .add_systems(Startup, (setup_camera, setup_scene, spawn_racks))
+            .add_systems(Startup, (setup_camera, setup_scene, spawn_racks, spawn_obstacles))
             .add_systems(EguiPrimaryContextPass, supervisor_panel)
             .add_systems(Update, (
+                attach_robot_visuals,
+                visual_interpolation_system.after(attach_robot_visuals),
                 draw_grid,
                 draw_zones,
                 draw_robot_paths,
@@ -36,11 +58,11 @@ impl Plugin for WarehousePlugins {
     }
 }
 
-fn setup_camera(mut commands: Commands) {
+fn setup_camera(mut commands: Commands, grid: Res<WarehouseGrid>) {
     let grid_center = Vec3::new(
-        GRID_WIDTH as f32 * CELL_SIZE * 0.5,
+        grid.width() as f32 * CELL_SIZE * 0.5,
         0.0,
-        GRID_HEIGHT as f32 * CELL_SIZE * 0.5,
+        grid.height() as f32 * CELL_SIZE * 0.5,
     );
 
     commands.spawn((
@@ -54,6 +76,7 @@ fn setup_scene(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    grid: Res<WarehouseGrid>,
 ) {
     commands.insert_resource(AmbientLight {
         color: Color::WHITE,
@@ -71,8 +94,8 @@ fn setup_scene(
     ));
 
     let floor_size = Vec2::new(
-        GRID_WIDTH as f32 * CELL_SIZE,
-        GRID_HEIGHT as f32 * CELL_SIZE,
+        grid.width() as f32 * CELL_SIZE,
+        grid.height() as f32 * CELL_SIZE,
     );
 
     commands.spawn((
@@ -117,24 +140,56 @@ fn spawn_racks(
     }
 }
 
-fn draw_grid(mut gizmos: Gizmos) {
+/// Cellules bloquées (murs, piliers) : un cube par cellule
+fn spawn_obstacles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    grid: Res<WarehouseGrid>,
+) {
+    let height = 1.0;
+    let mesh = meshes.add(Cuboid::new(CELL_SIZE, height, CELL_SIZE));
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.35, 0.35, 0.38),
+        perceptual_roughness: 0.9,
+        ..default()
+    });
+
+    for y in 0..grid.height() as i32 {
+        for x in 0..grid.width() as i32 {
+            let pos = GridPos::new(x, y);
+            if grid.get(pos) != Some(CellType::Blocked) {
+                continue;
+            }
+
+            let (wx, wz) = grid.grid_to_world(pos);
+            commands.spawn((
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_xyz(wx, height * 0.5, wz),
+            ));
+        }
+    }
+}
+
+fn draw_grid(mut gizmos: Gizmos, grid: Res<WarehouseGrid>) {
     let color = Color::srgba(0.7, 0.7, 0.7, 0.2);
-    let w = GRID_WIDTH as f32 * CELL_SIZE;
-    let h = GRID_HEIGHT as f32 * CELL_SIZE;
+    let w = grid.width() as f32 * CELL_SIZE;
+    let h = grid.height() as f32 * CELL_SIZE;
     let y = 0.01;
 
-    for i in 0..=GRID_WIDTH {
+    for i in 0..=grid.width() {
         let x = i as f32 * CELL_SIZE;
         gizmos.line(Vec3::new(x, y, 0.0), Vec3::new(x, y, h), color);
     }
 
-    for i in 0..=GRID_HEIGHT {
+    for i in 0..=grid.height() {
         let z = i as f32 * CELL_SIZE;
         gizmos.line(Vec3::new(0.0, y, z), Vec3::new(w, y, z), color);
     }
 }
 
-fn draw_zones(mut gizmos: Gizmos, zones: Res<WarehouseZones>) {
+fn draw_zones(mut gizmos: Gizmos, zones: Res<WarehouseZones>, inventory: Res<Inventory>) {
     let y = 0.02;
 
     for &pos in &zones.spawn_points {
@@ -153,8 +208,16 @@ fn draw_zones(mut gizmos: Gizmos, zones: Res<WarehouseZones>) {
         gizmos.rect(
             Isometry3d::new(Vec3::new(x, y, z), Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
             Vec2::splat(CELL_SIZE * 0.5),
-            Color::srgba(0.2, 0.75, 0.3, 0.4),
+            stock_color(inventory.cell_fill(pos).unwrap_or(1.0)),
         );
+        // Storage réservé par une mission
+        if zones.is_reserved(pos) {
+            gizmos.rect(
+                Isometry3d::new(Vec3::new(x, y, z), Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+                Vec2::splat(CELL_SIZE * 0.25),
+                Color::srgb(0.1, 0.5, 0.2),
+            );
+        }
     }
 
     for &pos in &zones.cargo_cells {
@@ -165,13 +228,43 @@ fn draw_zones(mut gizmos: Gizmos, zones: Res<WarehouseZones>) {
             Vec2::splat(CELL_SIZE * 0.7),
             Color::srgba(0.95, 0.45, 0.2, 0.4),
         );
+        if zones.is_reserved(pos) {
+            gizmos.rect(
+                Isometry3d::new(Vec3::new(x, y, z), Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+                Vec2::splat(CELL_SIZE * 0.35),
+                Color::srgb(0.8, 0.3, 0.1),
+            );
+        }
+    }
+
+    for &pos in &zones.charger_cells {
+        let x = pos.x as f32 * CELL_SIZE + CELL_SIZE * 0.5;
+        let z = pos.y as f32 * CELL_SIZE + CELL_SIZE * 0.5;
+        gizmos.rect(
+            Isometry3d::new(Vec3::new(x, y, z), Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+            Vec2::splat(CELL_SIZE * 0.7),
+            Color::srgba(0.65, 0.33, 0.97, 0.4),
+        );
+        if zones.is_reserved(pos) {
+            gizmos.rect(
+                Isometry3d::new(Vec3::new(x, y, z), Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+                Vec2::splat(CELL_SIZE * 0.35),
+                Color::srgb(0.45, 0.15, 0.75),
+            );
+        }
     }
 }
 
+/// Vert quand l'emplacement est plein, rouge quand il est vide
+fn stock_color(fill: f32) -> Color {
+    let fill = fill.clamp(0.0, 1.0);
+    Color::srgba(0.9 - 0.7 * fill, 0.2 + 0.55 * fill, 0.2 + 0.1 * fill, 0.4)
+}
+
 fn camera_controls(
     keyboard: Res<ButtonInput<KeyCode>>,
     mut camera: Query<&mut Transform, With<Camera3d>>,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
 ) {
     let Ok(mut transform) = camera.single_mut() else { return };
 
@@ -185,4 +278,36 @@ fn camera_controls(
     if keyboard.pressed(KeyCode::KeyD) { transform.translation += right * speed; }
     if keyboard.pressed(KeyCode::KeyQ) { transform.translation.y += speed; }
     if keyboard.pressed(KeyCode::KeyE) { transform.translation.y -= speed; }
-}
\ No newline at end of file
+}
+
+/// Espace : pause ; `.` : un tick, `step_size` avec Maj ; `+`/`-` : vitesse ; M : vitesse max
+fn speed_shortcuts(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut speed: ResMut<SimSpeed>,
+) -> Result {
+    // Laisse les saisies du panneau tranquilles
+    if contexts.ctx_mut()?.wants_keyboard_input() {
+        return Ok(());
+    }
+
+    if keyboard.just_pressed(KeyCode::Space) {
+        speed.toggle_pause();
+    }
+    if keyboard.just_pressed(KeyCode::Period) {
+        let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let ticks = if shift { speed.step_size } else { 1 };
+        speed.step(ticks);
+    }
+    if keyboard.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        speed.faster();
+    }
+    if keyboard.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        speed.slower();
+    }
+    if keyboard.just_pressed(KeyCode::KeyM) {
+        let enabled = !speed.max_speed;
+        speed.set_max_speed(enabled);
+    }
+    Ok(())
+}
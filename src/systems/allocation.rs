@@ -0,0 +1,598 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::components::{
+    ActionTimer, Battery, GridPosition, Loaded, Mission, MissionPhase, NextTask, OrderLine, Robot,
+    RobotState, State,
+};
+use crate::constants::{ALLOCATION_DISTANCE_CACHE, ALLOCATION_INTERVAL, ALLOCATION_LOOKAHEAD};
+use crate::core::{DistanceField, GridPos, HighwayGraph, SimRng, SpaceTimeTable, WarehouseGrid, WarehouseZones};
+use crate::systems::inventory::Inventory;
+use crate::systems::metrics::SimMetrics;
+use crate::systems::orders::{OrderBook, OrderConfig};
+use crate::systems::spawner::ChargingConfig;
+
+/// Coûts robot × tâche en pas sur la grille, `None` si la tâche est inatteignable
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostMatrix {
+    rows: usize,
+    cols: usize,
+    costs: Vec<Option<u32>>,
+}
+
+impl CostMatrix {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self { rows, cols, costs: vec![None; rows * cols] }
+    }
+
+    /// Une ligne par robot, toutes de même longueur
+    pub fn from_rows(rows: &[Vec<Option<u32>>]) -> Self {
+        let cols = rows.first().map_or(0, Vec::len);
+        let mut matrix = Self::new(rows.len(), cols);
+        for (r, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), cols, "cost rows must have the same length");
+            matrix.costs[r * cols..(r + 1) * cols].copy_from_slice(row);
+        }
+        matrix
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    #[inline]
+    pub fn get(&self, row: usize, col: usize) -> Option<u32> {
+        self.costs[row * self.cols + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, cost: Option<u32>) {
+        self.costs[row * self.cols + col] = cost;
+    }
+
+    /// Coût total d'une affectation
+    pub fn total(&self, assignment: &[Option<usize>]) -> u64 {
+        assignment
+            .iter()
+            .enumerate()
+            .filter_map(|(r, col)| col.and_then(|c| self.get(r, c)))
+            .map(u64::from)
+            .sum()
+    }
+}
+
+/// Stratégie d'affectation des tâches aux robots, interchangeable
+pub trait TaskAllocator: Send + Sync + 'static {
+    /// Nom unique, utilisé pour la sélection (CLI, configuration)
+    fn name(&self) -> &str;
+
+    /// Tâche de chaque robot, au plus un robot par tâche et jamais une tâche inatteignable
+    fn assign(&mut self, costs: &CostMatrix) -> Vec<Option<usize>>;
+}
+
+/// Chaque robot, dans l'ordre, prend la tâche libre la plus proche
+pub struct NearestFirst;
+
+impl TaskAllocator for NearestFirst {
+    fn name(&self) -> &str {
+        "nearest"
+    }
+
+    fn assign(&mut self, costs: &CostMatrix) -> Vec<Option<usize>> {
+        let mut taken = vec![false; costs.cols()];
+        (0..costs.rows())
+            .map(|r| {
+                let col = (0..costs.cols())
+                    .filter(|&c| !taken[c])
+                    .filter_map(|c| costs.get(r, c).map(|cost| (cost, c)))
+                    .min()
+                    .map(|(_, c)| c)?;
+                taken[col] = true;
+                Some(col)
+            })
+            .collect()
+    }
+}
+
+/// Coût d'une paire inatteignable : domine toute somme de coûts réels
+const FORBIDDEN: i64 = 1 << 40;
+
+/// Méthode hongroise (Kuhn-Munkres) : coût total minimal, en O(n²m)
+pub struct Hungarian;
+
+impl TaskAllocator for Hungarian {
+    fn name(&self) -> &str {
+        "hungarian"
+    }
+
+    fn assign(&mut self, costs: &CostMatrix) -> Vec<Option<usize>> {
+        let (rows, cols) = (costs.rows(), costs.cols());
+        let cost = |r: usize, c: usize| costs.get(r, c).map_or(FORBIDDEN, i64::from);
+        let mut assignment = vec![None; rows];
+        // L'algorithme veut au moins autant de colonnes que de lignes
+        if rows <= cols {
+            for (c, r) in hungarian(rows, cols, cost).into_iter().enumerate() {
+                if let Some(r) = r {
+                    assignment[r] = Some(c);
+                }
+            }
+        } else {
+            for (r, c) in hungarian(cols, rows, |c, r| cost(r, c)).into_iter().enumerate() {
+                if let Some(c) = c {
+                    assignment[r] = Some(c);
+                }
+            }
+        }
+        // Les paires interdites ne servaient qu'à compléter l'affectation
+        for (r, col) in assignment.iter_mut().enumerate() {
+            if col.is_some_and(|c| costs.get(r, c).is_none()) {
+                *col = None;
+            }
+        }
+        assignment
+    }
+}
+
+/// Affectation de coût minimal des `n` lignes à `m >= n` colonnes, par potentiels.
+/// Retourne la ligne de chaque colonne.
+fn hungarian(n: usize, m: usize, cost: impl Fn(usize, usize) -> i64) -> Vec<Option<usize>> {
+    // Indices décalés de un : la colonne 0 est fictive
+    let mut u = vec![0i64; n + 1];
+    let mut v = vec![0i64; m + 1];
+    let mut row_of = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+    for i in 1..=n {
+        row_of[0] = i;
+        let mut j0 = 0;
+        let mut min_v = vec![i64::MAX; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = row_of[j0];
+            let mut delta = i64::MAX;
+            let mut j1 = 0;
+            for j in 1..=m {
+                if used[j] {
+                    continue;
+                }
+                let reduced = cost(i0 - 1, j - 1) - u[i0] - v[j];
+                if reduced < min_v[j] {
+                    min_v[j] = reduced;
+                    way[j] = j0;
+                }
+                if min_v[j] < delta {
+                    delta = min_v[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_v[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if row_of[j0] == 0 {
+                break;
+            }
+        }
+        // Remonte le chemin augmentant
+        loop {
+            let j1 = way[j0];
+            row_of[j0] = row_of[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+    row_of[1..].iter().map(|&i| i.checked_sub(1)).collect()
+}
+
+/// Enchères (Bertsekas) : chaque robot libre enchérit sur sa meilleure tâche, de l'écart
+/// avec la deuxième plus un incrément, et reprend la main s'il est surenchéri.
+/// Décentralisable, et optimale avec un incrément sous un pas par robot.
+pub struct Auction;
+
+impl TaskAllocator for Auction {
+    fn name(&self) -> &str {
+        "auction"
+    }
+
+    fn assign(&mut self, costs: &CostMatrix) -> Vec<Option<usize>> {
+        let (rows, cols) = (costs.rows(), costs.cols());
+        // Coûts multipliés par n + 1 : un incrément de 1 reste sous un pas en tout
+        let n = rows.max(cols);
+        let scale = n as i64 + 1;
+        let max_cost = (0..rows)
+            .flat_map(|r| (0..cols).filter_map(move |c| costs.get(r, c)))
+            .max()
+            .unwrap_or(0) as i64;
+        // Rester libre : pire que tout réarrangement des autres robots
+        let idle = -(n as i64 * (max_cost + 1) + 1) * scale;
+        let value = |r: usize, c: usize| costs.get(r, c).map(|cost| -(cost as i64) * scale);
+
+        if rows < cols {
+            // Tâches en surplus : une seule phase, les tâches jamais disputées restent à prix nul
+            let mut prices = vec![0i64; cols];
+            return auction_phase(rows, &mut prices, 1, idle, value);
+        }
+
+        // Robots en surplus : problème carré, des tâches fictives « rester libre » complètent
+        // les colonnes, et l'incrément décroît d'une phase à l'autre pour éviter les guerres
+        // de prix
+        let mut prices = vec![0i64; rows];
+        let padded = |r: usize, c: usize| Some(if c < cols { value(r, c).unwrap_or(idle) } else { idle });
+        let mut epsilon = (-idle / 4).max(1);
+        loop {
+            let assignment = auction_phase(rows, &mut prices, epsilon, i64::MIN, padded);
+            if epsilon == 1 {
+                return assignment
+                    .into_iter()
+                    .enumerate()
+                    .map(|(r, c)| c.filter(|&c| c < cols && costs.get(r, c).is_some()))
+                    .collect();
+            }
+            epsilon = (epsilon / 4).max(1);
+        }
+    }
+}
+
+/// Phase d'enchères jusqu'à ce que chaque robot ait une tâche ou y renonce, quand sa
+/// meilleure valeur passe sous `floor`. Retourne la tâche de chaque robot.
+fn auction_phase(
+    bidders: usize,
+    prices: &mut [i64],
+    epsilon: i64,
+    floor: i64,
+    value: impl Fn(usize, usize) -> Option<i64>,
+) -> Vec<Option<usize>> {
+    let mut owner: Vec<Option<usize>> = vec![None; prices.len()];
+    let mut task_of: Vec<Option<usize>> = vec![None; bidders];
+    let mut queue: VecDeque<usize> = (0..bidders).collect();
+    while let Some(r) = queue.pop_front() {
+        let mut best: Option<(i64, usize)> = None;
+        let mut second = floor;
+        for (c, price) in prices.iter().enumerate() {
+            let Some(value) = value(r, c).map(|v| v - price) else {
+                continue;
+            };
+            match best {
+                Some((best_value, _)) if value <= best_value => second = second.max(value),
+                _ => {
+                    if let Some((best_value, _)) = best {
+                        second = second.max(best_value);
+                    }
+                    best = Some((value, c));
+                }
+            }
+        }
+        let Some((value, c)) = best.filter(|&(value, _)| value >= floor) else {
+            continue;
+        };
+        // Seul enchérisseur possible : l'incrément suffit
+        let gap = if second == i64::MIN { 0 } else { value - second };
+        prices[c] += gap + epsilon;
+        if let Some(previous) = owner[c].replace(r) {
+            task_of[previous] = None;
+            queue.push_back(previous);
+        }
+        task_of[r] = Some(c);
+    }
+    task_of
+}
+
+/// Allocateurs disponibles. Sans allocateur actif, chaque robot libre prend aussitôt le premier
+/// storage disponible.
+#[derive(Resource)]
+pub struct AllocatorRegistry {
+    allocators: Vec<Box<dyn TaskAllocator>>,
+    active: Option<usize>,
+}
+
+impl Default for AllocatorRegistry {
+    fn default() -> Self {
+        let mut registry = Self { allocators: Vec::new(), active: None };
+        registry.register(NearestFirst);
+        registry.register(Hungarian);
+        registry.register(Auction);
+        registry
+    }
+}
+
+impl AllocatorRegistry {
+    /// Ajoute un allocateur, ou remplace celui qui porte le même nom
+    pub fn register(&mut self, allocator: impl TaskAllocator) {
+        let allocator: Box<dyn TaskAllocator> = Box::new(allocator);
+        match self.allocators.iter().position(|a| a.name() == allocator.name()) {
+            Some(i) => self.allocators[i] = allocator,
+            None => self.allocators.push(allocator),
+        }
+    }
+
+    /// Active l'allocateur `name`, ou revient à l'attribution immédiate avec `None` ;
+    /// `false` si `name` n'est pas enregistré
+    pub fn select(&mut self, name: Option<&str>) -> bool {
+        let Some(name) = name else {
+            self.active = None;
+            return true;
+        };
+        match self.allocators.iter().position(|a| a.name() == name) {
+            Some(i) => {
+                self.active = Some(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn active_name(&self) -> Option<&str> {
+        self.active.map(|i| self.allocators[i].name())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.allocators.iter().map(|a| a.name())
+    }
+
+    /// Affectation par l'allocateur actif, aucune sans allocateur
+    pub fn assign(&mut self, costs: &CostMatrix) -> Vec<Option<usize>> {
+        match self.active {
+            Some(i) => self.allocators[i].assign(costs),
+            None => vec![None; costs.rows()],
+        }
+    }
+}
+
+/// Enregistrement d'un allocateur depuis l'extérieur du crate
+pub trait AllocatorAppExt {
+    fn register_allocator(&mut self, allocator: impl TaskAllocator) -> &mut Self;
+}
+
+impl AllocatorAppExt for App {
+    fn register_allocator(&mut self, allocator: impl TaskAllocator) -> &mut Self {
+        self.init_resource::<AllocatorRegistry>();
+        self.world_mut().resource_mut::<AllocatorRegistry>().register(allocator);
+        self
+    }
+}
+
+/// Cadence des affectations groupées
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct AllocationConfig {
+    /// Ticks entre deux affectations ; un robot qui termine son dépôt dans ce délai est
+    /// affecté à l'avance
+    pub interval: u64,
+    /// Tâches examinées par robot à affecter : lignes en attente, ou storages libres les
+    /// plus proches hors flux de commandes
+    pub lookahead: usize,
+}
+
+impl Default for AllocationConfig {
+    fn default() -> Self {
+        Self { interval: ALLOCATION_INTERVAL, lookahead: ALLOCATION_LOOKAHEAD }
+    }
+}
+
+/// Libère les cellules d'une tâche affectée à l'avance et remet sa ligne en attente
+pub fn release_task(zones: &mut WarehouseZones, orders: &mut OrderBook, task: &NextTask) {
+    zones.release_storage(task.storage);
+    zones.release_cargo(task.cargo);
+    orders.requeue(task.order);
+}
+
+/// Tâche à affecter : un storage, et la ligne de commande qu'il sert en flux de commandes
+struct Task {
+    storage: GridPos,
+    line: Option<OrderLine>,
+}
+
+/// Champs de distance par cible, limités à `ALLOCATION_DISTANCE_CACHE` et vidés quand la
+/// grille ou les couloirs changent
+#[derive(Default)]
+pub struct DistanceCache {
+    /// Champ et dernier accès
+    fields: FxHashMap<GridPos, (DistanceField, u64)>,
+    accesses: u64,
+    revision: (u64, u64),
+}
+
+impl DistanceCache {
+    /// Pas de `from` jusqu'à `to`
+    pub fn steps(&mut self, grid: &WarehouseGrid, highways: &HighwayGraph, from: GridPos, to: GridPos) -> Option<u32> {
+        let revision = (grid.revision(), highways.revision());
+        if self.revision != revision {
+            self.fields.clear();
+            self.revision = revision;
+        }
+        if !self.fields.contains_key(&to) && self.fields.len() >= ALLOCATION_DISTANCE_CACHE {
+            let oldest = self.fields.iter().min_by_key(|(_, (_, used))| *used).map(|(&pos, _)| pos);
+            if let Some(oldest) = oldest {
+                self.fields.remove(&oldest);
+            }
+        }
+
+        self.accesses += 1;
+        let (field, used) = self
+            .fields
+            .entry(to)
+            .or_insert_with(|| (DistanceField::towards(to, grid, highways), 0));
+        *used = self.accesses;
+        field.get(from)
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// Robot vu par l'allocateur
+type AllocationCandidate = (
+    Entity,
+    &'static GridPosition,
+    &'static Battery,
+    &'static State,
+    &'static Loaded,
+    Option<&'static Mission>,
+    Option<&'static ActionTimer>,
+    Has<NextTask>,
+);
+
+/// Affectation groupée, tous les `interval` ticks, des tâches en attente aux robots libres
+/// ou qui terminent leur dépôt, par l'allocateur actif sur les distances réelles de la
+/// grille. Chaque robot affecté reçoit une `NextTask` que l'avancement des missions lui
+/// fait prendre dès qu'il est libre.
+#[allow(clippy::too_many_arguments)]
+pub fn task_allocation_system(
+    mut commands: Commands,
+    robots: Query<AllocationCandidate, With<Robot>>,
+    mut registry: ResMut<AllocatorRegistry>,
+    mut zones: ResMut<WarehouseZones>,
+    mut orders: ResMut<OrderBook>,
+    mut rng: ResMut<SimRng>,
+    mut metrics: ResMut<SimMetrics>,
+    mut fields: Local<DistanceCache>,
+    config: Res<AllocationConfig>,
+    order_config: Res<OrderConfig>,
+    inventory: Res<Inventory>,
+    charging: Res<ChargingConfig>,
+    grid: Res<WarehouseGrid>,
+    highways: Res<HighwayGraph>,
+    space_time: Res<SpaceTimeTable>,
+) {
+    let tick = space_time.current_tick();
+    if !registry.is_active() || !tick.is_multiple_of(config.interval.max(1)) {
+        return;
+    }
+
+    // Batterie basse : le robot ira au chargeur s'il en reste un de libre
+    let charger_free = zones.charger_cells.iter().any(|&pos| !zones.is_reserved(pos));
+    let mut candidates: Vec<(Entity, GridPos)> = robots
+        .iter()
+        .filter(|&(_, _, battery, state, loaded, mission, timer, has_next)| {
+            if has_next || (battery.0 < charging.threshold && charger_free) {
+                return false;
+            }
+            match state.0 {
+                RobotState::Idle => !loaded.0,
+                RobotState::Unloading => {
+                    mission.is_some_and(|m| m.phase == MissionPhase::DroppingOff)
+                        && timer.is_some_and(|t| t.remaining <= config.interval)
+                }
+                _ => false,
+            }
+        })
+        .map(|(entity, pos, ..)| (entity, pos.0))
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+    candidates.sort_unstable_by_key(|(entity, _)| entity.index());
+
+    let tasks: Vec<Task> = if order_config.is_continuous() {
+        // Chaque robot garde ses `lookahead` storages libres les plus proches en distance de
+        // Manhattan : la matrice reste à la taille de la flotte, pas de l'entrepôt
+        let free: Vec<GridPos> = zones
+            .storage_cells
+            .iter()
+            .copied()
+            .filter(|&pos| !zones.is_reserved(pos) && inventory.is_stocked(pos))
+            .collect();
+        let k = config.lookahead.max(1);
+        let mut selected = vec![false; free.len()];
+        for &(_, pos) in &candidates {
+            let mut open: Vec<usize> = (0..free.len()).filter(|&i| !selected[i]).collect();
+            if open.len() > k {
+                open.select_nth_unstable_by_key(k, |&i| (free[i].manhattan_distance(&pos), i));
+                open.truncate(k);
+            }
+            for i in open {
+                selected[i] = true;
+            }
+        }
+        free.into_iter()
+            .zip(selected)
+            .filter(|&(_, selected)| selected)
+            .map(|(storage, _)| Task { storage, line: None })
+            .collect()
+    } else {
+        // Plus anciennes lignes d'abord, chacune sur le storage de sa référence le plus
+        // proche de son quai ; un seul champ par quai, lu à chaque storage candidat
+        let docks = zones.docks();
+        let mut claimed = FxHashSet::default();
+        let mut tasks = Vec::new();
+        for &line in &orders.pending {
+            if tasks.len() >= candidates.len() * config.lookahead.max(1) {
+                break;
+            }
+            let Some(&dock) = docks.get(line.dock).and_then(|dock| dock.first()) else {
+                continue;
+            };
+            let storage = inventory
+                .stocked_cells(line.sku)
+                .into_iter()
+                .filter(|&pos| !zones.is_reserved(pos) && !claimed.contains(&pos))
+                .filter_map(|pos| fields.steps(&grid, &highways, pos, dock).map(|d| (d, pos)))
+                .min_by_key(|&(d, pos)| (d, pos.x, pos.y))
+                .map(|(_, pos)| pos);
+            if let Some(storage) = storage {
+                claimed.insert(storage);
+                tasks.push(Task { storage, line: Some(line) });
+            }
+        }
+        tasks
+    };
+    if tasks.is_empty() {
+        return;
+    }
+
+    // Colonne par colonne : le champ de chaque tâche n'est calculé qu'une fois par
+    // affectation, même quand les tâches dépassent la taille du cache
+    let mut costs = CostMatrix::new(candidates.len(), tasks.len());
+    for (c, task) in tasks.iter().enumerate() {
+        for (r, &(_, pos)) in candidates.iter().enumerate() {
+            costs.set(r, c, fields.steps(&grid, &highways, pos, task.storage));
+        }
+    }
+    let assignment = registry.assign(&costs);
+
+    let docks = if order_config.is_continuous() { Vec::new() } else { zones.docks() };
+    for (r, c) in assignment.into_iter().enumerate() {
+        let Some(c) = c else {
+            continue;
+        };
+        let (entity, _) = candidates[r];
+        let task = &tasks[c];
+        let Some(storage) = zones.reserve_storage_near(&[task.storage], task.storage) else {
+            continue;
+        };
+        let cargo = match task.line {
+            Some(line) => zones.reserve_cargo_near(&docks[line.dock], storage),
+            None => zones.reserve_cargo(&mut rng),
+        };
+        let Some(cargo) = cargo else {
+            zones.release_storage(storage);
+            continue;
+        };
+        if let Some(line) = task.line {
+            orders.take(line);
+        }
+        commands.entity(entity).insert(NextTask { storage, cargo, order: task.line });
+        metrics.assignments += 1;
+        metrics.assignment_steps += costs.get(r, c).unwrap_or(0) as u64;
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    ActionTimer, Battery, BatteryModel, BatteryUsage, Breakdown, FaultCause, GridPosition, Loaded,
+    Mission, NextTask, PlannedPath, Robot, RobotState, State,
+};
+use crate::constants::{BATTERY_SWAP_SECS, TICK_DELTA};
+use crate::core::{SpaceTimeTable, WarehouseZones};
+use crate::systems::allocation::release_task;
+use crate::systems::events::{SimEvent, SimEventKind};
+use crate::systems::faults::secs_to_ticks;
+use crate::systems::metrics::SimMetrics;
+use crate::systems::orders::OrderBook;
+use crate::systems::pbs::PbsConfig;
+use crate::systems::planner::{stop_position, PlannerRegistry};
+use crate::systems::spawner::release_mission;
+
+/// Modèles de robots de la flotte, attribués à tour de rôle aux robots créés
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RobotModels {
+    pub models: Vec<BatteryModel>,
+}
+
+impl Default for RobotModels {
+    fn default() -> Self {
+        Self { models: vec![BatteryModel::default()] }
+    }
+}
+
+impl RobotModels {
+    /// Liste RON de modèles, par exemple `[(name: "heavy", capacity_wh: 1500.0)]`
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// Modèle du `index`-ième robot créé
+    pub fn model(&self, index: u32) -> BatteryModel {
+        self.models
+            .get(index as usize % self.models.len().max(1))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Robot vu par la consommation de batterie
+type BatteryUser = (
+    Entity,
+    &'static GridPosition,
+    &'static Loaded,
+    &'static mut State,
+    &'static mut Battery,
+    &'static BatteryModel,
+    &'static mut BatteryUsage,
+    Option<&'static Mission>,
+    Option<&'static PlannedPath>,
+    Option<&'static NextTask>,
+);
+
+/// Bilan énergétique par tick fixe : chaque cellule parcourue coûte `move_energy`, la
+/// consommation permanente court sur `TICK_DELTA`, et la charge suit la courbe du modèle.
+/// Rien ne dépend de l'horloge ni de la vitesse interpolée pour l'affichage.
+///
+/// Un robot à batterie vide passe en panne et libère les réservations de sa mission, sauf
+/// la cellule où il s'arrête : il suit son chemin réservé jusqu'à la replanification, puis
+/// devient un obstacle jusqu'au remplacement de sa batterie. La tâche que l'allocateur lui
+/// réservait est rendue.
+#[allow(clippy::too_many_arguments)]
+pub fn battery_consumption_system(
+    mut commands: Commands,
+    mut robots: Query<BatteryUser, With<Robot>>,
+    mut zones: ResMut<WarehouseZones>,
+    mut orders: ResMut<OrderBook>,
+    mut events: MessageWriter<SimEvent>,
+    mut metrics: ResMut<SimMetrics>,
+    space_time: Res<SpaceTimeTable>,
+    registry: Res<PlannerRegistry>,
+    config: Res<PbsConfig>,
+) {
+    let hours = TICK_DELTA as f64 / 3600.0;
+    for (entity, pos, loaded, mut state, mut battery, model, mut usage, mission, path, next) in &mut robots {
+        let moved = usage.last_cell.is_some_and(|cell| cell != pos.0);
+        usage.last_cell = Some(pos.0);
+        if state.0 == RobotState::Fault {
+            continue;
+        }
+
+        // En f64 : la consommation permanente d'un tick est de l'ordre de 1e-7 de la capacité
+        let capacity = model.capacity(&usage) as f64;
+        let stored = battery.0 as f64 * capacity;
+        if state.0 == RobotState::Charging {
+            // Le chargeur alimente aussi l'électronique
+            let energy = (model.charge_power(battery.0) as f64 * hours).min(capacity - stored);
+            battery.0 = ((stored + energy) / capacity).min(1.0) as f32;
+            metrics.energy_charged_wh += energy;
+        } else {
+            let mut energy = model.standby_w as f64 * hours;
+            if moved {
+                energy += model.move_energy(loaded.0) as f64;
+            }
+            let energy = energy.min(stored);
+            battery.0 = ((stored - energy) / capacity).max(0.0) as f32;
+            usage.discharged_wh += energy;
+            metrics.energy_used_wh += energy;
+        }
+
+        if battery.0 <= 0.0 {
+            warn!("Battery depleted: {:?} at {:?}", entity, pos.0);
+            let tick = space_time.current_tick();
+            let blocked = mission.and_then(|mission| {
+                let parked = path.map_or(pos.0, |path| stop_position(pos.0, path, tick, &registry, &config));
+                release_mission(&mut zones, &mut orders, mission, state.0, Some(parked))
+            });
+            if let Some(task) = next {
+                release_task(&mut zones, &mut orders, task);
+                commands.entity(entity).remove::<NextTask>();
+            }
+            state.0 = RobotState::Fault;
+            let repair_at = tick + secs_to_ticks(BATTERY_SWAP_SECS as f64);
+            commands
+                .entity(entity)
+                .insert(Breakdown { cause: FaultCause::BatteryDepleted, since: tick, repair_at, blocked })
+                .remove::<ActionTimer>();
+            events.write(SimEvent::new(tick, entity, pos.0, SimEventKind::BatteryDepleted));
+        }
+    }
+}
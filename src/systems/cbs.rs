@@ -0,0 +1,578 @@
+use bevy::prelude::Entity;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use crate::core::{GridPos, HighwayGraph, SpaceTimeTable, WarehouseGrid};
+use crate::systems::pbs::{Path, PbsConfig, PriorityTree, StaticObstacles};
+use crate::systems::planner::{MultiAgentPlanner, PlanningAgent, PlanningOutcome, PlanningProblem};
+
+/// Coût d'une attente sur place, identique à celui de `PbsPlanner`
+const WAIT_COST: f32 = 0.5;
+const MAX_LOW_LEVEL_EXPANSIONS: usize = 15000;
+
+/// Coût flottant totalement ordonné, pour les clés des ensembles triés
+#[derive(Clone, Copy, PartialEq)]
+struct Cost(f32);
+
+impl Eq for Cost {}
+
+impl Ord for Cost {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Cost {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Contrainte imposée à un agent par une branche de l'arbre
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Constraint {
+    /// L'agent ne doit pas être sur `pos` à `tick`
+    Vertex { pos: GridPos, tick: u64 },
+    /// L'agent ne doit pas aller de `from` à `to` entre `tick` et `tick + 1`
+    Edge { from: GridPos, to: GridPos, tick: u64 },
+}
+
+#[derive(Default)]
+struct AgentConstraints {
+    vertices: FxHashSet<(GridPos, u64)>,
+    edges: FxHashSet<(GridPos, GridPos, u64)>,
+}
+
+impl AgentConstraints {
+    fn of(constraints: &[(usize, Constraint)], agent: usize) -> Self {
+        let mut out = Self::default();
+        for &(_, c) in constraints.iter().filter(|(a, _)| *a == agent) {
+            match c {
+                Constraint::Vertex { pos, tick } => {
+                    out.vertices.insert((pos, tick));
+                }
+                Constraint::Edge { from, to, tick } => {
+                    out.edges.insert((from, to, tick));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Cellules et arêtes utilisées par les autres agents, pour départager les nœuds
+/// du focal par nombre de conflits
+#[derive(Default)]
+struct ConflictTable {
+    cells: FxHashMap<(GridPos, u64), u32>,
+    edges: FxHashSet<(GridPos, GridPos, u64)>,
+}
+
+impl ConflictTable {
+    fn count(&self, from: GridPos, to: GridPos, tick: u64) -> u32 {
+        let vertex = self.cells.get(&(to, tick + 1)).copied().unwrap_or(0);
+        let swap = (from != to && self.edges.contains(&(to, from, tick))) as u32;
+        vertex + swap
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ConflictKind {
+    Vertex { pos: GridPos },
+    Edge { from: GridPos, to: GridPos },
+}
+
+/// Premier conflit entre deux agents ; pour un échange, `from`/`to` est le mouvement de `a`
+#[derive(Clone, Copy)]
+struct Conflict {
+    a: usize,
+    b: usize,
+    tick: u64,
+    kind: ConflictKind,
+}
+
+/// Nœud de l'arbre de contraintes
+#[derive(Clone)]
+struct ConstraintNode {
+    constraints: Vec<(usize, Constraint)>,
+    paths: Vec<Path>,
+    costs: Vec<f32>,
+    lower_bounds: Vec<f32>,
+    cost: f32,
+    lower_bound: f32,
+    conflicts: usize,
+}
+
+struct LowNode {
+    pos: GridPos,
+    tick: u64,
+    g: f32,
+    f: f32,
+    conflicts: u32,
+    parent: Option<usize>,
+}
+
+/// Chemin trouvé par le niveau bas, avec son coût et une borne inférieure de l'optimum
+struct LowLevelPath {
+    path: Path,
+    cost: f32,
+    lower_bound: f32,
+}
+
+/// Conflict-Based Search : arbre binaire de contraintes de sommet et d'arête.
+/// Avec un facteur de sous-optimalité `w > 1`, devient ECBS : recherche focale aux
+/// deux niveaux, qui préfère les nœuds ayant le moins de conflits parmi ceux dont
+/// le coût reste sous `w` fois la borne inférieure.
+pub struct CbsSolver<'a> {
+    grid: &'a WarehouseGrid,
+    highways: &'a HighwayGraph,
+    base: &'a SpaceTimeTable,
+    static_obstacles: &'a StaticObstacles,
+    config: &'a PbsConfig,
+    start_tick: u64,
+    suboptimality: f32,
+}
+
+impl<'a> CbsSolver<'a> {
+    pub fn new(
+        grid: &'a WarehouseGrid,
+        highways: &'a HighwayGraph,
+        base: &'a SpaceTimeTable,
+        static_obstacles: &'a StaticObstacles,
+        config: &'a PbsConfig,
+        start_tick: u64,
+    ) -> Self {
+        Self {
+            grid,
+            highways,
+            base,
+            static_obstacles,
+            config,
+            start_tick,
+            suboptimality: 1.0,
+        }
+    }
+
+    pub fn from_problem(problem: &PlanningProblem<'a>) -> Self {
+        Self::new(
+            problem.grid,
+            problem.highways,
+            problem.reservations,
+            problem.static_obstacles,
+            problem.config,
+            problem.start_tick,
+        )
+    }
+
+    /// Variante bornée (ECBS) : le coût trouvé reste sous `w` fois l'optimum
+    pub fn with_suboptimality(mut self, w: f32) -> Self {
+        self.suboptimality = w.max(1.0);
+        self
+    }
+
+    /// Cherche des chemins sans conflit ; `None` si le budget de nœuds est épuisé
+    pub fn solve(&self, agents: &[PlanningAgent]) -> Option<Vec<Path>> {
+        let mut root = ConstraintNode {
+            constraints: Vec::new(),
+            paths: Vec::with_capacity(agents.len()),
+            costs: Vec::with_capacity(agents.len()),
+            lower_bounds: Vec::with_capacity(agents.len()),
+            cost: 0.0,
+            lower_bound: 0.0,
+            conflicts: 0,
+        };
+        for i in 0..agents.len() {
+            let found = self.plan_agent(agents, &root.paths, &root.constraints, i);
+            root.paths.push(found.path);
+            root.costs.push(found.cost);
+            root.lower_bounds.push(found.lower_bound);
+        }
+        self.refresh(agents, &mut root);
+
+        let mut open = vec![root];
+        let mut expanded = 0;
+
+        while let Some(index) = self.select(&open) {
+            let node = open.swap_remove(index);
+            expanded += 1;
+            if expanded > self.config.max_tree_nodes {
+                return None;
+            }
+
+            let Some(conflict) = self.first_conflict(agents, &node.paths) else {
+                return Some(node.paths);
+            };
+
+            for (agent, constraint) in Self::split(&conflict) {
+                let mut child = node.clone();
+                child.constraints.push((agent, constraint));
+                let found = self.plan_agent(agents, &child.paths, &child.constraints, agent);
+
+                // La contrainte n'a pas pu être respectée : branche morte
+                if self.violates(&found.path, constraint) {
+                    continue;
+                }
+
+                child.paths[agent] = found.path;
+                child.costs[agent] = found.cost;
+                child.lower_bounds[agent] = found.lower_bound;
+                self.refresh(agents, &mut child);
+                open.push(child);
+            }
+        }
+
+        None
+    }
+
+    /// Nœud suivant : meilleur coût pour CBS, moins de conflits dans le focal pour ECBS
+    fn select(&self, open: &[ConstraintNode]) -> Option<usize> {
+        let (lowest, min_bound) = open
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (i, n.lower_bound))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+        if self.suboptimality <= 1.0 {
+            return open
+                .iter()
+                .enumerate()
+                .min_by(|(_, x), (_, y)| {
+                    x.cost.total_cmp(&y.cost).then(x.conflicts.cmp(&y.conflicts))
+                })
+                .map(|(i, _)| i);
+        }
+
+        // Focal vide (coût au-delà de la borne partout) : on reprend le nœud de plus petite borne
+        let bound = min_bound * self.suboptimality;
+        let focal = open
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.cost <= bound)
+            .min_by(|(_, x), (_, y)| {
+                x.conflicts.cmp(&y.conflicts).then(x.cost.total_cmp(&y.cost))
+            })
+            .map(|(i, _)| i);
+        Some(focal.unwrap_or(lowest))
+    }
+
+    fn refresh(&self, agents: &[PlanningAgent], node: &mut ConstraintNode) {
+        node.cost = node.costs.iter().sum();
+        node.lower_bound = node.lower_bounds.iter().sum();
+        node.conflicts = self.count_conflicts(agents, &node.paths);
+    }
+
+    /// Les deux contraintes qui résolvent un conflit, une par agent
+    fn split(conflict: &Conflict) -> [(usize, Constraint); 2] {
+        let tick = conflict.tick;
+        match conflict.kind {
+            ConflictKind::Vertex { pos } => [
+                (conflict.a, Constraint::Vertex { pos, tick }),
+                (conflict.b, Constraint::Vertex { pos, tick }),
+            ],
+            ConflictKind::Edge { from, to } => [
+                (conflict.a, Constraint::Edge { from, to, tick }),
+                (conflict.b, Constraint::Edge { from: to, to: from, tick }),
+            ],
+        }
+    }
+
+    fn violates(&self, path: &Path, constraint: Constraint) -> bool {
+        match constraint {
+            Constraint::Vertex { pos, tick } => {
+                let end = path.last().map_or(self.start_tick, |&(_, t)| t);
+                path.contains(&(pos, tick))
+                    || (tick > end && path.last().is_some_and(|&(p, _)| p == pos))
+            }
+            Constraint::Edge { from, to, tick } => path
+                .windows(2)
+                .any(|w| w[0] == (from, tick) && w[1] == (to, tick + 1)),
+        }
+    }
+
+    /// Space-time A* sous contraintes, en recherche focale si `suboptimality > 1`
+    fn plan_agent(
+        &self,
+        agents: &[PlanningAgent],
+        paths: &[Path],
+        constraints: &[(usize, Constraint)],
+        agent: usize,
+    ) -> LowLevelPath {
+        let PlanningAgent { entity, start, goal, .. } = agents[agent];
+        let constraints = AgentConstraints::of(constraints, agent);
+        let table = self.conflict_table(agents, paths, agent);
+        let horizon_end = self.start_tick + self.config.horizon;
+        let next_replan = self.start_tick + self.config.replan_interval;
+
+        let can_stop = |pos: GridPos, tick: u64| {
+            let hold_end = if pos == goal { horizon_end } else { next_replan + 1 };
+            (tick + 1..=next_replan).all(|t| self.base.is_free(pos, t, Some(entity)))
+                && (tick + 1..hold_end).all(|t| !constraints.vertices.contains(&(pos, t)))
+        };
+
+        let mut nodes = vec![LowNode {
+            pos: start,
+            tick: self.start_tick,
+            g: 0.0,
+            f: self.heuristic(start, goal),
+            conflicts: 0,
+            parent: None,
+        }];
+        let mut open: BTreeSet<(Cost, usize)> = BTreeSet::new();
+        let mut focal: BTreeSet<(u32, Cost, usize)> = BTreeSet::new();
+        let mut closed: FxHashSet<(GridPos, u64)> = FxHashSet::default();
+        open.insert((Cost(nodes[0].f), 0));
+        focal.insert((0, Cost(nodes[0].f), 0));
+
+        let mut f_min = nodes[0].f;
+        let mut best: Option<usize> = None;
+        let mut expansions = 0;
+
+        while let Some(&(Cost(open_min), _)) = open.first() {
+            // Le plancher a monté : les nœuds passés sous la nouvelle borne entrent dans le focal
+            if open_min > f_min {
+                let old_bound = Cost(f_min * self.suboptimality);
+                let new_bound = Cost(open_min * self.suboptimality);
+                for &(f, id) in open.range((old_bound, usize::MAX)..=(new_bound, usize::MAX)) {
+                    if f > old_bound {
+                        focal.insert((nodes[id].conflicts, f, id));
+                    }
+                }
+                f_min = open_min;
+            }
+
+            let Some((_, f, id)) = focal.pop_first() else {
+                break;
+            };
+            open.remove(&(f, id));
+
+            expansions += 1;
+            if expansions > MAX_LOW_LEVEL_EXPANSIONS {
+                break;
+            }
+
+            let (pos, tick) = (nodes[id].pos, nodes[id].tick);
+            if !closed.insert((pos, tick)) {
+                continue;
+            }
+
+            let stoppable = can_stop(pos, tick);
+            if pos == goal && stoppable {
+                return LowLevelPath {
+                    path: Self::reconstruct(&nodes, id),
+                    cost: nodes[id].g,
+                    lower_bound: f_min.min(nodes[id].g),
+                };
+            }
+            if stoppable
+                && best.is_none_or(|b| pos.manhattan_distance(&goal) < nodes[b].pos.manhattan_distance(&goal))
+            {
+                best = Some(id);
+            }
+
+            if tick >= horizon_end {
+                continue;
+            }
+
+            let next_tick = tick + 1;
+            let moves = std::iter::once((pos, WAIT_COST)).chain(
+                self.highways
+                    .legal_neighbors(pos)
+                    .map(|n| (n, self.highways.move_cost(pos, n))),
+            );
+
+            for (next, step_cost) in moves {
+                if closed.contains(&(next, next_tick))
+                    || !self.is_valid(pos, next, tick, entity, &constraints)
+                {
+                    continue;
+                }
+
+                let g = nodes[id].g + step_cost;
+                let f = g + self.heuristic(next, goal);
+                let conflicts = nodes[id].conflicts + table.count(pos, next, tick);
+                let child = nodes.len();
+                nodes.push(LowNode { pos: next, tick: next_tick, g, f, conflicts, parent: Some(id) });
+
+                open.insert((Cost(f), child));
+                if f <= f_min * self.suboptimality {
+                    focal.insert((conflicts, Cost(f), child));
+                }
+            }
+        }
+
+        // Pas de chemin jusqu'au but dans l'horizon : chemin partiel le plus proche, sinon attente
+        match best {
+            Some(id) => {
+                let remaining = nodes[id].pos.manhattan_distance(&goal) as f32;
+                let cost = nodes[id].g + remaining;
+                LowLevelPath {
+                    path: Self::reconstruct(&nodes, id),
+                    cost,
+                    lower_bound: f_min.min(cost),
+                }
+            }
+            None => {
+                let cost = self.heuristic(start, goal);
+                LowLevelPath {
+                    path: vec![(start, self.start_tick)],
+                    cost,
+                    lower_bound: cost,
+                }
+            }
+        }
+    }
+
+    fn is_valid(
+        &self,
+        from: GridPos,
+        to: GridPos,
+        tick: u64,
+        entity: Entity,
+        constraints: &AgentConstraints,
+    ) -> bool {
+        let next_tick = tick + 1;
+        if from != to {
+            if !self.grid.is_passable(to) || self.static_obstacles.is_blocked(to, Some(entity)) {
+                return false;
+            }
+            if !self.base.is_edge_free(from, to, tick, Some(entity))
+                || constraints.edges.contains(&(from, to, tick))
+            {
+                return false;
+            }
+        }
+        self.base.is_free(to, next_tick, Some(entity))
+            && !constraints.vertices.contains(&(to, next_tick))
+    }
+
+    fn conflict_table(&self, agents: &[PlanningAgent], paths: &[Path], agent: usize) -> ConflictTable {
+        let mut table = ConflictTable::default();
+        for (i, path) in paths.iter().enumerate().filter(|&(i, _)| i != agent) {
+            for cell in self.occupied(&agents[i], path) {
+                *table.cells.entry(cell).or_default() += 1;
+            }
+            for w in path.windows(2) {
+                table.edges.insert((w[0].0, w[1].0, w[0].1));
+            }
+        }
+        table
+    }
+
+    #[inline]
+    fn heuristic(&self, from: GridPos, to: GridPos) -> f32 {
+        from.manhattan_distance(&to) as f32
+    }
+
+    fn reconstruct(nodes: &[LowNode], end: usize) -> Path {
+        let mut path = Vec::new();
+        let mut current = Some(end);
+        while let Some(id) = current {
+            path.push((nodes[id].pos, nodes[id].tick));
+            current = nodes[id].parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Cellules occupées : le chemin, puis la dernière cellule jusqu'à l'horizon si c'est
+    /// le but, sinon jusqu'à la prochaine replanification incluse (mêmes règles que PBS)
+    fn occupied<'p>(&self, agent: &PlanningAgent, path: &'p Path) -> impl Iterator<Item = (GridPos, u64)> + 'p {
+        let hold_end = if path.last().is_some_and(|&(pos, _)| pos == agent.goal) {
+            self.start_tick + self.config.horizon
+        } else {
+            self.start_tick + self.config.replan_interval + 1
+        };
+        let hold = path
+            .last()
+            .map(|&(pos, tick)| (tick + 1..hold_end).map(move |t| (pos, t)))
+            .into_iter()
+            .flatten();
+        path.iter().copied().chain(hold)
+    }
+
+    /// Conflits de sommet puis d'échange, le plus tôt d'abord
+    fn conflicts(&self, agents: &[PlanningAgent], paths: &[Path]) -> Vec<Conflict> {
+        let mut vertices: FxHashMap<(GridPos, u64), usize> = FxHashMap::default();
+        let mut edges: FxHashMap<(GridPos, GridPos, u64), usize> = FxHashMap::default();
+        let mut found = Vec::new();
+
+        for (i, path) in paths.iter().enumerate() {
+            for (pos, tick) in self.occupied(&agents[i], path) {
+                match vertices.get(&(pos, tick)) {
+                    Some(&other) => found.push(Conflict {
+                        a: other,
+                        b: i,
+                        tick,
+                        kind: ConflictKind::Vertex { pos },
+                    }),
+                    None => {
+                        vertices.insert((pos, tick), i);
+                    }
+                }
+            }
+            for w in path.windows(2) {
+                let ((from, tick), (to, _)) = (w[0], w[1]);
+                if from == to {
+                    continue;
+                }
+                if let Some(&other) = edges.get(&(to, from, tick)) {
+                    found.push(Conflict {
+                        a: other,
+                        b: i,
+                        tick,
+                        kind: ConflictKind::Edge { from: to, to: from },
+                    });
+                }
+                edges.insert((from, to, tick), i);
+            }
+        }
+
+        found
+    }
+
+    fn first_conflict(&self, agents: &[PlanningAgent], paths: &[Path]) -> Option<Conflict> {
+        self.conflicts(agents, paths).into_iter().min_by_key(|c| c.tick)
+    }
+
+    fn count_conflicts(&self, agents: &[PlanningAgent], paths: &[Path]) -> usize {
+        self.conflicts(agents, paths).len()
+    }
+}
+
+/// CBS (ou ECBS si `bounded`) exposé comme solveur, avec repli sur PBS puis sur l'ordre glouton
+pub struct ConflictBasedSearch {
+    bounded: bool,
+}
+
+impl ConflictBasedSearch {
+    pub fn optimal() -> Self {
+        Self { bounded: false }
+    }
+
+    /// ECBS, avec le facteur `PbsConfig::suboptimality`
+    pub fn bounded() -> Self {
+        Self { bounded: true }
+    }
+}
+
+impl MultiAgentPlanner for ConflictBasedSearch {
+    fn name(&self) -> &str {
+        if self.bounded { "ecbs" } else { "cbs" }
+    }
+
+    fn plan(&mut self, problem: &PlanningProblem) -> PlanningOutcome {
+        let w = if self.bounded { problem.config.suboptimality } else { 1.0 };
+        let solver = CbsSolver::from_problem(problem).with_suboptimality(w);
+        if let Some(paths) = solver.solve(&problem.agents) {
+            return PlanningOutcome::new(paths.into_iter().map(Some).collect());
+        }
+
+        let tree = PriorityTree::from_problem(problem);
+        match tree.search(&problem.agents) {
+            Some(paths) => PlanningOutcome::new(paths.into_iter().map(Some).collect())
+                .with_fallback("pbs"),
+            None => PlanningOutcome::new(tree.greedy(&problem.agents)).with_fallback("greedy order"),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::core::GridPos;
+
+/// Type d'événement de simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimEventKind {
+    /// Nouvelle mission ; la cellule est le storage visé, ou le cargo d'un robot resté chargé
+    MissionAssigned,
+    ArrivedAtStorage,
+    PickupDone,
+    ArrivedAtCargo,
+    DropoffDone,
+    /// Chemin recalculé ; `planned` est faux si le solveur n'en a pas trouvé
+    Replan { planned: bool },
+    DeadlockWarning,
+    /// Recharge attribuée ; la cellule est le chargeur visé
+    ChargerAssigned,
+    ArrivedAtCharger,
+    ChargingDone,
+    /// Batterie vide : le robot passe en panne sur sa cellule
+    BatteryDepleted,
+    /// Panne réparable : le robot s'arrête sur sa cellule
+    Breakdown,
+    Repaired,
+}
+
+impl SimEventKind {
+    /// Nom stable, utilisé dans les exports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MissionAssigned => "mission_assigned",
+            Self::ArrivedAtStorage => "arrived_at_storage",
+            Self::PickupDone => "pickup_done",
+            Self::ArrivedAtCargo => "arrived_at_cargo",
+            Self::DropoffDone => "dropoff_done",
+            Self::Replan { planned: true } => "replan",
+            Self::Replan { planned: false } => "replan_failed",
+            Self::DeadlockWarning => "deadlock_warning",
+            Self::ChargerAssigned => "charger_assigned",
+            Self::ArrivedAtCharger => "arrived_at_charger",
+            Self::ChargingDone => "charging_done",
+            Self::BatteryDepleted => "battery_depleted",
+            Self::Breakdown => "breakdown",
+            Self::Repaired => "repaired",
+        }
+    }
+
+    /// Code compact, utilisé dans l'export binaire
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::MissionAssigned => 0,
+            Self::ArrivedAtStorage => 1,
+            Self::PickupDone => 2,
+            Self::ArrivedAtCargo => 3,
+            Self::DropoffDone => 4,
+            Self::Replan { planned: true } => 5,
+            Self::Replan { planned: false } => 6,
+            Self::DeadlockWarning => 7,
+            Self::ChargerAssigned => 8,
+            Self::ArrivedAtCharger => 9,
+            Self::ChargingDone => 10,
+            Self::BatteryDepleted => 11,
+            Self::Breakdown => 12,
+            Self::Repaired => 13,
+        }
+    }
+}
+
+/// Événement émis par les systèmes de simulation, daté par `SpaceTimeTable::current_tick`
+#[derive(Message, Debug, Clone, Copy)]
+pub struct SimEvent {
+    pub tick: u64,
+    pub entity: Entity,
+    /// Cellule concernée (cible de mission ou position du robot)
+    pub cell: GridPos,
+    pub kind: SimEventKind,
+}
+
+impl SimEvent {
+    pub fn new(tick: u64, entity: Entity, cell: GridPos, kind: SimEventKind) -> Self {
+        Self { tick, entity, cell, kind }
+    }
+}
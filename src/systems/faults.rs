@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    ActionTimer, Battery, Breakdown, FaultCause, GridPosition, Mission, NextFailure, NextTask, PlannedPath,
+    Robot, RobotId, RobotState, State,
+};
+use crate::constants::{FAULT_MTTR, TICK_RATE_HZ};
+use crate::core::{SimRng, SpaceTimeTable, WarehouseZones};
+use crate::systems::allocation::release_task;
+use crate::systems::events::{SimEvent, SimEventKind};
+use crate::systems::metrics::SimMetrics;
+use crate::systems::orders::OrderBook;
+use crate::systems::pbs::PbsConfig;
+use crate::systems::planner::{stop_position, PlannerRegistry};
+use crate::systems::spawner::release_mission;
+
+/// Panne prévue à un tick donné
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScriptedFault {
+    pub tick: u64,
+    /// Numéro du robot (`RobotId`), celui affiché `#n` dans l'interface
+    pub robot: u32,
+    /// Durée de la réparation en secondes, tirée selon le MTTR si absente
+    #[serde(default)]
+    pub repair_secs: Option<f32>,
+}
+
+/// Injection de pannes ; aucune par défaut
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct FaultConfig {
+    /// Temps moyen entre deux pannes d'un robot en service, en secondes
+    pub mtbf: Option<f32>,
+    /// Temps moyen de réparation, en secondes
+    pub mttr: f32,
+    pub script: Vec<ScriptedFault>,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self { mtbf: None, mttr: FAULT_MTTR, script: Vec::new() }
+    }
+}
+
+impl FaultConfig {
+    /// Liste RON de pannes, par exemple `[(tick: 600, robot: 3, repair_secs: Some(20.0))]`
+    pub fn script_from_ron(text: &str) -> Result<Vec<ScriptedFault>, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+}
+
+/// Pannes et réparations demandées depuis l'interface, appliquées au tick suivant
+#[derive(Resource, Debug, Default)]
+pub struct FaultRequests {
+    pub breakdowns: Vec<Entity>,
+    pub repairs: Vec<Entity>,
+}
+
+pub(crate) fn secs_to_ticks(secs: f64) -> u64 {
+    ((secs * TICK_RATE_HZ).round() as u64).max(1)
+}
+
+/// Durée de loi exponentielle de moyenne `mean` secondes, en ticks
+fn exponential_ticks(rng: &mut SimRng, mean: f32) -> u64 {
+    let u: f64 = rng.random();
+    secs_to_ticks(-(mean as f64) * (1.0 - u).ln())
+}
+
+/// Robot vu par l'injection de pannes
+type FaultTarget = (
+    Entity,
+    Option<&'static RobotId>,
+    &'static GridPosition,
+    &'static PlannedPath,
+    &'static mut State,
+    &'static mut Battery,
+    Option<&'static Mission>,
+    Option<&'static Breakdown>,
+    Option<&'static NextTask>,
+    Option<&'static NextFailure>,
+);
+
+/// Pannes et réparations du tick, dans l'ordre des entités pour rester déterministe.
+///
+/// Une panne vient du script, de l'interface ou du MTBF : l'instant de la prochaine panne
+/// aléatoire est tiré une fois par robot en service, selon une loi exponentielle. Le robot
+/// libère les réservations de sa mission, sauf la cellule où il s'arrête, ainsi que la tâche
+/// que l'allocateur lui réservait, et devient un obstacle à la planification suivante. À la
+/// réparation (batterie remplacée si elle était vide), il repart en `Idle` et le dispatcher
+/// lui donne une mission, vers un cargo s'il est encore chargé.
+#[allow(clippy::too_many_arguments)]
+pub fn fault_injection_system(
+    mut commands: Commands,
+    mut robots: Query<FaultTarget, With<Robot>>,
+    mut zones: ResMut<WarehouseZones>,
+    mut orders: ResMut<OrderBook>,
+    mut rng: ResMut<SimRng>,
+    mut requests: ResMut<FaultRequests>,
+    mut events: MessageWriter<SimEvent>,
+    mut metrics: ResMut<SimMetrics>,
+    config: Res<FaultConfig>,
+    space_time: Res<SpaceTimeTable>,
+    registry: Res<PlannerRegistry>,
+    pbs: Res<PbsConfig>,
+) {
+    let tick = space_time.current_tick();
+    let breakdowns = std::mem::take(&mut requests.breakdowns);
+    let repairs = std::mem::take(&mut requests.repairs);
+
+    let mut robots: Vec<_> = robots.iter_mut().collect();
+    robots.sort_unstable_by_key(|(entity, ..)| entity.index());
+
+    for (entity, id, pos, path, mut state, mut battery, mission, breakdown, next, next_failure) in robots {
+        if let Some(breakdown) = breakdown {
+            if breakdown.repair_at <= tick || repairs.contains(&entity) {
+                if let Some(cell) = breakdown.blocked {
+                    zones.release(cell);
+                }
+                if breakdown.cause == FaultCause::BatteryDepleted {
+                    battery.0 = 1.0;
+                }
+                state.0 = RobotState::Idle;
+                // Nouveau tirage au tick suivant, le robot étant de nouveau en service
+                commands.entity(entity).remove::<(Breakdown, NextFailure)>();
+                events.write(SimEvent::new(tick, entity, pos.0, SimEventKind::Repaired));
+                info!("Robot repaired: {:?} at {:?}", entity, pos.0);
+            }
+            continue;
+        }
+        // Batterie vide à ce tick : sa panne vient d'être enregistrée
+        if state.0 == RobotState::Fault {
+            continue;
+        }
+
+        let random = match (config.mtbf, next_failure) {
+            (Some(_), Some(&NextFailure(at))) => at <= tick,
+            (Some(mtbf), None) => {
+                commands.entity(entity).insert(NextFailure(tick + exponential_ticks(&mut rng, mtbf)));
+                false
+            }
+            (None, _) => false,
+        };
+        let scripted = config.script.iter().find(|f| f.tick == tick && id.is_some_and(|id| id.0 == f.robot));
+        let (cause, repair_ticks) = if let Some(fault) = scripted {
+            let ticks = match fault.repair_secs {
+                Some(secs) => secs_to_ticks(secs as f64),
+                None => exponential_ticks(&mut rng, config.mttr),
+            };
+            (FaultCause::Scripted, ticks)
+        } else if breakdowns.contains(&entity) {
+            (FaultCause::Manual, exponential_ticks(&mut rng, config.mttr))
+        } else if random {
+            (FaultCause::Random, exponential_ticks(&mut rng, config.mttr))
+        } else {
+            continue;
+        };
+
+        let parked = stop_position(pos.0, path, tick, &registry, &pbs);
+        let blocked = mission.and_then(|mission| release_mission(&mut zones, &mut orders, mission, state.0, Some(parked)));
+        if let Some(task) = next {
+            release_task(&mut zones, &mut orders, task);
+        }
+        state.0 = RobotState::Fault;
+        commands
+            .entity(entity)
+            .insert(Breakdown { cause, since: tick, repair_at: tick + repair_ticks, blocked })
+            .remove::<(ActionTimer, NextTask)>();
+        events.write(SimEvent::new(tick, entity, pos.0, SimEventKind::Breakdown));
+        metrics.breakdowns += 1;
+        warn!("Robot breakdown ({:?}): {:?} at {:?}, repair in {} ticks", cause, entity, parked, repair_ticks);
+    }
+}
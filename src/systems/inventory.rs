@@ -0,0 +1,273 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::components::Sku;
+use crate::constants::SLOT_CAPACITY;
+use crate::core::{Direction, GridPos, WarehouseZones};
+
+/// Emplacement de stockage : une face de rack, servie depuis une cellule d'accès. Un
+/// storage entre deux racks a un emplacement par face.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Slot {
+    pub cell: GridPos,
+    /// Cellule de rack servie depuis `cell`, absente pour un storage au sol
+    #[serde(default)]
+    pub face: Option<GridPos>,
+    pub sku: Sku,
+    pub quantity: u32,
+    pub capacity: u32,
+}
+
+impl Slot {
+    pub fn fill(&self) -> f32 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        self.quantity as f32 / self.capacity as f32
+    }
+}
+
+/// Plan de stockage incohérent
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryError {
+    /// Deux emplacements sur la même face d'un storage : le second ne serait jamais prélevé
+    DuplicateSlot { cell: GridPos, face: Option<GridPos> },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateSlot { cell, face: Some(face) } => write!(
+                f,
+                "storage cell ({}, {}) has more than one slot on rack face ({}, {})",
+                cell.x, cell.y, face.x, face.y
+            ),
+            Self::DuplicateSlot { cell, face: None } => {
+                write!(f, "storage cell ({}, {}) has more than one slot", cell.x, cell.y)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+/// Remplissage initial des emplacements
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct InventoryConfig {
+    /// Unités par emplacement
+    pub capacity: u32,
+    /// Part de la capacité en stock au départ
+    pub initial_fill: f32,
+    /// Plan de stockage explicite, sinon un emplacement par face de rack (ou par storage au
+    /// sol), de référence `i % skus`
+    pub slots: Option<Vec<Slot>>,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self { capacity: SLOT_CAPACITY, initial_fill: 1.0, slots: None }
+    }
+}
+
+impl InventoryConfig {
+    /// Liste RON d'emplacements, par exemple `[(cell: (x: 3, y: 1), sku: 7, quantity: 20, capacity: 40)]`
+    pub fn slots_from_ron(text: &str) -> Result<Vec<Slot>, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// Un seul emplacement par face de storage ; deux emplacements sans face sur un même
+    /// storage se disputeraient la première
+    pub fn check_slots(slots: &[Slot]) -> Result<(), InventoryError> {
+        let mut seen = FxHashSet::default();
+        match slots.iter().find(|slot| !seen.insert((slot.cell, slot.face))) {
+            Some(slot) => Err(InventoryError::DuplicateSlot { cell: slot.cell, face: slot.face }),
+            None => Ok(()),
+        }
+    }
+
+    /// Inventaire de départ des storages de `zones`, `skus` références
+    pub fn build(&self, zones: &WarehouseZones, skus: u32) -> Result<Inventory, InventoryError> {
+        let slots: Vec<Slot> = match &self.slots {
+            Some(slots) => {
+                let (known, unknown): (Vec<Slot>, Vec<Slot>) =
+                    slots.iter().partition(|slot| zones.storage_cells.contains(&slot.cell));
+                for slot in unknown {
+                    warn!("Inventory slot {:?} is not a storage cell, ignored", slot.cell);
+                }
+                // Sans face explicite, l'emplacement prend la première
+                known
+                    .into_iter()
+                    .map(|slot| Slot { face: slot.face.or_else(|| rack_faces(zones, slot.cell).next()), ..slot })
+                    .collect()
+            }
+            None => {
+                let quantity = (self.capacity as f32 * self.initial_fill.clamp(0.0, 1.0)).round() as u32;
+                zones
+                    .storage_cells
+                    .iter()
+                    .flat_map(|&cell| {
+                        let faces: Vec<Option<GridPos>> = rack_faces(zones, cell).map(Some).collect();
+                        let faces = if faces.is_empty() { vec![None] } else { faces };
+                        faces.into_iter().map(move |face| (cell, face))
+                    })
+                    .enumerate()
+                    .map(|(i, (cell, face))| Slot {
+                        cell,
+                        face,
+                        sku: (i % skus.max(1) as usize) as Sku,
+                        quantity,
+                        capacity: self.capacity,
+                    })
+                    .collect()
+            }
+        };
+        Self::check_slots(&slots)?;
+        Ok(Inventory::new(slots))
+    }
+}
+
+/// Cellules de rack voisines de `cell`, dans l'ordre nord, est, sud, ouest
+fn rack_faces(zones: &WarehouseZones, cell: GridPos) -> impl Iterator<Item = GridPos> + '_ {
+    Direction::CARDINALS
+        .into_iter()
+        .map(move |dir| cell.neighbor(dir))
+        .filter(|&pos| zones.is_rack(pos))
+}
+
+/// Issue d'un prélèvement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    /// Une unité de la référence a été prélevée
+    Picked(Sku),
+    /// Storage sans emplacement : rien n'est décompté
+    Untracked,
+    /// Aucun emplacement du storage n'a la référence en stock
+    Empty,
+}
+
+/// Stock des emplacements. Les storages sans emplacement ne sont pas suivis : toujours
+/// servables, leurs prélèvements ne décomptent rien.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "InventorySlots")]
+pub struct Inventory {
+    slots: Vec<Slot>,
+    /// Emplacements de chaque storage, consultés pour chaque candidat à l'attribution
+    #[serde(skip)]
+    index: FxHashMap<GridPos, Vec<usize>>,
+}
+
+/// Forme sérialisée de l'inventaire, dont l'index est reconstruit au chargement
+#[derive(Deserialize)]
+struct InventorySlots {
+    slots: Vec<Slot>,
+}
+
+impl From<InventorySlots> for Inventory {
+    fn from(data: InventorySlots) -> Self {
+        Self::new(data.slots)
+    }
+}
+
+impl Inventory {
+    /// Inventaire indexé par storage, un emplacement au plus par face (voir
+    /// `InventoryConfig::check_slots`)
+    pub fn new(slots: Vec<Slot>) -> Self {
+        let mut index: FxHashMap<GridPos, Vec<usize>> = FxHashMap::default();
+        for (i, slot) in slots.iter().enumerate() {
+            index.entry(slot.cell).or_default().push(i);
+        }
+        Self { slots, index }
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    /// Emplacements servis depuis un storage, un par face de rack
+    pub fn slots_at(&self, cell: GridPos) -> impl Iterator<Item = &Slot> {
+        self.index.get(&cell).into_iter().flatten().map(|&i| &self.slots[i])
+    }
+
+    pub fn slot(&self, cell: GridPos, face: Option<GridPos>) -> Option<&Slot> {
+        self.slots_at(cell).find(|slot| slot.face == face)
+    }
+
+    pub fn slot_mut(&mut self, cell: GridPos, face: Option<GridPos>) -> Option<&mut Slot> {
+        let i = *self.index.get(&cell)?.iter().find(|&&i| self.slots[i].face == face)?;
+        Some(&mut self.slots[i])
+    }
+
+    /// Remplissage des emplacements d'un storage, absent s'il n'est pas suivi
+    pub fn cell_fill(&self, cell: GridPos) -> Option<f32> {
+        let slots = self.index.get(&cell)?;
+        let capacity: u64 = slots.iter().map(|&i| self.slots[i].capacity as u64).sum();
+        if capacity == 0 {
+            return Some(0.0);
+        }
+        let quantity: u64 = slots.iter().map(|&i| self.slots[i].quantity as u64).sum();
+        Some(quantity as f32 / capacity as f32)
+    }
+
+    /// Emplacements de la référence, vides compris
+    pub fn slots_of(&self, sku: Sku) -> impl Iterator<Item = &Slot> {
+        self.slots.iter().filter(move |slot| slot.sku == sku)
+    }
+
+    /// Storages où la référence est en stock, chacun une fois
+    pub fn stocked_cells(&self, sku: Sku) -> Vec<GridPos> {
+        let mut seen = FxHashSet::default();
+        self.slots_of(sku)
+            .filter(|slot| slot.quantity > 0 && seen.insert(slot.cell))
+            .map(|slot| slot.cell)
+            .collect()
+    }
+
+    pub fn stock(&self, sku: Sku) -> u32 {
+        self.slots_of(sku).map(|slot| slot.quantity).sum()
+    }
+
+    /// Storage non suivi, ou dont une face au moins a du stock
+    pub fn is_stocked(&self, cell: GridPos) -> bool {
+        match self.index.get(&cell) {
+            Some(slots) => slots.iter().any(|&i| self.slots[i].quantity > 0),
+            None => true,
+        }
+    }
+
+    /// Prélève une unité de `sku` sur l'une des faces du storage, ou de la première face en
+    /// stock si aucune référence n'est demandée
+    pub fn pick(&mut self, cell: GridPos, sku: Option<Sku>) -> Pick {
+        let Some(slots) = self.index.get(&cell) else {
+            return Pick::Untracked;
+        };
+        let found = slots.iter().copied().find(|&i| {
+            let slot = &self.slots[i];
+            slot.quantity > 0 && sku.is_none_or(|sku| slot.sku == sku)
+        });
+        match found {
+            Some(i) => {
+                let slot = &mut self.slots[i];
+                slot.quantity -= 1;
+                Pick::Picked(slot.sku)
+            }
+            None => Pick::Empty,
+        }
+    }
+
+    /// Remplissage global, en part de la capacité totale
+    pub fn fill(&self) -> f32 {
+        let capacity: u64 = self.slots.iter().map(|slot| slot.capacity as u64).sum();
+        if capacity == 0 {
+            return 0.0;
+        }
+        let quantity: u64 = self.slots.iter().map(|slot| slot.quantity as u64).sum();
+        quantity as f32 / capacity as f32
+    }
+
+    pub fn empty_slots(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.quantity == 0).count()
+    }
+}
@@ -0,0 +1,283 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{GridPosition, Mission, MissionPhase, Robot, RobotState, State};
+use crate::constants::TICK_RATE_HZ;
+use crate::core::{GridPos, SpaceTimeTable};
+use crate::systems::planner::PlannerDiagnostics;
+
+/// Indicateurs de performance cumulés depuis le début de l'exécution
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimMetrics {
+    pub ticks: u64,
+    pub completed_missions: u64,
+    /// Durée de chaque mission terminée, en ticks
+    pub cycle_times: Vec<u64>,
+    /// Robot-ticks passés dans chaque phase, dans l'ordre de `MissionPhase::ALL`
+    pub phase_ticks: [u64; 6],
+    /// Robot-ticks en déplacement sans avancer
+    pub wait_ticks: u64,
+    /// Robot-ticks en déplacement, chargement ou déchargement
+    pub busy_ticks: u64,
+    pub robot_ticks: u64,
+    /// Planifications lancées
+    pub planning_runs: u64,
+    /// Chemins recalculés, un par robot et par planification
+    pub replans: u64,
+    /// Robots laissés sans chemin par le solveur
+    pub planner_failures: u64,
+    /// Planifications terminées par un repli
+    pub fallbacks: u64,
+    /// Énergie tirée des batteries, en Wh
+    pub energy_used_wh: f64,
+    /// Énergie rendue aux batteries par les chargeurs, en Wh
+    pub energy_charged_wh: f64,
+    /// Pannes réparables survenues
+    pub breakdowns: u64,
+    /// Robot-ticks en panne, batteries vides comprises
+    pub fault_ticks: u64,
+    pub orders_received: u64,
+    /// Lignes abandonnées ou prélèvements manqués faute de stock
+    pub stockouts: u64,
+    /// Délai de chaque commande close, de l'arrivée au dernier dépôt, en ticks
+    pub order_lead_times: Vec<u64>,
+    /// Tâches affectées par l'allocateur
+    pub assignments: u64,
+    /// Pas à vide jusqu'au storage, cumulés sur les tâches affectées
+    pub assignment_steps: u64,
+}
+
+impl SimMetrics {
+    pub fn record_mission(&mut self, cycle_ticks: u64) {
+        self.completed_missions += 1;
+        self.cycle_times.push(cycle_ticks);
+    }
+
+    pub fn record_order(&mut self, lead_ticks: u64) {
+        self.order_lead_times.push(lead_ticks);
+    }
+
+    pub fn record_planning(&mut self, diagnostics: &PlannerDiagnostics) {
+        self.planning_runs += 1;
+        self.replans += diagnostics.agents as u64;
+        self.planner_failures += diagnostics.unplanned as u64;
+        if diagnostics.fallback.is_some() {
+            self.fallbacks += 1;
+        }
+    }
+
+    pub fn missions_per_hour(&self) -> f64 {
+        let hours = self.ticks as f64 / TICK_RATE_HZ / 3600.0;
+        if hours > 0.0 {
+            self.completed_missions as f64 / hours
+        } else {
+            0.0
+        }
+    }
+
+    /// Temps de cycle moyen, en secondes
+    pub fn mean_cycle_time(&self) -> Option<f64> {
+        if self.cycle_times.is_empty() {
+            return None;
+        }
+        let total: u64 = self.cycle_times.iter().sum();
+        Some(ticks_to_secs(total) / self.cycle_times.len() as f64)
+    }
+
+    /// Percentile du temps de cycle (rang le plus proche, `p` entre 0 et 100), en secondes
+    pub fn cycle_time_percentile(&self, p: f64) -> Option<f64> {
+        if self.cycle_times.is_empty() {
+            return None;
+        }
+        let mut sorted = self.cycle_times.clone();
+        sorted.sort_unstable();
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(ticks_to_secs(sorted[rank.saturating_sub(1)]))
+    }
+
+    /// Percentile du délai des commandes closes, en secondes
+    pub fn order_lead_time_percentile(&self, p: f64) -> Option<f64> {
+        if self.order_lead_times.is_empty() {
+            return None;
+        }
+        let mut sorted = self.order_lead_times.clone();
+        sorted.sort_unstable();
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(ticks_to_secs(sorted[rank.saturating_sub(1)]))
+    }
+
+    /// Énergie consommée par mission terminée, en Wh
+    pub fn energy_per_mission(&self) -> Option<f64> {
+        (self.completed_missions > 0).then(|| self.energy_used_wh / self.completed_missions as f64)
+    }
+
+    /// Part des robot-ticks passés à travailler plutôt qu'à attendre une mission
+    pub fn utilization(&self) -> f64 {
+        if self.robot_ticks == 0 {
+            return 0.0;
+        }
+        self.busy_ticks as f64 / self.robot_ticks as f64
+    }
+
+    /// Pas moyens d'un robot jusqu'au storage de la tâche que l'allocateur lui donne
+    pub fn mean_assignment_steps(&self) -> Option<f64> {
+        (self.assignments > 0).then(|| self.assignment_steps as f64 / self.assignments as f64)
+    }
+
+    /// Part des robot-ticks hors panne
+    pub fn availability(&self) -> f64 {
+        if self.robot_ticks == 0 {
+            return 1.0;
+        }
+        1.0 - self.fault_ticks as f64 / self.robot_ticks as f64
+    }
+
+    pub fn summary(&self) -> MetricsSummary {
+        let phase = |p: MissionPhase| ticks_to_secs(self.phase_ticks[p as usize]);
+        MetricsSummary {
+            ticks: self.ticks,
+            completed_missions: self.completed_missions,
+            missions_per_hour: self.missions_per_hour(),
+            cycle_time_mean: self.mean_cycle_time(),
+            cycle_time_p50: self.cycle_time_percentile(50.0),
+            cycle_time_p90: self.cycle_time_percentile(90.0),
+            cycle_time_p99: self.cycle_time_percentile(99.0),
+            phase_time: PhaseTimes {
+                going_to_storage: phase(MissionPhase::GoingToStorage),
+                picking_up: phase(MissionPhase::PickingUp),
+                going_to_cargo: phase(MissionPhase::GoingToCargo),
+                dropping_off: phase(MissionPhase::DroppingOff),
+                going_to_charger: phase(MissionPhase::GoingToCharger),
+                charging: phase(MissionPhase::Charging),
+            },
+            wait_time: ticks_to_secs(self.wait_ticks),
+            utilization: self.utilization(),
+            planning_runs: self.planning_runs,
+            replans: self.replans,
+            planner_failures: self.planner_failures,
+            fallbacks: self.fallbacks,
+            energy_used_wh: self.energy_used_wh,
+            energy_charged_wh: self.energy_charged_wh,
+            energy_per_mission_wh: self.energy_per_mission(),
+            breakdowns: self.breakdowns,
+            fault_time: ticks_to_secs(self.fault_ticks),
+            availability: self.availability(),
+            orders_received: self.orders_received,
+            orders_completed: self.order_lead_times.len() as u64,
+            stockouts: self.stockouts,
+            order_lead_time_p50: self.order_lead_time_percentile(50.0),
+            order_lead_time_p90: self.order_lead_time_percentile(90.0),
+            assignments: self.assignments,
+            assignment_steps_mean: self.mean_assignment_steps(),
+        }
+    }
+}
+
+fn ticks_to_secs(ticks: u64) -> f64 {
+    ticks as f64 / TICK_RATE_HZ
+}
+
+/// Temps cumulé de tous les robots dans chaque phase, en secondes
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseTimes {
+    pub going_to_storage: f64,
+    pub picking_up: f64,
+    pub going_to_cargo: f64,
+    pub dropping_off: f64,
+    pub going_to_charger: f64,
+    pub charging: f64,
+}
+
+/// Bilan exportable des indicateurs ; les durées sont en secondes de simulation
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSummary {
+    pub ticks: u64,
+    pub completed_missions: u64,
+    pub missions_per_hour: f64,
+    pub cycle_time_mean: Option<f64>,
+    pub cycle_time_p50: Option<f64>,
+    pub cycle_time_p90: Option<f64>,
+    pub cycle_time_p99: Option<f64>,
+    pub phase_time: PhaseTimes,
+    pub wait_time: f64,
+    pub utilization: f64,
+    pub planning_runs: u64,
+    pub replans: u64,
+    pub planner_failures: u64,
+    pub fallbacks: u64,
+    pub energy_used_wh: f64,
+    pub energy_charged_wh: f64,
+    pub energy_per_mission_wh: Option<f64>,
+    pub breakdowns: u64,
+    pub fault_time: f64,
+    pub availability: f64,
+    pub orders_received: u64,
+    pub orders_completed: u64,
+    pub stockouts: u64,
+    pub order_lead_time_p50: Option<f64>,
+    pub order_lead_time_p90: Option<f64>,
+    pub assignments: u64,
+    pub assignment_steps_mean: Option<f64>,
+}
+
+impl MetricsSummary {
+    pub fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("metrics are always serializable")
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_ron())
+    }
+}
+
+/// Fichier où écrire le bilan des indicateurs en fin d'exécution
+#[derive(Resource, Debug, Clone)]
+pub struct MetricsExport {
+    pub path: PathBuf,
+}
+
+/// Échantillonne l'état des robots une fois par tick, après l'exécution des chemins.
+/// Une attente se lit sur la cellule du tick précédent : l'exécution réécrit la position
+/// même pour un pas d'attente planifié.
+pub fn metrics_sampling_system(
+    robots: Query<(Entity, &GridPosition, &State, Option<&Mission>), With<Robot>>,
+    space_time: Res<SpaceTimeTable>,
+    mut metrics: ResMut<SimMetrics>,
+    mut last_cells: Local<FxHashMap<Entity, GridPos>>,
+) {
+    metrics.ticks = space_time.current_tick();
+    for (entity, pos, state, mission) in &robots {
+        let stayed = last_cells.insert(entity, pos.0) == Some(pos.0);
+        metrics.robot_ticks += 1;
+        if let Some(mission) = mission {
+            metrics.phase_ticks[mission.phase as usize] += 1;
+        }
+        match state.0 {
+            RobotState::Moving => {
+                metrics.busy_ticks += 1;
+                if stayed {
+                    metrics.wait_ticks += 1;
+                }
+            }
+            RobotState::Loading | RobotState::Unloading => metrics.busy_ticks += 1,
+            RobotState::Fault => metrics.fault_ticks += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Écrit le bilan des indicateurs si un fichier d'export est configuré
+pub fn export_metrics(world: &World) {
+    let Some(export) = world.get_resource::<MetricsExport>() else {
+        return;
+    };
+    let summary = world.resource::<SimMetrics>().summary();
+    match summary.save(&export.path) {
+        Ok(()) => info!("Metrics written to {}", export.path.display()),
+        Err(e) => error!("cannot write metrics to {}: {e}", export.path.display()),
+    }
+}
@@ -1,5 +1,25 @@
+pub mod allocation;
+pub mod battery;
+pub mod cbs;
+pub mod events;
+pub mod faults;
+pub mod inventory;
+pub mod metrics;
 pub mod navigation;
+pub mod orders;
 pub mod pbs;
+pub mod pibt;
+pub mod planner;
+pub mod recorder;
+pub mod replay;
+pub mod report;
+pub mod scenario;
+pub mod snapshot;
+pub mod sipp;
 pub mod spawner;
+pub mod speed;
+pub mod validation;
+#[cfg(feature = "gui")]
 pub mod ui;
+#[cfg(feature = "gui")]
 pub mod visualization;
@@ -0,0 +1,309 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{OrderLine, Sku};
+use crate::constants::{SKU_SKEW, TICK_RATE_HZ};
+use crate::core::{GridPos, SimRng, SpaceTimeTable, WarehouseZones};
+use crate::systems::inventory::Inventory;
+use crate::systems::metrics::SimMetrics;
+
+/// Commande datée, lue dans un fichier
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderSpec {
+    pub tick: u64,
+    pub lines: Vec<Sku>,
+    /// Quai de sortie, tiré au hasard si absent
+    #[serde(default)]
+    pub dock: Option<usize>,
+}
+
+/// Origine des missions
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum OrderSource {
+    /// Pas de commandes : un storage et un cargo tirés au hasard dès qu'un robot est libre
+    #[default]
+    Continuous,
+    /// Arrivées de Poisson, `rate` commandes par heure de 1 à `max_lines` lignes
+    Poisson { rate: f32, max_lines: u32 },
+    /// Commandes d'un fichier ; plusieurs au même tick forment une vague
+    Script(Vec<OrderSpec>),
+}
+
+impl OrderSource {
+    /// Commandes d'un fichier, remises dans l'ordre des ticks (l'ordre du fichier est gardé
+    /// entre commandes d'un même tick)
+    pub fn script(mut specs: Vec<OrderSpec>) -> Self {
+        specs.sort_by_key(|spec| spec.tick);
+        Self::Script(specs)
+    }
+}
+
+/// Commande de script incompatible avec l'entrepôt simulé
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderError {
+    /// Commande plus tôt que la précédente : le script doit être trié par tick
+    Unsorted { tick: u64 },
+    UnknownDock { tick: u64, dock: usize, docks: usize },
+    UnknownSku { tick: u64, sku: Sku, skus: u32 },
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsorted { tick } => write!(f, "order at tick {tick} comes after a later order"),
+            Self::UnknownDock { tick, dock, docks } => {
+                write!(f, "order at tick {tick} uses dock {dock}, but the layout has {docks}")
+            }
+            Self::UnknownSku { tick, sku, skus } => {
+                write!(f, "order at tick {tick} asks for SKU {sku}, but there are {skus}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+/// Flux de commandes et catalogue des références
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct OrderConfig {
+    pub source: OrderSource,
+    /// Nombre de références, une par storage si absent
+    pub skus: Option<u32>,
+    /// Exposant de Zipf de la popularité des références, 0 pour une demande uniforme
+    pub sku_skew: f32,
+}
+
+impl Default for OrderConfig {
+    fn default() -> Self {
+        Self { source: OrderSource::Continuous, skus: None, sku_skew: SKU_SKEW }
+    }
+}
+
+impl OrderConfig {
+    /// Liste RON de commandes, par exemple `[(tick: 60, lines: [3, 8], dock: Some(1))]`
+    pub fn script_from_ron(text: &str) -> Result<Vec<OrderSpec>, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// Vérifie qu'un script est trié et ne cite que des quais et références existants
+    pub fn validate(&self, zones: &WarehouseZones) -> Result<(), OrderError> {
+        let OrderSource::Script(specs) = &self.source else {
+            return Ok(());
+        };
+        let docks = zones.docks().len();
+        let skus = self.sku_count(zones);
+        let mut last_tick = 0;
+        for spec in specs {
+            let tick = spec.tick;
+            if tick < last_tick {
+                return Err(OrderError::Unsorted { tick });
+            }
+            last_tick = tick;
+            if let Some(dock) = spec.dock.filter(|&dock| dock >= docks) {
+                return Err(OrderError::UnknownDock { tick, dock, docks });
+            }
+            if let Some(&sku) = spec.lines.iter().find(|&&sku| sku >= skus) {
+                return Err(OrderError::UnknownSku { tick, sku, skus });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        self.source == OrderSource::Continuous
+    }
+
+    pub fn sku_count(&self, zones: &WarehouseZones) -> u32 {
+        self.skus.unwrap_or(zones.storage_cells.len() as u32).max(1)
+    }
+
+    /// Référence tirée selon sa popularité : la `k`-ième pèse `1 / (k + 1)^sku_skew`
+    pub fn sample_sku(&self, zones: &WarehouseZones, rng: &mut SimRng) -> Sku {
+        let skus = self.sku_count(zones);
+        let weight = |k: u32| 1.0 / ((k + 1) as f64).powf(self.sku_skew as f64);
+        let total: f64 = (0..skus).map(weight).sum();
+        let mut draw = rng.random::<f64>() * total;
+        for k in 0..skus {
+            draw -= weight(k);
+            if draw < 0.0 {
+                return k;
+            }
+        }
+        skus - 1
+    }
+}
+
+/// Commande en cours
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OpenOrder {
+    pub id: u64,
+    pub arrival: u64,
+    pub dock: usize,
+    /// Lignes pas encore déposées au quai
+    pub remaining: u32,
+    /// Lignes abandonnées faute de stock
+    pub short: u32,
+}
+
+/// Carnet de commandes : lignes en attente d'un robot et commandes ouvertes
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderBook {
+    /// Dans l'ordre d'arrivée
+    pub pending: VecDeque<OrderLine>,
+    pub open: Vec<OpenOrder>,
+    pub next_id: u64,
+    /// Tick de la prochaine arrivée de Poisson
+    pub next_arrival: Option<u64>,
+    /// Prochaine commande du fichier à publier
+    pub script_index: usize,
+}
+
+impl OrderBook {
+    /// Enregistre une commande et met ses lignes en attente
+    pub fn publish(&mut self, tick: u64, lines: &[Sku], dock: usize) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.open.push(OpenOrder { id, arrival: tick, dock, remaining: lines.len() as u32, short: 0 });
+        self.pending.extend(lines.iter().map(|&sku| OrderLine { order: id, sku, dock }));
+        id
+    }
+
+    /// Première ligne en attente servable : storage libre où la référence est en stock, le
+    /// plus proche de `from`, puis cargo libre du quai le plus proche de ce storage. Les
+    /// lignes bloquées gardent leur place.
+    pub fn take_line(
+        &mut self,
+        inventory: &Inventory,
+        zones: &mut WarehouseZones,
+        docks: &[Vec<GridPos>],
+        from: GridPos,
+    ) -> Option<(OrderLine, GridPos, GridPos)> {
+        for i in 0..self.pending.len() {
+            let line = self.pending[i];
+            let Some(dock) = docks.get(line.dock) else {
+                continue;
+            };
+            let Some(storage) = zones.reserve_storage_near(&inventory.stocked_cells(line.sku), from) else {
+                continue;
+            };
+            let Some(cargo) = zones.reserve_cargo_near(dock, storage) else {
+                zones.release_storage(storage);
+                continue;
+            };
+            self.pending.remove(i);
+            return Some((line, storage, cargo));
+        }
+        None
+    }
+
+    /// Remet en tête la ligne d'une mission abandonnée avant le prélèvement
+    pub fn requeue(&mut self, line: Option<OrderLine>) {
+        if let Some(line) = line {
+            self.pending.push_front(line);
+        }
+    }
+
+    /// Retire une ligne en attente, attribuée hors de `take_line`
+    pub fn take(&mut self, line: OrderLine) -> bool {
+        match self.pending.iter().position(|&l| l == line) {
+            Some(i) => self.pending.remove(i).is_some(),
+            None => false,
+        }
+    }
+
+    /// Ligne déposée au quai ; à la dernière, la commande est close et son délai enregistré
+    pub fn complete_line(&mut self, line: OrderLine, tick: u64, metrics: &mut SimMetrics) {
+        self.close_line(line, false, tick, metrics);
+    }
+
+    /// Ligne abandonnée au prélèvement, son emplacement étant vide
+    pub fn close_short(&mut self, line: OrderLine, tick: u64, metrics: &mut SimMetrics) {
+        self.close_line(line, true, tick, metrics);
+    }
+
+    /// Retire les lignes en attente dont la référence n'a plus de stock nulle part
+    pub fn drop_stockouts(&mut self, inventory: &Inventory, tick: u64, metrics: &mut SimMetrics) {
+        let (short, pending): (Vec<OrderLine>, Vec<OrderLine>) =
+            self.pending.drain(..).partition(|line| inventory.stock(line.sku) == 0);
+        self.pending = pending.into();
+        for line in short {
+            warn!("Stock-out: SKU {} for order {}", line.sku, line.order);
+            metrics.stockouts += 1;
+            self.close_line(line, true, tick, metrics);
+        }
+    }
+
+    fn close_line(&mut self, line: OrderLine, short: bool, tick: u64, metrics: &mut SimMetrics) {
+        let Some(i) = self.open.iter().position(|o| o.id == line.order) else {
+            return;
+        };
+        let order = &mut self.open[i];
+        order.remaining = order.remaining.saturating_sub(1);
+        order.short += short as u32;
+        if order.remaining == 0 {
+            let order = self.open.remove(i);
+            metrics.record_order(tick - order.arrival);
+            debug!("Order {} completed in {} ticks", order.id, tick - order.arrival);
+        }
+    }
+}
+
+/// Délai jusqu'à la prochaine arrivée de Poisson, en ticks ; au moins un tick, sans quoi un
+/// débit extrême publierait des commandes sans fin dans le même tick
+fn next_gap(rng: &mut SimRng, rate: f32) -> u64 {
+    let u: f64 = rng.random();
+    let secs = -(1.0 - u).ln() * 3600.0 / rate as f64;
+    (secs * TICK_RATE_HZ).round().max(1.0) as u64
+}
+
+/// Publie les commandes arrivées à ce tick : celles du fichier, ou les arrivées de Poisson
+/// avec des références tirées selon leur popularité et un quai au hasard. Les lignes en
+/// rupture de stock sont ensuite retirées.
+pub fn order_arrival_system(
+    mut book: ResMut<OrderBook>,
+    mut rng: ResMut<SimRng>,
+    mut metrics: ResMut<SimMetrics>,
+    config: Res<OrderConfig>,
+    zones: Res<WarehouseZones>,
+    inventory: Res<Inventory>,
+    space_time: Res<SpaceTimeTable>,
+) {
+    let docks = zones.docks().len();
+    if config.is_continuous() || docks == 0 {
+        return;
+    }
+    let tick = space_time.current_tick();
+
+    match &config.source {
+        OrderSource::Continuous => {}
+        OrderSource::Script(specs) => {
+            while let Some(spec) = specs.get(book.script_index).filter(|spec| spec.tick <= tick) {
+                // Quais et références vérifiés au démarrage (`OrderConfig::validate`)
+                let dock = spec.dock.unwrap_or_else(|| rng.random_range(0..docks));
+                book.publish(tick, &spec.lines, dock);
+                book.script_index += 1;
+                metrics.orders_received += 1;
+            }
+        }
+        &OrderSource::Poisson { rate, max_lines } => {
+            let mut next = match book.next_arrival {
+                Some(next) => next,
+                None => tick + next_gap(&mut rng, rate),
+            };
+            while next <= tick {
+                let count = rng.random_range(1..=max_lines.max(1));
+                let lines: Vec<Sku> = (0..count).map(|_| config.sample_sku(&zones, &mut rng)).collect();
+                let dock = rng.random_range(0..docks);
+                book.publish(tick, &lines, dock);
+                metrics.orders_received += 1;
+                next += next_gap(&mut rng, rate);
+            }
+            book.next_arrival = Some(next);
+        }
+    }
+    book.drop_stockouts(&inventory, tick, &mut metrics);
+}
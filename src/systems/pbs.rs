@@ -3,16 +3,34 @@ use rustc_hash::FxHashMap;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 
-use crate::components::{
-    Destination, GridPosition, Loaded, PlannedPath, Priority, Robot, RobotState, State,
-};
-use crate::core::{GridPos, HighwayGraph, SpaceTimeTable, WarehouseGrid};
+use crate::components::{Loaded, Priority, Robot, State};
+use crate::core::{GridPos, HighwayGraph, ReservationLog, SpaceTimeTable, WarehouseGrid};
+use crate::systems::planner::{MultiAgentPlanner, PlanningAgent, PlanningOutcome, PlanningProblem};
+use crate::systems::sipp::SippPlanner;
+use crate::systems::spawner::ChargingConfig;
+
+/// Planificateur mono-agent utilisé par PBS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LowLevelPlanner {
+    /// A* espace-temps, un nœud par (cellule, tick)
+    #[default]
+    SpaceTimeAStar,
+    /// Safe Interval Path Planning, un nœud par (cellule, intervalle libre)
+    Sipp,
+}
 
-#[derive(Resource)]
+/// Paramètres communs aux solveurs. Le choix du solveur lui-même (PBS, CBS, ECBS, PIBT)
+/// ne passe pas par ici mais par son nom dans le [`PlannerRegistry`](crate::systems::planner::PlannerRegistry)
+#[derive(Resource, Debug, Clone)]
 pub struct PbsConfig {
     pub horizon: u64,
     pub replan_interval: u64,
     pub heuristic_weight: f32,
+    /// Nombre max de nœuds de l'arbre (priorités ou contraintes) avant repli sur l'ordre glouton
+    pub max_tree_nodes: usize,
+    /// Facteur de sous-optimalité d'ECBS (>= 1)
+    pub suboptimality: f32,
+    pub low_level: LowLevelPlanner,
 }
 
 impl Default for PbsConfig {
@@ -21,6 +39,9 @@ impl Default for PbsConfig {
             horizon: 100,
             replan_interval: 3,
             heuristic_weight: 1.2,
+            max_tree_nodes: 32,
+            suboptimality: 1.5,
+            low_level: LowLevelPlanner::SpaceTimeAStar,
         }
     }
 }
@@ -61,10 +82,14 @@ pub struct StaticObstacles {
 }
 
 impl StaticObstacles {
+    pub fn insert(&mut self, pos: GridPos, entity: Entity) {
+        self.positions.insert(pos, entity);
+    }
+
     pub fn is_blocked(&self, pos: GridPos, exclude: Option<Entity>) -> bool {
         match self.positions.get(&pos) {
             None => false,
-            Some(&e) => exclude.map_or(true, |ex| ex != e),
+            Some(&e) => exclude != Some(e),
         }
     }
 }
@@ -88,6 +113,8 @@ impl<'a> PbsPlanner<'a> {
         Self { grid, highways, space_time, static_obstacles, config }
     }
 
+    /// Chemin tick par tick jusqu'au but, ou chemin partiel vers la cellule la plus proche
+    /// où le robot peut s'arrêter (but occupé par un robot à l'arrêt, horizon atteint)
     pub fn plan_path(
         &self,
         start: GridPos,
@@ -99,11 +126,6 @@ impl<'a> PbsPlanner<'a> {
             return Some(vec![(start, start_tick)]);
         }
 
-        // Si le goal est bloqué par un obstacle statique, pas de chemin possible
-        if self.static_obstacles.is_blocked(goal, Some(entity)) {
-            return None;
-        }
-
         let horizon_end = start_tick + self.config.horizon;
         let mut open = BinaryHeap::new();
         let mut closed: FxHashMap<(GridPos, u64), SpaceTimeNode> = FxHashMap::default();
@@ -127,11 +149,14 @@ impl<'a> PbsPlanner<'a> {
                 break;
             }
 
-            if current.pos == goal {
+            // Le robot reste sur la dernière cellule du chemin jusqu'à la prochaine replanification
+            let can_stop = self.can_hold(current.pos, current.tick, start_tick, entity);
+
+            if current.pos == goal && can_stop {
                 return Some(self.reconstruct_path(&closed, current));
             }
 
-            if best_node.as_ref().map_or(true, |b| {
+            if can_stop && best_node.as_ref().is_none_or(|b| {
                 current.pos.manhattan_distance(&goal) < b.pos.manhattan_distance(&goal)
             }) {
                 best_node = Some(current.clone());
@@ -170,9 +195,10 @@ impl<'a> PbsPlanner<'a> {
                     continue;
                 }
 
+                let move_cost = self.highways.move_cost(current.pos, neighbor);
                 self.try_add_neighbor(
                     &mut open, &closed, neighbor, next_tick,
-                    current.g_cost + 1.0, goal, current.pos, current.tick,
+                    current.g_cost + move_cost, goal, current.pos, current.tick,
                 );
             }
         }
@@ -180,6 +206,11 @@ impl<'a> PbsPlanner<'a> {
         best_node.map(|node| self.reconstruct_path(&closed, node))
     }
 
+    fn can_hold(&self, pos: GridPos, tick: u64, start_tick: u64, entity: Entity) -> bool {
+        let next_replan = start_tick + self.config.replan_interval;
+        (tick + 1..=next_replan).all(|t| self.space_time.is_free(pos, t, Some(entity)))
+    }
+
     fn is_valid_wait(&self, pos: &GridPos, to_tick: u64, entity: Entity) -> bool {
         self.space_time.is_free(*pos, to_tick, Some(entity))
     }
@@ -200,6 +231,7 @@ impl<'a> PbsPlanner<'a> {
         self.space_time.is_edge_free(*from, *to, from_tick, Some(entity))
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn try_add_neighbor(
         &self,
         open: &mut BinaryHeap<SpaceTimeNode>,
@@ -248,87 +280,384 @@ impl<'a> PbsPlanner<'a> {
     }
 }
 
-/// Système de planification PBS
-pub fn pbs_planning_system(
-    mut robots: Query<
-    (Entity, &GridPosition, &Destination, &Priority, &Loaded, &State, &mut PlannedPath),
-    With<Robot>,
-    >,
-    grid: Res<WarehouseGrid>,
-    highways: Res<HighwayGraph>,
-    mut space_time: ResMut<SpaceTimeTable>,
-    config: Res<PbsConfig>,
-) {
-    let current_tick = space_time.current_tick();
+pub type Path = Vec<(GridPos, u64)>;
+
+/// Conflit entre deux agents (sommet ou échange de cellules)
+#[derive(Clone, Copy)]
+struct Conflict {
+    a: usize,
+    b: usize,
+    tick: u64,
+}
+
+/// Nœud de l'arbre : un ordre partiel de priorités et les chemins qui le respectent
+#[derive(Clone)]
+struct PriorityNode {
+    /// `lower[i]` : agents auxquels `i` est directement prioritaire
+    lower: Vec<Vec<usize>>,
+    paths: Vec<Path>,
+    cost: f32,
+}
+
+impl PriorityNode {
+    /// `high` est-il (transitivement) prioritaire sur `low` ?
+    fn precedes(&self, high: usize, low: usize) -> bool {
+        let mut stack = vec![high];
+        let mut seen = vec![false; self.lower.len()];
+        while let Some(i) = stack.pop() {
+            for &j in &self.lower[i] {
+                if j == low {
+                    return true;
+                }
+                if !seen[j] {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+        false
+    }
+
+    /// Agents transitivement prioritaires sur chaque agent, par ordre d'indice, calculés
+    /// en un parcours par agent plutôt qu'un test par paire
+    fn higher_sets(&self) -> Vec<Vec<usize>> {
+        let n = self.lower.len();
+        let mut higher = vec![Vec::new(); n];
+        let mut seen = vec![usize::MAX; n];
+        for i in 0..n {
+            let mut stack = vec![i];
+            while let Some(k) = stack.pop() {
+                for &j in &self.lower[k] {
+                    if seen[j] != i {
+                        seen[j] = i;
+                        higher[j].push(i);
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        higher
+    }
+
+    /// `agent` et ses descendants, dans un ordre topologique
+    fn topological_from(&self, agent: usize) -> Vec<usize> {
+        let n = self.lower.len();
+        let mut reachable = vec![false; n];
+        reachable[agent] = true;
+        let mut stack = vec![agent];
+        while let Some(i) = stack.pop() {
+            for &j in &self.lower[i] {
+                if !reachable[j] {
+                    reachable[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+
+        let mut in_degree = vec![0usize; n];
+        for i in (0..n).filter(|&i| reachable[i]) {
+            for &j in &self.lower[i] {
+                in_degree[j] += 1;
+            }
+        }
+
+        let mut order = Vec::new();
+        let mut ready = vec![agent];
+        while let Some(i) = ready.pop() {
+            order.push(i);
+            for &j in &self.lower[i] {
+                in_degree[j] -= 1;
+                if in_degree[j] == 0 {
+                    ready.push(j);
+                }
+            }
+        }
+        order
+    }
+}
+
+/// Priority-Based Search : arbre de recherche qui branche sur l'ordre de priorité
+/// de chaque paire d'agents en conflit, avec un budget de nœuds
+pub struct PriorityTree<'a> {
+    grid: &'a WarehouseGrid,
+    highways: &'a HighwayGraph,
+    base: &'a SpaceTimeTable,
+    static_obstacles: &'a StaticObstacles,
+    config: &'a PbsConfig,
+    start_tick: u64,
+}
+
+impl<'a> PriorityTree<'a> {
+    pub fn new(
+        grid: &'a WarehouseGrid,
+        highways: &'a HighwayGraph,
+        base: &'a SpaceTimeTable,
+        static_obstacles: &'a StaticObstacles,
+        config: &'a PbsConfig,
+        start_tick: u64,
+    ) -> Self {
+        Self { grid, highways, base, static_obstacles, config, start_tick }
+    }
 
-    if current_tick % config.replan_interval != 0 {
-        return;
+    pub fn from_problem(problem: &PlanningProblem<'a>) -> Self {
+        Self::new(
+            problem.grid,
+            problem.highways,
+            problem.reservations,
+            problem.static_obstacles,
+            problem.config,
+            problem.start_tick,
+        )
     }
 
-    // TOUS les robots stationnaires sont des obstacles (pas seulement Idle)
-    let mut static_obstacles = StaticObstacles::default();
-    for (entity, grid_pos, _, _, _, state, _) in &robots {
-        let is_stationary = matches!(
-            state.0,
-            RobotState::Idle | RobotState::Loading | RobotState::Unloading | RobotState::Charging
-        );
-        if is_stationary {
-            static_obstacles.positions.insert(grid_pos.0, entity);
+    /// Cherche des chemins sans conflit ; `None` si le budget de nœuds est épuisé
+    pub fn search(&self, agents: &[PlanningAgent]) -> Option<Vec<Path>> {
+        // Une seule copie des réservations pour toute la recherche : chaque planification
+        // y ajoute les agents prioritaires puis les retire
+        let mut table = self.base.clone();
+        let mut root = PriorityNode {
+            lower: vec![Vec::new(); agents.len()],
+            paths: Vec::with_capacity(agents.len()),
+            cost: 0.0,
+        };
+        for (i, agent) in agents.iter().enumerate() {
+            let path = self.plan_agent(agents, &root.paths, &[], &mut table, i);
+            root.cost += self.path_cost(agent, &path);
+            root.paths.push(path);
         }
+
+        // Recherche en profondeur, l'enfant le moins coûteux d'abord
+        let mut stack = vec![root];
+        let mut expanded = 0;
+
+        while let Some(node) = stack.pop() {
+            expanded += 1;
+            if expanded > self.config.max_tree_nodes {
+                return None;
+            }
+
+            let Some(conflict) = self.first_conflict(agents, &node.paths) else {
+                return Some(node.paths);
+            };
+
+            let mut children = Vec::with_capacity(2);
+            for (high, low) in [(conflict.a, conflict.b), (conflict.b, conflict.a)] {
+                // Paire déjà ordonnée ou ordre qui créerait un cycle : branche morte
+                if node.precedes(high, low) || node.precedes(low, high) {
+                    continue;
+                }
+                let mut child = node.clone();
+                child.lower[high].push(low);
+                if self.replan_from(agents, &mut child, &mut table, low) {
+                    children.push(child);
+                }
+            }
+
+            children.sort_by(|x, y| y.cost.partial_cmp(&x.cost).unwrap_or(Ordering::Equal));
+            stack.extend(children);
+        }
+
+        None
     }
 
-    // Trie par priorité (plus bas = plus prioritaire)
-    let mut sorted_robots: Vec<_> = robots.iter_mut().collect();
-    sorted_robots.sort_by_key(|(_, _, _, prio, loaded, _, _)| {
-        let load_bonus = if loaded.0 { 0u8 } else { 50 };
-        prio.0.saturating_add(load_bonus)
-    });
+    /// Planification séquentielle dans l'ordre donné (repli historique)
+    pub fn greedy(&self, agents: &[PlanningAgent]) -> Vec<Option<Path>> {
+        let mut table = self.base.clone();
+        let mut log = ReservationLog::default();
+        agents
+            .iter()
+            .map(|agent| {
+                let path = self.plan_single(&table, agent);
+                if let Some(path) = &path {
+                    self.reserve_constraint(&mut table, agent, path, &mut log);
+                }
+                path
+            })
+            .collect()
+    }
+
+    /// Replanifie `agent` puis ses descendants qui entrent en conflit avec un agent prioritaire
+    fn replan_from(
+        &self,
+        agents: &[PlanningAgent],
+        node: &mut PriorityNode,
+        table: &mut SpaceTimeTable,
+        agent: usize,
+    ) -> bool {
+        let higher_sets = node.higher_sets();
+        for i in node.topological_from(agent) {
+            let higher = &higher_sets[i];
+            let must_replan = i == agent
+                || higher
+                    .iter()
+                    .any(|&h| self.conflict_between(agents, &node.paths[h], &node.paths[i], h, i));
+            if !must_replan {
+                continue;
+            }
+
+            let path = self.plan_agent(agents, &node.paths, higher, table, i);
+            if higher.iter().any(|&h| self.conflict_between(agents, &node.paths[h], &path, h, i)) {
+                return false;
+            }
+
+            node.cost += self.path_cost(&agents[i], &path) - self.path_cost(&agents[i], &node.paths[i]);
+            node.paths[i] = path;
+        }
+        true
+    }
+
+    /// Chemin de `agent` qui évite les agents `higher`, réservés dans `table` le temps de
+    /// la planification
+    fn plan_agent(
+        &self,
+        agents: &[PlanningAgent],
+        paths: &[Path],
+        higher: &[usize],
+        table: &mut SpaceTimeTable,
+        agent: usize,
+    ) -> Path {
+        let mut log = ReservationLog::default();
+        for &h in higher {
+            self.reserve_constraint(table, &agents[h], &paths[h], &mut log);
+        }
+
+        let path = self
+            .plan_single(table, &agents[agent])
+            .unwrap_or_else(|| vec![(agents[agent].start, self.start_tick)]);
+        table.undo(log);
+        path
+    }
 
-    space_time.cleanup(current_tick);
+    /// Chemin d'un agent seul avec le planificateur bas niveau configuré
+    fn plan_single(&self, table: &SpaceTimeTable, agent: &PlanningAgent) -> Option<Path> {
+        let PlanningAgent { entity, start, goal, .. } = *agent;
+        match self.config.low_level {
+            LowLevelPlanner::SpaceTimeAStar => {
+                PbsPlanner::new(self.grid, self.highways, table, self.static_obstacles, self.config)
+                    .plan_path(start, goal, self.start_tick, entity)
+            }
+            LowLevelPlanner::Sipp => {
+                SippPlanner::new(self.grid, self.highways, table, self.static_obstacles, self.config)
+                    .plan_path(start, goal, self.start_tick, entity)
+            }
+        }
+    }
 
-    // D'abord, réserve les positions de TOUS les robots pour éviter les collisions
-    for (entity, pos, _, _, _, _, _) in &sorted_robots {
-        // Réserve la position actuelle pour quelques ticks (sécurité)
-        for tick in current_tick..current_tick + 5 {
-            space_time.reserve(pos.0, tick, *entity);
+    /// Réserve le chemin d'un agent prioritaire et sa dernière cellule (voir `occupied`)
+    fn reserve_constraint(
+        &self,
+        table: &mut SpaceTimeTable,
+        agent: &PlanningAgent,
+        path: &Path,
+        log: &mut ReservationLog,
+    ) {
+        for (pos, tick) in self.occupied(agent, path) {
+            table.reserve_logged(pos, tick, agent.entity, log);
+        }
+        for w in path.windows(2) {
+            let ((from, tick), (to, _)) = (w[0], w[1]);
+            if from != to {
+                table.reserve_edge_logged(from, to, tick, agent.entity, log);
+            }
         }
     }
 
-    // Réserve les positions des robots stationnaires pour tout l'horizon
-    for (entity, pos, _, _, _, state, _) in &sorted_robots {
-        let is_stationary = matches!(
-            state.0,
-            RobotState::Idle | RobotState::Loading | RobotState::Unloading | RobotState::Charging
-        );
-        if is_stationary {
-            for tick in current_tick..current_tick + config.horizon {
-                space_time.reserve(pos.0, tick, *entity);
+    /// Longueur du chemin plus la distance restante au but (chemins partiels)
+    fn path_cost(&self, agent: &PlanningAgent, path: &Path) -> f32 {
+        let remaining = path
+            .last()
+            .map_or(0, |(pos, _)| pos.manhattan_distance(&agent.goal));
+        (path.len() + remaining as usize) as f32
+    }
+
+    fn first_conflict(&self, agents: &[PlanningAgent], paths: &[Path]) -> Option<Conflict> {
+        let mut vertices: FxHashMap<(GridPos, u64), usize> = FxHashMap::default();
+        let mut edges: FxHashMap<(GridPos, GridPos, u64), usize> = FxHashMap::default();
+        let mut best: Option<Conflict> = None;
+        let mut keep = |c: Conflict| {
+            if best.is_none_or(|b| c.tick < b.tick) {
+                best = Some(c);
+            }
+        };
+
+        for (i, path) in paths.iter().enumerate() {
+            for (pos, tick) in self.occupied(&agents[i], path) {
+                if let Some(&other) = vertices.get(&(pos, tick)) {
+                    keep(Conflict { a: other, b: i, tick });
+                } else {
+                    vertices.insert((pos, tick), i);
+                }
+            }
+            for w in path.windows(2) {
+                let ((from, tick), (to, _)) = (w[0], w[1]);
+                if from == to {
+                    continue;
+                }
+                if let Some(&other) = edges.get(&(to, from, tick)) {
+                    keep(Conflict { a: other, b: i, tick });
+                }
+                edges.insert((from, to, tick), i);
             }
         }
+
+        best
     }
 
-    // Planifie les robots mobiles uniquement
-    for (entity, grid_pos, dest, _, _, state, mut path) in sorted_robots {
-        // Skip robots stationnaires
-        if !matches!(state.0, RobotState::Moving) {
-            path.clear();
-            continue;
+    fn conflict_between(&self, agents: &[PlanningAgent], pa: &Path, pb: &Path, a: usize, b: usize) -> bool {
+        let cells: FxHashMap<u64, GridPos> = self
+            .occupied(&agents[a], pa)
+            .map(|(pos, tick)| (tick, pos))
+            .collect();
+        if self.occupied(&agents[b], pb).any(|(pos, tick)| cells.get(&tick) == Some(&pos)) {
+            return true;
         }
 
-        // Efface les anciennes réservations de ce robot (sauf position actuelle)
-        space_time.clear_entity_except_pos(entity, grid_pos.0, current_tick);
+        // Échange de cellules entre deux ticks consécutifs
+        pb.windows(2).any(|w| {
+            let ((from, tick), (to, _)) = (w[0], w[1]);
+            from != to && cells.get(&tick) == Some(&to) && cells.get(&(tick + 1)) == Some(&from)
+        })
+    }
 
-        let planner = PbsPlanner::new(&grid, &highways, &space_time, &static_obstacles, &config);
+    /// Cellules occupées par un agent : son chemin, puis sa dernière cellule jusqu'à l'horizon
+    /// s'il a atteint son but, sinon jusqu'à la prochaine replanification incluse
+    fn occupied<'p>(&self, agent: &PlanningAgent, path: &'p Path) -> impl Iterator<Item = (GridPos, u64)> + 'p {
+        let hold_end = |pos: GridPos| {
+            if pos == agent.goal {
+                self.start_tick + self.config.horizon
+            } else {
+                self.start_tick + self.config.replan_interval + 1
+            }
+        };
+        let hold = path
+            .last()
+            .map(|&(pos, tick)| (tick + 1..hold_end(pos)).map(move |t| (pos, t)))
+            .into_iter()
+            .flatten();
+        path.iter().copied().chain(hold)
+    }
+}
+
+/// PBS exposé comme solveur : arbre de priorités, puis ordre glouton si le budget est épuisé
+pub struct PriorityBasedSearch;
+
+impl MultiAgentPlanner for PriorityBasedSearch {
+    fn name(&self) -> &str {
+        "pbs"
+    }
 
-        if let Some(new_path) = planner.plan_path(grid_pos.0, dest.0, current_tick, entity) {
-            space_time.reserve_path(&new_path, entity);
-            *path = PlannedPath::new(new_path);
+    fn plan(&mut self, problem: &PlanningProblem) -> PlanningOutcome {
+        let tree = PriorityTree::from_problem(problem);
+        match tree.search(&problem.agents) {
+            Some(paths) => PlanningOutcome::new(paths.into_iter().map(Some).collect()),
+            None => PlanningOutcome::new(tree.greedy(&problem.agents)).with_fallback("greedy order"),
         }
     }
 }
 
 pub fn update_priorities_system(
     mut robots: Query<(&State, &Loaded, &crate::components::Battery, &mut Priority), With<Robot>>,
+    charging: Res<ChargingConfig>,
 ) {
     for (state, loaded, battery, mut priority) in &mut robots {
         let mut p = state.0.base_priority();
@@ -337,7 +666,7 @@ pub fn update_priorities_system(
             p = p.saturating_sub(15);
         }
 
-        if battery.0 < 0.2 {
+        if battery.0 < charging.threshold {
             p = p.saturating_sub(10);
         }
 
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cmp::Reverse;
+
+use crate::constants::PIBT_DISTANCE_CACHE;
+use crate::core::{DistanceField, GridPos};
+use crate::systems::planner::{MultiAgentPlanner, PlanningOutcome, PlanningProblem};
+
+/// Priority Inheritance with Backtracking : un seul pas par robot et par tick.
+/// Chaque robot choisit la cellule voisine la plus proche de son but ; s'il la prend
+/// à un robot moins prioritaire, celui-ci hérite de sa priorité et doit se pousser.
+#[derive(Default)]
+pub struct Pibt {
+    /// Champs de distance par but et dernier tick planifié qui s'en est servi
+    fields: FxHashMap<GridPos, (DistanceField, u64)>,
+    /// Nombre d'appels à `plan`, horloge de l'éviction
+    plans: u64,
+    /// Révisions de la grille et des couloirs dont dérivent les champs en cache
+    revision: (u64, u64),
+    /// Ticks écoulés depuis que chaque robot a atteint son dernier but
+    ages: FxHashMap<Entity, u32>,
+}
+
+/// État d'un tick de PIBT
+struct Step<'p> {
+    problem: &'p PlanningProblem<'p>,
+    fields: Vec<&'p DistanceField>,
+    planned: FxHashSet<Entity>,
+    current: Vec<GridPos>,
+    next: Vec<Option<GridPos>>,
+    occupied_now: FxHashMap<GridPos, usize>,
+    occupied_next: FxHashMap<GridPos, usize>,
+}
+
+impl Step<'_> {
+    fn distance(&self, agent: usize, pos: GridPos) -> u32 {
+        self.fields[agent].get(pos).unwrap_or(u32::MAX)
+    }
+
+    /// Cellule utilisable au prochain tick : libre de robot à l'arrêt et de réservation externe
+    fn is_open(&self, agent: usize, pos: GridPos) -> bool {
+        let entity = self.problem.agents[agent].entity;
+        if pos != self.current[agent]
+            && (!self.problem.grid.is_passable(pos)
+                || self.problem.static_obstacles.is_blocked(pos, Some(entity)))
+        {
+            return false;
+        }
+        // Les réservations des robots planifiés ici sont remplacées par ce tick
+        match self.problem.reservations.occupant(pos, self.problem.start_tick + 1) {
+            Some(other) => other == entity || self.planned.contains(&other),
+            None => true,
+        }
+    }
+
+    fn pibt(&mut self, agent: usize, parent: Option<usize>) -> bool {
+        let here = self.current[agent];
+        let mut candidates: Vec<GridPos> = std::iter::once(here)
+            .chain(self.problem.highways.legal_neighbors(here))
+            .filter(|&pos| self.is_open(agent, pos))
+            .collect();
+        // Plus proche du but d'abord, puis cellule libre plutôt qu'occupée
+        candidates.sort_by_key(|&pos| {
+            (self.distance(agent, pos), self.occupied_now.contains_key(&pos) && pos != here)
+        });
+
+        for pos in candidates {
+            if self.occupied_next.contains_key(&pos) {
+                continue;
+            }
+            // Pas d'échange de cellules avec le robot qui nous pousse
+            if parent.is_some_and(|p| self.current[p] == pos) {
+                continue;
+            }
+
+            self.next[agent] = Some(pos);
+            self.occupied_next.insert(pos, agent);
+
+            if let Some(&other) = self.occupied_now.get(&pos) {
+                if other != agent && self.next[other].is_none() && !self.pibt(other, Some(agent)) {
+                    continue;
+                }
+            }
+            return true;
+        }
+
+        self.next[agent] = Some(here);
+        self.occupied_next.insert(here, agent);
+        false
+    }
+}
+
+impl Pibt {
+    fn field(&mut self, problem: &PlanningProblem, goal: GridPos) {
+        let (_, used) = self
+            .fields
+            .entry(goal)
+            .or_insert_with(|| (DistanceField::towards(goal, problem.grid, problem.highways), 0));
+        *used = self.plans;
+    }
+
+    /// Évince les champs les moins récemment utilisés au-delà de `PIBT_DISTANCE_CACHE`,
+    /// sans toucher à ceux du tick courant
+    fn evict(&mut self) {
+        let excess = self.fields.len().saturating_sub(PIBT_DISTANCE_CACHE);
+        if excess == 0 {
+            return;
+        }
+        let mut stale: Vec<(u64, GridPos)> = self
+            .fields
+            .iter()
+            .filter(|(_, (_, used))| *used < self.plans)
+            .map(|(&goal, &(_, used))| (used, goal))
+            .collect();
+        stale.sort_unstable_by_key(|&(used, goal)| (used, goal.x, goal.y));
+        for (_, goal) in stale.into_iter().take(excess) {
+            self.fields.remove(&goal);
+        }
+    }
+
+    /// Nombre de champs de distance en cache
+    pub fn cached_fields(&self) -> usize {
+        self.fields.len()
+    }
+}
+
+impl MultiAgentPlanner for Pibt {
+    fn name(&self) -> &str {
+        "pibt"
+    }
+
+    fn replans_every_tick(&self) -> bool {
+        true
+    }
+
+    fn agent_counters(&self) -> Vec<(Entity, u32)> {
+        let mut ages: Vec<_> = self.ages.iter().map(|(&e, &age)| (e, age)).collect();
+        ages.sort_unstable();
+        ages
+    }
+
+    fn restore_agent_counters(&mut self, counters: &[(Entity, u32)]) {
+        self.ages = counters.iter().copied().collect();
+    }
+
+    fn plan(&mut self, problem: &PlanningProblem) -> PlanningOutcome {
+        // Les champs dépendent de la grille et des couloirs : on repart de zéro au moindre
+        // changement, obstacles compris
+        let revision = (problem.grid.revision(), problem.highways.revision());
+        if self.revision != revision {
+            self.fields.clear();
+            self.revision = revision;
+        }
+
+        self.plans += 1;
+        for agent in &problem.agents {
+            self.field(problem, agent.goal);
+            let age = self.ages.entry(agent.entity).or_default();
+            *age = if agent.start == agent.goal { 0 } else { *age + 1 };
+        }
+        let live: FxHashSet<Entity> = problem.agents.iter().map(|a| a.entity).collect();
+        self.ages.retain(|e, _| live.contains(e));
+        self.evict();
+
+        let n = problem.agents.len();
+        let mut step = Step {
+            problem,
+            fields: problem.agents.iter().map(|a| &self.fields[&a.goal].0).collect(),
+            planned: live,
+            current: problem.agents.iter().map(|a| a.start).collect(),
+            next: vec![None; n],
+            occupied_now: problem.agents.iter().enumerate().map(|(i, a)| (a.start, i)).collect(),
+            occupied_next: FxHashMap::default(),
+        };
+
+        // Un robot arrivé passe à l'arrêt au tick suivant : il ne peut plus être poussé
+        for (i, agent) in problem.agents.iter().enumerate() {
+            if agent.start == agent.goal {
+                step.next[i] = Some(agent.start);
+                step.occupied_next.insert(agent.start, i);
+            }
+        }
+
+        // Priorité du composant d'abord, puis les robots qui attendent leur but depuis le plus longtemps
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by_key(|&i| {
+            let agent = &problem.agents[i];
+            (agent.priority, Reverse(self.ages[&agent.entity]))
+        });
+
+        for i in order {
+            if step.next[i].is_none() {
+                step.pibt(i, None);
+            }
+        }
+
+        let tick = problem.start_tick;
+        let paths = problem
+            .agents
+            .iter()
+            .zip(&step.next)
+            .map(|(agent, next)| Some(vec![(agent.start, tick), (next.unwrap_or(agent.start), tick + 1)]))
+            .collect();
+        PlanningOutcome::new(paths)
+    }
+}
@@ -0,0 +1,368 @@
+use bevy::prelude::*;
+use std::time::{Duration, Instant};
+
+use crate::components::{
+    Destination, GridPosition, Loaded, PlannedPath, Priority, Robot, RobotState, State,
+};
+use crate::core::{GridPos, HighwayGraph, SpaceTimeTable, WarehouseGrid};
+use crate::systems::cbs::ConflictBasedSearch;
+use crate::systems::events::{SimEvent, SimEventKind};
+use crate::systems::metrics::SimMetrics;
+use crate::systems::pbs::{Path, PbsConfig, PriorityBasedSearch, StaticObstacles};
+use crate::systems::pibt::Pibt;
+
+/// Robot mobile à planifier
+#[derive(Debug, Clone, Copy)]
+pub struct PlanningAgent {
+    pub entity: Entity,
+    pub start: GridPos,
+    pub goal: GridPos,
+    /// Priorité effective (plus bas = plus prioritaire)
+    pub priority: u8,
+}
+
+/// Instantané du problème à résoudre à un tick de replanification
+pub struct PlanningProblem<'a> {
+    pub grid: &'a WarehouseGrid,
+    pub highways: &'a HighwayGraph,
+    /// Réservations courantes : positions actuelles et robots à l'arrêt
+    pub reservations: &'a SpaceTimeTable,
+    pub static_obstacles: &'a StaticObstacles,
+    pub config: &'a PbsConfig,
+    pub start_tick: u64,
+    /// Robots mobiles, du plus prioritaire au moins prioritaire
+    pub agents: Vec<PlanningAgent>,
+}
+
+/// Informations sur la dernière planification, affichées dans l'UI
+#[derive(Debug, Clone, Default)]
+pub struct PlannerDiagnostics {
+    pub planner: String,
+    pub tick: u64,
+    pub agents: usize,
+    pub unplanned: usize,
+    /// Méthode de repli utilisée si le solveur n'a pas abouti
+    pub fallback: Option<String>,
+    pub elapsed: Duration,
+}
+
+/// Résultat d'un solveur : un chemin par agent, dans l'ordre de `PlanningProblem::agents`
+pub struct PlanningOutcome {
+    pub paths: Vec<Option<Path>>,
+    pub fallback: Option<String>,
+}
+
+impl PlanningOutcome {
+    pub fn new(paths: Vec<Option<Path>>) -> Self {
+        Self { paths, fallback: None }
+    }
+
+    pub fn with_fallback(mut self, fallback: impl Into<String>) -> Self {
+        self.fallback = Some(fallback.into());
+        self
+    }
+}
+
+/// Solveur multi-agents interchangeable
+pub trait MultiAgentPlanner: Send + Sync + 'static {
+    /// Nom unique, utilisé pour la sélection (UI, configuration)
+    fn name(&self) -> &str;
+
+    fn plan(&mut self, problem: &PlanningProblem) -> PlanningOutcome;
+
+    /// Solveur à un pas (PIBT) : appelé à chaque tick plutôt que tous les `replan_interval`
+    fn replans_every_tick(&self) -> bool {
+        false
+    }
+
+    /// Compteurs internes par robot (ancienneté PIBT), conservés dans les snapshots
+    fn agent_counters(&self) -> Vec<(Entity, u32)> {
+        Vec::new()
+    }
+
+    fn restore_agent_counters(&mut self, _counters: &[(Entity, u32)]) {}
+}
+
+/// Solveurs disponibles et solveur actif
+#[derive(Resource)]
+pub struct PlannerRegistry {
+    planners: Vec<Box<dyn MultiAgentPlanner>>,
+    active: usize,
+    last_diagnostics: Option<PlannerDiagnostics>,
+}
+
+impl Default for PlannerRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            planners: Vec::new(),
+            active: 0,
+            last_diagnostics: None,
+        };
+        registry.register(PriorityBasedSearch);
+        registry.register(ConflictBasedSearch::optimal());
+        registry.register(ConflictBasedSearch::bounded());
+        registry.register(Pibt::default());
+        registry
+    }
+}
+
+impl PlannerRegistry {
+    /// Ajoute un solveur, ou remplace celui qui porte le même nom
+    pub fn register(&mut self, planner: impl MultiAgentPlanner) {
+        let planner: Box<dyn MultiAgentPlanner> = Box::new(planner);
+        match self.planners.iter().position(|p| p.name() == planner.name()) {
+            Some(i) => self.planners[i] = planner,
+            None => self.planners.push(planner),
+        }
+    }
+
+    /// Active le solveur `name` ; `false` s'il n'est pas enregistré
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.planners.iter().position(|p| p.name() == name) {
+            Some(i) => {
+                self.active = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn active_name(&self) -> &str {
+        self.planners.get(self.active).map_or("", |p| p.name())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.planners.iter().map(|p| p.name())
+    }
+
+    pub fn agent_counters(&self) -> Vec<(Entity, u32)> {
+        self.active().map_or_else(Vec::new, |p| p.agent_counters())
+    }
+
+    pub fn restore_agent_counters(&mut self, counters: &[(Entity, u32)]) {
+        if let Some(planner) = self.active_mut() {
+            planner.restore_agent_counters(counters);
+        }
+    }
+
+    pub fn last_diagnostics(&self) -> Option<&PlannerDiagnostics> {
+        self.last_diagnostics.as_ref()
+    }
+
+    fn active(&self) -> Option<&dyn MultiAgentPlanner> {
+        self.planners.get(self.active).map(|p| p.as_ref())
+    }
+
+    fn active_mut(&mut self) -> Option<&mut Box<dyn MultiAgentPlanner>> {
+        self.planners.get_mut(self.active)
+    }
+}
+
+/// Enregistrement d'un solveur depuis l'extérieur du crate
+pub trait PlannerAppExt {
+    fn register_planner(&mut self, planner: impl MultiAgentPlanner) -> &mut Self;
+}
+
+impl PlannerAppExt for App {
+    fn register_planner(&mut self, planner: impl MultiAgentPlanner) -> &mut Self {
+        self.init_resource::<PlannerRegistry>();
+        self.world_mut().resource_mut::<PlannerRegistry>().register(planner);
+        self
+    }
+}
+
+/// Robot vu par la planification
+type PlanningRobot = (
+    Entity,
+    &'static GridPosition,
+    &'static Destination,
+    &'static Priority,
+    &'static Loaded,
+    &'static State,
+    &'static mut PlannedPath,
+);
+
+/// Système de planification : prépare les réservations puis délègue au solveur actif
+#[allow(clippy::too_many_arguments)]
+pub fn planning_system(
+    mut robots: Query<PlanningRobot, With<Robot>>,
+    grid: Res<WarehouseGrid>,
+    highways: Res<HighwayGraph>,
+    mut space_time: ResMut<SpaceTimeTable>,
+    config: Res<PbsConfig>,
+    mut registry: ResMut<PlannerRegistry>,
+    mut metrics: ResMut<SimMetrics>,
+    mut events: MessageWriter<SimEvent>,
+) {
+    let current_tick = space_time.current_tick();
+
+    let every_tick = registry.active().is_some_and(|p| p.replans_every_tick());
+    if !current_tick.is_multiple_of(config.replan_interval) && !every_tick {
+        return;
+    }
+
+    // TOUS les robots stationnaires sont des obstacles (pas seulement Idle), là où leur
+    // dernier pas engagé les amène
+    let mut static_obstacles = StaticObstacles::default();
+    for (entity, grid_pos, _, _, _, state, path) in &robots {
+        if is_stationary(state.0) {
+            static_obstacles.insert(committed_position(grid_pos.0, path, current_tick), entity);
+        }
+    }
+
+    // Trie par priorité (plus bas = plus prioritaire), puis par entité : l'ordre de la
+    // requête dépend des archetypes et ne survit pas à une reprise de snapshot
+    let mut sorted_robots: Vec<_> = robots.iter_mut().collect();
+    sorted_robots.sort_by_key(|(entity, _, _, prio, loaded, _, _)| {
+        (effective_priority(prio, loaded), entity.index())
+    });
+
+    space_time.cleanup(current_tick);
+
+    // Les robots mobiles vont être replanifiés, ceux en panne s'arrêtent : leurs anciens
+    // chemins ne doivent pas empêcher de réserver la position actuelle des autres robots
+    for (entity, _, _, _, _, state, _) in &sorted_robots {
+        if matches!(state.0, RobotState::Moving | RobotState::Fault) {
+            space_time.clear_entity(*entity);
+        }
+    }
+
+    // D'abord, réserve les positions de TOUS les robots pour éviter les collisions
+    for (entity, pos, _, _, _, _, path) in &sorted_robots {
+        // Réserve la position actuelle pour quelques ticks (sécurité)
+        let pos = committed_position(pos.0, path, current_tick);
+        for tick in current_tick..current_tick + 5 {
+            space_time.reserve(pos, tick, *entity);
+        }
+    }
+
+    // Réserve les positions des robots stationnaires pour tout l'horizon
+    for (entity, pos, _, _, _, state, path) in &sorted_robots {
+        if is_stationary(state.0) {
+            let pos = committed_position(pos.0, path, current_tick);
+            for tick in current_tick..current_tick + config.horizon {
+                space_time.reserve(pos, tick, *entity);
+            }
+        }
+    }
+
+    // Efface les anciennes réservations des robots mobiles (sauf position actuelle)
+    let mut agents = Vec::new();
+    for (entity, grid_pos, dest, prio, loaded, state, path) in &sorted_robots {
+        if matches!(state.0, RobotState::Moving) {
+            let start = committed_position(grid_pos.0, path, current_tick);
+            // Jusqu'à la prochaine replanification incluse, comme les fins de chemin
+            space_time.clear_entity_except_pos(*entity, start, current_tick, config.replan_interval);
+            agents.push(PlanningAgent {
+                entity: *entity,
+                start,
+                goal: dest.0,
+                priority: effective_priority(prio, loaded),
+            });
+        }
+    }
+
+    let Some(planner) = registry.active_mut() else {
+        return;
+    };
+    let problem = PlanningProblem {
+        grid: &grid,
+        highways: &highways,
+        reservations: &space_time,
+        static_obstacles: &static_obstacles,
+        config: &config,
+        start_tick: current_tick,
+        agents,
+    };
+
+    let started = Instant::now();
+    let outcome = planner.plan(&problem);
+    let planned = outcome.paths.iter().flatten().count();
+    let diagnostics = PlannerDiagnostics {
+        planner: planner.name().to_string(),
+        tick: current_tick,
+        agents: problem.agents.len(),
+        unplanned: problem.agents.len().saturating_sub(planned),
+        fallback: outcome.fallback,
+        elapsed: started.elapsed(),
+    };
+    if let Some(fallback) = &diagnostics.fallback {
+        debug!("{} did not converge for {} robots, used {fallback}", diagnostics.planner, diagnostics.agents);
+    }
+    metrics.record_planning(&diagnostics);
+    registry.last_diagnostics = Some(diagnostics);
+
+    let mut new_paths = outcome.paths.into_iter();
+    for (entity, pos, _, _, _, state, mut path) in sorted_robots {
+        // Skip robots stationnaires ; un robot tombé en panne en route termine le pas engagé
+        if !matches!(state.0, RobotState::Moving) {
+            match path.current() {
+                Some(step) if step.1 <= current_tick => *path = PlannedPath::new(vec![step]),
+                _ => path.clear(),
+            }
+            continue;
+        }
+
+        // Sans chemin, le robot attend sur place plutôt que de suivre un chemin périmé
+        let planned = match new_paths.next().flatten() {
+            Some(new_path) => {
+                space_time.reserve_path(&new_path, entity);
+                *path = PlannedPath::new(new_path);
+                true
+            }
+            None => {
+                path.clear();
+                false
+            }
+        };
+        events.write(SimEvent::new(current_tick, entity, pos.0, SimEventKind::Replan { planned }));
+    }
+}
+
+/// Position du robot à la fin du tick courant : le pas prévu pour ce tick n'est exécuté
+/// qu'après la planification, il est donc déjà acquis
+pub(crate) fn committed_position(pos: GridPos, path: &PlannedPath, tick: u64) -> GridPos {
+    match path.current() {
+        Some((next, at)) if at <= tick => next,
+        _ => pos,
+    }
+}
+
+/// Cellule où s'arrête un robot qui cesse d'avancer après le tick `tick` : il suit son
+/// chemin réservé jusqu'à la replanification suivante, qui le coupe au pas engagé
+pub(crate) fn stop_position(
+    pos: GridPos,
+    path: &PlannedPath,
+    tick: u64,
+    registry: &PlannerRegistry,
+    config: &PbsConfig,
+) -> GridPos {
+    let replan = match registry.active() {
+        Some(planner) if planner.replans_every_tick() => tick + 1,
+        Some(_) => (tick / config.replan_interval + 1) * config.replan_interval,
+        None => u64::MAX,
+    };
+    path.remaining()
+        .iter()
+        .take_while(|&&(_, at)| at <= replan)
+        .last()
+        .map_or(pos, |&(cell, _)| cell)
+}
+
+#[inline]
+fn is_stationary(state: RobotState) -> bool {
+    matches!(
+        state,
+        RobotState::Idle
+            | RobotState::Loading
+            | RobotState::Unloading
+            | RobotState::Charging
+            | RobotState::Fault
+    )
+}
+
+#[inline]
+fn effective_priority(prio: &Priority, loaded: &Loaded) -> u8 {
+    let load_bonus = if loaded.0 { 0u8 } else { 50 };
+    prio.0.saturating_add(load_bonus)
+}
@@ -0,0 +1,239 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::components::{Battery, GridPosition, Loaded, Robot, State};
+use crate::core::SpaceTimeTable;
+use crate::systems::events::SimEvent;
+
+pub const TRAJECTORIES_FILE: &str = "trajectories";
+pub const EVENTS_FILE: &str = "events";
+
+/// Enregistrement d'une exécution dans un dossier : `trajectories.csv` et `events.csv`,
+/// plus leurs équivalents colonnaires `.bin` si demandé. Les lignes sont indexées par
+/// `SpaceTimeTable::current_tick` ; les fichiers sont finalisés à la destruction.
+#[derive(Resource)]
+pub struct Recorder {
+    dir: PathBuf,
+    trajectories: BufWriter<File>,
+    events: BufWriter<File>,
+    columns: Option<(TrajectoryColumns, EventColumns)>,
+    finished: bool,
+}
+
+impl Recorder {
+    pub fn new(dir: impl AsRef<Path>, binary: bool) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut trajectories = BufWriter::new(File::create(dir.join(format!("{TRAJECTORIES_FILE}.csv")))?);
+        writeln!(trajectories, "tick,entity,x,y,state,loaded,battery")?;
+        let mut events = BufWriter::new(File::create(dir.join(format!("{EVENTS_FILE}.csv")))?);
+        writeln!(events, "tick,entity,event,x,y")?;
+
+        Ok(Self {
+            dir,
+            trajectories,
+            events,
+            columns: binary.then(Default::default),
+            finished: false,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn record_robot(
+        &mut self,
+        tick: u64,
+        entity: Entity,
+        pos: &GridPosition,
+        state: &State,
+        loaded: &Loaded,
+        battery: &Battery,
+    ) -> io::Result<()> {
+        writeln!(
+            self.trajectories,
+            "{tick},{},{},{},{:?},{},{:.4}",
+            entity.index(),
+            pos.0.x,
+            pos.0.y,
+            state.0,
+            loaded.0 as u8,
+            battery.0
+        )?;
+        if let Some((columns, _)) = &mut self.columns {
+            columns.tick.push(tick);
+            columns.entity.push(entity.index());
+            columns.x.push(pos.0.x);
+            columns.y.push(pos.0.y);
+            columns.state.push(state.0 as u8);
+            columns.loaded.push(loaded.0 as u8);
+            columns.battery.push(battery.0);
+        }
+        Ok(())
+    }
+
+    pub fn record_event(&mut self, event: &SimEvent) -> io::Result<()> {
+        writeln!(
+            self.events,
+            "{},{},{},{},{}",
+            event.tick,
+            event.entity.index(),
+            event.kind.name(),
+            event.cell.x,
+            event.cell.y
+        )?;
+        if let Some((_, columns)) = &mut self.columns {
+            columns.tick.push(event.tick);
+            columns.entity.push(event.entity.index());
+            columns.kind.push(event.kind.code());
+            columns.x.push(event.cell.x);
+            columns.y.push(event.cell.y);
+        }
+        Ok(())
+    }
+
+    /// Vide les CSV et écrit les fichiers binaires ; sans effet la seconde fois
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.trajectories.flush()?;
+        self.events.flush()?;
+
+        if let Some((trajectories, events)) = &self.columns {
+            let mut w = BufWriter::new(File::create(self.dir.join(format!("{TRAJECTORIES_FILE}.bin")))?);
+            write_header(&mut w, trajectories.tick.len(), 7)?;
+            write_column(&mut w, "tick", &trajectories.tick)?;
+            write_column(&mut w, "entity", &trajectories.entity)?;
+            write_column(&mut w, "x", &trajectories.x)?;
+            write_column(&mut w, "y", &trajectories.y)?;
+            write_column(&mut w, "state", &trajectories.state)?;
+            write_column(&mut w, "loaded", &trajectories.loaded)?;
+            write_column(&mut w, "battery", &trajectories.battery)?;
+            w.flush()?;
+
+            let mut w = BufWriter::new(File::create(self.dir.join(format!("{EVENTS_FILE}.bin")))?);
+            write_header(&mut w, events.tick.len(), 5)?;
+            write_column(&mut w, "tick", &events.tick)?;
+            write_column(&mut w, "entity", &events.entity)?;
+            write_column(&mut w, "event", &events.kind)?;
+            write_column(&mut w, "x", &events.x)?;
+            write_column(&mut w, "y", &events.y)?;
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        match self.finish() {
+            Ok(()) => info!("Run recorded in {}", self.dir.display()),
+            Err(e) => error!("cannot finalize recording in {}: {e}", self.dir.display()),
+        }
+    }
+}
+
+/// Colonnes des trajectoires ; `state` suit l'ordre de `RobotState::ALL`
+#[derive(Default)]
+struct TrajectoryColumns {
+    tick: Vec<u64>,
+    entity: Vec<u32>,
+    x: Vec<i32>,
+    y: Vec<i32>,
+    state: Vec<u8>,
+    loaded: Vec<u8>,
+    battery: Vec<f32>,
+}
+
+/// Colonnes des événements ; `kind` est `SimEventKind::code`
+#[derive(Default)]
+struct EventColumns {
+    tick: Vec<u64>,
+    entity: Vec<u32>,
+    kind: Vec<u8>,
+    x: Vec<i32>,
+    y: Vec<i32>,
+}
+
+/// Format binaire colonnaire, little-endian :
+/// `b"WTSC"`, version `u16`, nombre de lignes `u64`, nombre de colonnes `u16`, puis pour
+/// chaque colonne : longueur du nom `u8`, nom UTF-8, type `u8`, et toutes les valeurs.
+const COLUMNAR_MAGIC: &[u8; 4] = b"WTSC";
+const COLUMNAR_VERSION: u16 = 1;
+
+fn write_header(w: &mut impl Write, rows: usize, columns: u16) -> io::Result<()> {
+    w.write_all(COLUMNAR_MAGIC)?;
+    w.write_all(&COLUMNAR_VERSION.to_le_bytes())?;
+    w.write_all(&(rows as u64).to_le_bytes())?;
+    w.write_all(&columns.to_le_bytes())
+}
+
+fn write_column<T: ColumnValue>(w: &mut impl Write, name: &str, values: &[T]) -> io::Result<()> {
+    w.write_all(&[name.len() as u8])?;
+    w.write_all(name.as_bytes())?;
+    w.write_all(&[T::TYPE])?;
+    for value in values {
+        value.write_le(w)?;
+    }
+    Ok(())
+}
+
+/// Type d'une colonne binaire : 0 = u8, 1 = u32, 2 = i32, 3 = u64, 4 = f32
+trait ColumnValue: Copy {
+    const TYPE: u8;
+    fn write_le(self, w: &mut impl Write) -> io::Result<()>;
+}
+
+macro_rules! column_value {
+    ($($ty:ty => $tag:expr),*) => {$(
+        impl ColumnValue for $ty {
+            const TYPE: u8 = $tag;
+            fn write_le(self, w: &mut impl Write) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+        }
+    )*};
+}
+
+column_value!(u8 => 0, u32 => 1, i32 => 2, u64 => 3, f32 => 4);
+
+/// Une ligne par robot et par tick, après l'exécution des chemins
+pub fn record_trajectories_system(
+    mut commands: Commands,
+    robots: Query<(Entity, &GridPosition, &State, &Loaded, &Battery), With<Robot>>,
+    space_time: Res<SpaceTimeTable>,
+    mut recorder: ResMut<Recorder>,
+) {
+    let tick = space_time.current_tick();
+    let mut rows: Vec<_> = robots.iter().collect();
+    rows.sort_by_key(|(entity, ..)| *entity);
+
+    for (entity, pos, state, loaded, battery) in rows {
+        if let Err(e) = recorder.record_robot(tick, entity, pos, state, loaded, battery) {
+            error!("recording stopped, cannot write to {}: {e}", recorder.dir().display());
+            commands.remove_resource::<Recorder>();
+            return;
+        }
+    }
+}
+
+pub fn record_events_system(
+    mut commands: Commands,
+    mut events: MessageReader<SimEvent>,
+    mut recorder: ResMut<Recorder>,
+) {
+    for event in events.read() {
+        if let Err(e) = recorder.record_event(event) {
+            error!("recording stopped, cannot write to {}: {e}", recorder.dir().display());
+            commands.remove_resource::<Recorder>();
+            return;
+        }
+    }
+}
@@ -0,0 +1,451 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    Destination, GridPosition, Loaded, Mission, MissionPhase, PlannedPath, Robot, RobotState,
+    State,
+};
+#[cfg(feature = "gui")]
+use crate::constants::TICK_RATE_HZ;
+use crate::core::{GridPos, SpaceTimeTable, WarehouseLayout, WarehouseZones};
+
+/// Version courante du format de relecture
+pub const REPLAY_VERSION: u32 = 1;
+pub const REPLAY_FILE: &str = "replay.ron";
+
+/// Mission en cours au moment de l'échantillon
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MissionSample {
+    pub phase: MissionPhase,
+    pub storage: GridPos,
+    pub cargo: GridPos,
+    /// Absent des enregistrements antérieurs aux chargeurs
+    #[serde(default)]
+    pub charger: Option<GridPos>,
+}
+
+/// État d'un robot, enregistré à chaque changement
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RobotSample {
+    pub tick: u64,
+    pub pos: GridPos,
+    pub state: RobotState,
+    pub loaded: bool,
+}
+
+/// Destination et mission, enregistrées à chaque changement
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GoalSample {
+    pub tick: u64,
+    pub destination: GridPos,
+    pub mission: Option<MissionSample>,
+}
+
+/// Chemin planifié, enregistré à chaque replanification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathSample {
+    pub tick: u64,
+    pub waypoints: Vec<(GridPos, u64)>,
+}
+
+/// Historique d'un robot ; `id` est l'index de son entité pendant l'enregistrement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RobotTrack {
+    pub id: u32,
+    pub samples: Vec<RobotSample>,
+    pub goals: Vec<GoalSample>,
+    pub paths: Vec<PathSample>,
+}
+
+/// Réservations de zones, enregistrées à chaque changement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneSample {
+    pub tick: u64,
+    pub storage: Vec<GridPos>,
+    pub cargo: Vec<GridPos>,
+    #[serde(default)]
+    pub chargers: Vec<GridPos>,
+}
+
+/// Enregistrement complet d'une exécution, suffisant pour la rejouer sans solveur.
+/// Chaque historique est trié par tick et n'est échantillonné qu'aux changements.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub layout: WarehouseLayout,
+    pub seed: u64,
+    pub last_tick: u64,
+    pub robots: Vec<RobotTrack>,
+    pub zones: Vec<ZoneSample>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(String),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Parse(message) => write!(f, "parse error: {message}"),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "unsupported replay version {v} (expected {REPLAY_VERSION})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Dernier élément dont le tick est `<= tick`, dans une liste triée par tick
+fn latest<T>(items: &[T], tick: u64, tick_of: impl Fn(&T) -> u64) -> Option<&T> {
+    let end = items.partition_point(|item| tick_of(item) <= tick);
+    end.checked_sub(1).map(|i| &items[i])
+}
+
+impl RobotTrack {
+    pub fn sample_at(&self, tick: u64) -> Option<&RobotSample> {
+        latest(&self.samples, tick, |s| s.tick)
+    }
+
+    pub fn goal_at(&self, tick: u64) -> Option<&GoalSample> {
+        latest(&self.goals, tick, |g| g.tick)
+    }
+
+    pub fn path_at(&self, tick: u64) -> Option<&PathSample> {
+        latest(&self.paths, tick, |p| p.tick)
+    }
+}
+
+impl Replay {
+    pub fn first_tick(&self) -> u64 {
+        self.robots
+            .iter()
+            .filter_map(|track| track.samples.first())
+            .map(|s| s.tick)
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn zones_at(&self, tick: u64) -> Option<&ZoneSample> {
+        latest(&self.zones, tick, |z| z.tick)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let text = std::fs::read_to_string(path)?;
+        let replay: Self = ron::from_str(&text).map_err(|e| ReplayError::Parse(e.to_string()))?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let text = ron::to_string(self).map_err(|e| ReplayError::Parse(e.to_string()))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Enregistrement en cours, écrit dans `path` à la sortie de l'application ; à défaut
+/// (panique, app détruite sans sortie), à la destruction
+#[derive(Resource)]
+pub struct ReplayCapture {
+    path: PathBuf,
+    replay: Replay,
+    tracks: FxHashMap<Entity, usize>,
+    /// Des échantillons n'ont pas encore été écrits
+    dirty: bool,
+}
+
+impl ReplayCapture {
+    pub fn new(path: impl Into<PathBuf>, layout: WarehouseLayout, seed: u64) -> Self {
+        Self {
+            path: path.into(),
+            replay: Replay {
+                version: REPLAY_VERSION,
+                layout,
+                seed,
+                last_tick: 0,
+                robots: Vec::new(),
+                zones: Vec::new(),
+            },
+            tracks: FxHashMap::default(),
+            dirty: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Écrit l'enregistrement tel qu'il est ; la capture peut continuer ensuite
+    pub fn save(&mut self) -> Result<(), ReplayError> {
+        self.replay.save(&self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn track(&mut self, entity: Entity) -> &mut RobotTrack {
+        let robots = &mut self.replay.robots;
+        let index = *self.tracks.entry(entity).or_insert_with(|| {
+            robots.push(RobotTrack {
+                id: entity.index(),
+                samples: Vec::new(),
+                goals: Vec::new(),
+                paths: Vec::new(),
+            });
+            robots.len() - 1
+        });
+        &mut robots[index]
+    }
+}
+
+impl Drop for ReplayCapture {
+    fn drop(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        warn!("run ended without a clean exit, saving the replay on drop");
+        match self.save() {
+            Ok(()) => info!("Replay saved to {}", self.path.display()),
+            Err(e) => error!("cannot save replay to {}: {e}", self.path.display()),
+        }
+    }
+}
+
+/// Écrit la relecture en cours, s'il y en a une
+pub fn export_replay(world: &mut World) {
+    let Some(mut capture) = world.get_resource_mut::<ReplayCapture>() else {
+        return;
+    };
+    match capture.save() {
+        Ok(()) => info!("Replay saved to {}", capture.path().display()),
+        Err(e) => error!("cannot save replay to {}: {e}", capture.path().display()),
+    }
+}
+
+/// Robot vu par l'enregistrement
+type CapturedRobot = (
+    Entity,
+    &'static GridPosition,
+    &'static Destination,
+    &'static State,
+    &'static Loaded,
+    Option<&'static Mission>,
+    Ref<'static, PlannedPath>,
+);
+
+/// Ajoute un échantillon pour chaque robot, chemin ou réservation qui a changé ce tick
+pub fn capture_replay_system(
+    robots: Query<CapturedRobot, With<Robot>>,
+    zones: Res<WarehouseZones>,
+    space_time: Res<SpaceTimeTable>,
+    mut capture: ResMut<ReplayCapture>,
+) {
+    let tick = space_time.current_tick();
+    capture.replay.last_tick = tick;
+    capture.dirty = true;
+
+    let mut rows: Vec<_> = robots.iter().collect();
+    rows.sort_by_key(|(entity, ..)| *entity);
+    for (entity, pos, dest, state, loaded, mission, path) in rows {
+        let track = capture.track(entity);
+
+        let sample = RobotSample { tick, pos: pos.0, state: state.0, loaded: loaded.0 };
+        if track.samples.last().is_none_or(|last| RobotSample { tick, ..*last } != sample) {
+            track.samples.push(sample);
+        }
+
+        let goal = GoalSample {
+            tick,
+            destination: dest.0,
+            mission: mission.map(|m| MissionSample {
+                phase: m.phase,
+                storage: m.storage_target,
+                cargo: m.cargo_target,
+                charger: m.charger,
+            }),
+        };
+        if track.goals.last().is_none_or(|last| GoalSample { tick, ..*last } != goal) {
+            track.goals.push(goal);
+        }
+
+        // `advance` marque aussi le chemin modifié, et une replanification redonne souvent
+        // la suite du chemin précédent : seuls les chemins réellement nouveaux sont gardés
+        if path.is_changed()
+            && !track.paths.last().is_some_and(|last| continues(&last.waypoints, &path.waypoints))
+        {
+            track.paths.push(PathSample { tick, waypoints: path.waypoints.clone() });
+        }
+    }
+
+    if zones.is_changed() {
+        let storage = zones.reserved_storage();
+        let cargo = zones.reserved_cargo();
+        let chargers = zones.reserved_chargers();
+        let last = capture.replay.zones.last();
+        if last.is_none_or(|z| z.storage != storage || z.cargo != cargo || z.chargers != chargers) {
+            capture.replay.zones.push(ZoneSample { tick, storage, cargo, chargers });
+        }
+    }
+}
+
+/// `new` est-il la fin de `old`, à partir de son premier point ?
+fn continues(old: &[(GridPos, u64)], new: &[(GridPos, u64)]) -> bool {
+    let Some(&(_, start)) = new.first() else {
+        return old.is_empty();
+    };
+    let skip = old.partition_point(|(_, at)| *at < start);
+    old[skip..] == *new
+}
+
+/// Relecture d'un enregistrement : le tick affiché avance avec l'horloge, sans solveur
+#[cfg(feature = "gui")]
+#[derive(Resource)]
+pub struct Playback {
+    pub replay: Replay,
+    /// Tick affiché, fractionnaire pour les vitesses lentes
+    pub position: f64,
+    pub playing: bool,
+    /// Multiplicateur de la vitesse réelle
+    pub speed: f64,
+    /// Entités de relecture, dans l'ordre de `replay.robots`
+    robots: Vec<Entity>,
+}
+
+#[cfg(feature = "gui")]
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            position: replay.first_tick() as f64,
+            replay,
+            playing: true,
+            speed: 1.0,
+            robots: Vec::new(),
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.position as u64
+    }
+
+    pub fn seek(&mut self, tick: u64) {
+        self.position = tick.min(self.replay.last_tick) as f64;
+    }
+}
+
+/// Crée une entité robot par historique ; les visuels sont ajoutés par le rendu
+#[cfg(feature = "gui")]
+pub fn spawn_playback_robots(mut commands: Commands, mut playback: ResMut<Playback>) {
+    let robots = playback
+        .replay
+        .robots
+        .iter()
+        .map(|track| {
+            let first = &track.samples[0];
+            commands
+                .spawn((
+                    Robot,
+                    GridPosition(first.pos),
+                    Destination(first.pos),
+                    Mission::new(first.pos, first.pos, first.tick),
+                    Visibility::Hidden,
+                ))
+                .id()
+        })
+        .collect();
+    playback.robots = robots;
+}
+
+#[cfg(feature = "gui")]
+pub fn playback_clock_system(time: Res<Time>, mut playback: ResMut<Playback>) {
+    if !playback.playing {
+        return;
+    }
+    let end = playback.replay.last_tick as f64;
+    playback.position =
+        (playback.position + time.delta_secs_f64() * TICK_RATE_HZ * playback.speed).min(end);
+    if playback.position >= end {
+        playback.playing = false;
+    }
+}
+
+/// Applique l'état enregistré au tick affiché ; l'interpolation visuelle fait le reste
+#[cfg(feature = "gui")]
+pub fn apply_playback_system(
+    playback: Res<Playback>,
+    mut space_time: ResMut<SpaceTimeTable>,
+    mut zones: ResMut<WarehouseZones>,
+    mut robots: Query<(
+        &mut GridPosition,
+        &mut Destination,
+        &mut State,
+        &mut Loaded,
+        &mut Mission,
+        &mut PlannedPath,
+        &mut Visibility,
+    )>,
+    mut shown: Local<Option<u64>>,
+) {
+    let tick = playback.tick();
+    if *shown == Some(tick) {
+        return;
+    }
+    *shown = Some(tick);
+    space_time.set_current_tick(tick);
+
+    match playback.replay.zones_at(tick) {
+        Some(z) => zones.set_reservations(&z.storage, &z.cargo, &z.chargers),
+        None => zones.set_reservations(&[], &[], &[]),
+    }
+
+    for (track, &entity) in playback.replay.robots.iter().zip(&playback.robots) {
+        let Ok((mut pos, mut dest, mut state, mut loaded, mut mission, mut path, mut visibility)) =
+            robots.get_mut(entity)
+        else {
+            continue;
+        };
+        let Some(sample) = track.sample_at(tick) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        pos.0 = sample.pos;
+        state.0 = sample.state;
+        if loaded.0 != sample.loaded {
+            loaded.0 = sample.loaded;
+        }
+        if let Some(goal) = track.goal_at(tick) {
+            dest.0 = goal.destination;
+            if let Some(m) = goal.mission {
+                mission.phase = m.phase;
+                mission.storage_target = m.storage;
+                mission.cargo_target = m.cargo;
+                mission.charger = m.charger;
+            }
+        }
+
+        // Prochain pas : le premier point pas encore atteint à ce tick
+        match track.path_at(tick) {
+            Some(p) => {
+                path.waypoints.clone_from(&p.waypoints);
+                path.current_index = p.waypoints.partition_point(|(_, at)| *at <= tick);
+            }
+            None => path.clear(),
+        }
+    }
+}
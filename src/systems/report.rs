@@ -0,0 +1,168 @@
+use std::fmt;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::components::{Robot, RobotState, State};
+use crate::core::{SimRng, SpaceTimeTable};
+use crate::systems::allocation::AllocatorRegistry;
+use crate::systems::metrics::{export_metrics, MetricsSummary, SimMetrics};
+use crate::systems::planner::PlannerRegistry;
+use crate::systems::replay::export_replay;
+use crate::systems::snapshot::export_snapshot;
+use crate::systems::spawner::SpawnQueue;
+use crate::systems::validation::MotionValidation;
+
+/// Bilan d'une exécution, affiché en fin de simulation
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub planner: String,
+    /// Allocateur de tâches, absent si les robots prennent le premier storage libre
+    pub allocator: Option<String>,
+    pub seed: u64,
+    pub ticks: u64,
+    /// Temps réel écoulé
+    pub elapsed: Duration,
+    pub robots_spawned: u32,
+    pub robots_total: u32,
+    /// Nombre de robots par état, dans l'ordre de `RobotState::ALL`
+    pub states: Vec<(RobotState, usize)>,
+    /// Dernier repli utilisé par le solveur, s'il y en a eu un
+    pub last_fallback: Option<String>,
+    pub metrics: MetricsSummary,
+    /// Violations de mouvement relevées, si la validation était active
+    pub violations: Option<u64>,
+}
+
+impl SimulationReport {
+    pub fn from_world(world: &mut World, elapsed: Duration) -> Self {
+        let mut states: Vec<(RobotState, usize)> =
+            RobotState::ALL.iter().map(|&s| (s, 0)).collect();
+        let mut robots = world.query_filtered::<&State, With<Robot>>();
+        for state in robots.iter(world) {
+            if let Some(entry) = states.iter_mut().find(|(s, _)| *s == state.0) {
+                entry.1 += 1;
+            }
+        }
+
+        let registry = world.resource::<PlannerRegistry>();
+        let queue = world.resource::<SpawnQueue>();
+        Self {
+            planner: registry.active_name().to_string(),
+            allocator: world
+                .get_resource::<AllocatorRegistry>()
+                .and_then(|a| a.active_name())
+                .map(str::to_string),
+            seed: world.resource::<SimRng>().seed(),
+            ticks: world.resource::<SpaceTimeTable>().current_tick(),
+            elapsed,
+            robots_spawned: queue.spawned_count,
+            robots_total: queue.total,
+            states,
+            last_fallback: registry.last_diagnostics().and_then(|d| d.fallback.clone()),
+            metrics: world.resource::<SimMetrics>().summary(),
+            violations: world.get_resource::<MotionValidation>().map(|v| v.count),
+        }
+    }
+
+    pub fn ticks_per_second(&self) -> f64 {
+        self.ticks as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== Simulation report ===")?;
+        writeln!(f, "planner        {}", self.planner)?;
+        if let Some(allocator) = &self.allocator {
+            writeln!(f, "allocator      {allocator}")?;
+        }
+        writeln!(f, "seed           {}", self.seed)?;
+        writeln!(f, "ticks          {}", self.ticks)?;
+        writeln!(
+            f,
+            "wall time      {:.2?} ({:.0} ticks/s)",
+            self.elapsed,
+            self.ticks_per_second()
+        )?;
+        writeln!(f, "robots         {}/{} spawned", self.robots_spawned, self.robots_total)?;
+        for (state, count) in &self.states {
+            writeln!(f, "  {:<12} {count}", format!("{state:?}"))?;
+        }
+        if let Some(fallback) = &self.last_fallback {
+            writeln!(f, "last fallback  {fallback}")?;
+        }
+
+        let m = &self.metrics;
+        let secs = |t: Option<f64>| t.map_or("-".to_string(), |t| format!("{t:.1}s"));
+        writeln!(
+            f,
+            "missions       {} ({:.0}/h)",
+            m.completed_missions, m.missions_per_hour
+        )?;
+        writeln!(
+            f,
+            "cycle time     mean {} p50 {} p90 {} p99 {}",
+            secs(m.cycle_time_mean),
+            secs(m.cycle_time_p50),
+            secs(m.cycle_time_p90),
+            secs(m.cycle_time_p99)
+        )?;
+        if m.orders_received > 0 {
+            writeln!(
+                f,
+                "orders         {} received, {} completed, {} stock-outs, lead time p50 {} p90 {}",
+                m.orders_received,
+                m.orders_completed,
+                m.stockouts,
+                secs(m.order_lead_time_p50),
+                secs(m.order_lead_time_p90)
+            )?;
+        }
+        if m.assignments > 0 {
+            writeln!(
+                f,
+                "assignments    {} tasks, {} steps to storage on average",
+                m.assignments,
+                m.assignment_steps_mean.map_or("-".to_string(), |s| format!("{s:.1}"))
+            )?;
+        }
+        writeln!(f, "utilization    {:.1}%", m.utilization * 100.0)?;
+        writeln!(f, "wait time      {:.1}s", m.wait_time)?;
+        writeln!(
+            f,
+            "energy         {:.1} Wh used ({} Wh/mission), {:.1} Wh charged",
+            m.energy_used_wh,
+            m.energy_per_mission_wh.map_or("-".to_string(), |e| format!("{e:.2}")),
+            m.energy_charged_wh
+        )?;
+        writeln!(
+            f,
+            "faults         {} breakdowns, {:.1}s down, availability {:.1}%",
+            m.breakdowns,
+            m.fault_time,
+            m.availability * 100.0
+        )?;
+        writeln!(
+            f,
+            "planning       {} runs, {} replans, {} failures, {} fallbacks",
+            m.planning_runs, m.replans, m.planner_failures, m.fallbacks
+        )?;
+        if let Some(violations) = self.violations {
+            writeln!(f, "violations     {violations}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Affiche le bilan à la fermeture de l'application (mode fenêtré)
+pub fn report_on_exit_system(world: &mut World) {
+    if world.resource::<Messages<AppExit>>().is_empty() {
+        return;
+    }
+    let elapsed = world.resource::<Time<Real>>().elapsed();
+    println!("{}", SimulationReport::from_world(world, elapsed));
+    export_metrics(world);
+    export_snapshot(world);
+    export_replay(world);
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::components::{Destination, GridPosition, Robot, RobotState, State};
+use crate::core::movingai::ScenarioAgent;
+use crate::core::SpaceTimeTable;
+
+/// Agents d'un scénario MovingAI à faire apparaître au démarrage
+#[derive(Resource, Default)]
+pub struct ScenarioQueue {
+    pub agents: Vec<ScenarioAgent>,
+}
+
+/// Index de l'agent dans le scénario
+#[derive(Component)]
+pub struct ScenarioAgentId(pub usize);
+
+/// Tick d'arrivée de chaque agent, pour comparer aux résultats publiés
+#[derive(Resource, Default)]
+pub struct ScenarioResults {
+    pub arrivals: Vec<Option<u64>>,
+}
+
+impl ScenarioResults {
+    pub fn is_complete(&self) -> bool {
+        !self.arrivals.is_empty() && self.arrivals.iter().all(Option::is_some)
+    }
+
+    pub fn sum_of_costs(&self) -> u64 {
+        self.arrivals.iter().flatten().sum()
+    }
+
+    pub fn makespan(&self) -> u64 {
+        self.arrivals.iter().flatten().copied().max().unwrap_or(0)
+    }
+}
+
+/// Fait apparaître tous les agents du scénario, chacun avec une seule destination
+pub fn spawn_scenario_agents(
+    mut commands: Commands,
+    queue: Res<ScenarioQueue>,
+    mut results: ResMut<ScenarioResults>,
+) {
+    results.arrivals = vec![None; queue.agents.len()];
+
+    for (i, agent) in queue.agents.iter().enumerate() {
+        commands.spawn((
+            Robot,
+            ScenarioAgentId(i),
+            GridPosition(agent.start),
+            Destination(agent.goal),
+            State(RobotState::Moving),
+        ));
+    }
+
+    info!("Spawned {} scenario agents", queue.agents.len());
+}
+
+/// Un agent arrivé à destination s'arrête et note son tick d'arrivée
+pub fn scenario_arrival_system(
+    mut robots: Query<(&ScenarioAgentId, &GridPosition, &Destination, &mut State), With<Robot>>,
+    space_time: Res<SpaceTimeTable>,
+    mut results: ResMut<ScenarioResults>,
+) {
+    let current_tick = space_time.current_tick();
+
+    for (id, pos, dest, mut state) in &mut robots {
+        if state.0 != RobotState::Moving || pos.0 != dest.0 {
+            continue;
+        }
+
+        state.0 = RobotState::Idle;
+        results.arrivals[id.0] = Some(current_tick);
+
+        if results.is_complete() {
+            info!(
+                "Scenario complete: sum of costs {}, makespan {}",
+                results.sum_of_costs(),
+                results.makespan()
+            );
+        }
+    }
+}
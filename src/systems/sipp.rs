@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::core::{GridPos, HighwayGraph, SpaceTimeTable, WarehouseGrid};
+use crate::systems::pbs::{Path, PbsConfig, StaticObstacles};
+
+const MAX_EXPANSIONS: usize = 15000;
+/// Coût d'un tick d'attente, identique à celui de `PbsPlanner`
+const WAIT_COST: f32 = 0.5;
+
+/// Plage de ticks `[start, end]` pendant laquelle une cellule est libre
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SafeInterval {
+    start: u64,
+    end: u64,
+}
+
+#[derive(Clone, Copy)]
+struct IntervalNode {
+    pos: GridPos,
+    interval: usize,
+    arrival: u64,
+    g_cost: f32,
+    f_cost: f32,
+    parent: Option<(GridPos, usize)>,
+}
+
+impl PartialEq for IntervalNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.f_cost == other.f_cost && self.arrival == other.arrival
+    }
+}
+
+impl Eq for IntervalNode {}
+
+impl Ord for IntervalNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_cost
+            .total_cmp(&self.f_cost)
+            .then_with(|| other.arrival.cmp(&self.arrival))
+    }
+}
+
+impl PartialOrd for IntervalNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Safe Interval Path Planning : la recherche porte sur des couples (cellule, intervalle
+/// libre) plutôt que sur chaque (cellule, tick). Attendre ne crée pas de nœud : on arrive
+/// dans un intervalle au plus tôt, les attentes sont développées dans le chemin final.
+pub struct SippPlanner<'a> {
+    grid: &'a WarehouseGrid,
+    highways: &'a HighwayGraph,
+    space_time: &'a SpaceTimeTable,
+    static_obstacles: &'a StaticObstacles,
+    config: &'a PbsConfig,
+    /// Intervalles libres par cellule, calculés à la première visite
+    intervals: RefCell<FxHashMap<GridPos, Vec<SafeInterval>>>,
+}
+
+impl<'a> SippPlanner<'a> {
+    pub fn new(
+        grid: &'a WarehouseGrid,
+        highways: &'a HighwayGraph,
+        space_time: &'a SpaceTimeTable,
+        static_obstacles: &'a StaticObstacles,
+        config: &'a PbsConfig,
+    ) -> Self {
+        Self {
+            grid,
+            highways,
+            space_time,
+            static_obstacles,
+            config,
+            intervals: RefCell::default(),
+        }
+    }
+
+    /// Même contrat que `PbsPlanner::plan_path` : chemin tick par tick jusqu'au but,
+    /// ou chemin partiel vers la cellule la plus proche où le robot peut s'arrêter
+    pub fn plan_path(
+        &self,
+        start: GridPos,
+        goal: GridPos,
+        start_tick: u64,
+        entity: Entity,
+    ) -> Option<Path> {
+        if start == goal {
+            return Some(vec![(start, start_tick)]);
+        }
+
+        let horizon_end = start_tick + self.config.horizon;
+        let next_replan = start_tick + self.config.replan_interval;
+        self.intervals.borrow_mut().clear();
+
+        let start_interval = self.interval_at(start, start_tick, start_tick, horizon_end, entity)?;
+        let mut open = BinaryHeap::new();
+        let mut closed: FxHashMap<(GridPos, usize), IntervalNode> = FxHashMap::default();
+        open.push(IntervalNode {
+            pos: start,
+            interval: start_interval,
+            arrival: start_tick,
+            g_cost: 0.0,
+            f_cost: self.heuristic(start, goal),
+            parent: None,
+        });
+
+        let mut best: Option<IntervalNode> = None;
+        let mut expansions = 0;
+
+        while let Some(current) = open.pop() {
+            let key = (current.pos, current.interval);
+            if closed.contains_key(&key) {
+                continue;
+            }
+            closed.insert(key, current);
+
+            expansions += 1;
+            if expansions > MAX_EXPANSIONS {
+                break;
+            }
+
+            // Le robot peut rester sur place jusqu'à la prochaine replanification incluse
+            let interval = self.interval(current.pos, current.interval, start_tick, horizon_end, entity);
+            let can_stop = interval.end >= next_replan.max(current.arrival);
+
+            if current.pos == goal && can_stop {
+                return Some(self.reconstruct_path(&closed, current));
+            }
+            if can_stop
+                && best.is_none_or(|b| {
+                    current.pos.manhattan_distance(&goal) < b.pos.manhattan_distance(&goal)
+                })
+            {
+                best = Some(current);
+            }
+
+            for neighbor in self.highways.legal_neighbors(current.pos) {
+                if !self.grid.is_passable(neighbor)
+                    || self.static_obstacles.is_blocked(neighbor, Some(entity))
+                {
+                    continue;
+                }
+
+                // Départ possible entre l'arrivée et la fin de l'intervalle courant
+                let earliest = current.arrival + 1;
+                let latest = (interval.end + 1).min(horizon_end);
+                let count = self.interval_count(neighbor, start_tick, horizon_end, entity);
+
+                for index in 0..count {
+                    let target = self.interval(neighbor, index, start_tick, horizon_end, entity);
+                    if target.start > latest || target.end < earliest {
+                        continue;
+                    }
+                    if closed.contains_key(&(neighbor, index)) {
+                        continue;
+                    }
+
+                    // Premier tick d'arrivée dont l'arête n'est pas empruntée en sens inverse
+                    let first = earliest.max(target.start);
+                    let last = latest.min(target.end);
+                    let Some(arrival) = (first..=last).find(|&t| {
+                        self.space_time.is_edge_free(current.pos, neighbor, t - 1, Some(entity))
+                    }) else {
+                        continue;
+                    };
+
+                    let wait = (arrival - 1 - current.arrival) as f32;
+                    let g_cost = current.g_cost
+                        + wait * WAIT_COST
+                        + self.highways.move_cost(current.pos, neighbor);
+                    let h = self.heuristic(neighbor, goal) * self.config.heuristic_weight;
+                    open.push(IntervalNode {
+                        pos: neighbor,
+                        interval: index,
+                        arrival,
+                        g_cost,
+                        f_cost: g_cost + h,
+                        parent: Some(key),
+                    });
+                }
+            }
+        }
+
+        best.map(|node| self.reconstruct_path(&closed, node))
+    }
+
+    /// Intervalles libres de `pos` sur `[start_tick, horizon_end]`
+    fn intervals_of(&self, pos: GridPos, start_tick: u64, horizon_end: u64, entity: Entity) -> Vec<SafeInterval> {
+        if !self.space_time.has_reservations(pos) {
+            return vec![SafeInterval { start: start_tick, end: horizon_end }];
+        }
+
+        let mut out = Vec::new();
+        let mut open: Option<u64> = None;
+        for tick in start_tick..=horizon_end {
+            let free = self.space_time.is_free(pos, tick, Some(entity));
+            match (free, open) {
+                (true, None) => open = Some(tick),
+                (false, Some(s)) => {
+                    out.push(SafeInterval { start: s, end: tick - 1 });
+                    open = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = open {
+            out.push(SafeInterval { start: s, end: horizon_end });
+        }
+        out
+    }
+
+    fn with_intervals<R>(
+        &self,
+        pos: GridPos,
+        start_tick: u64,
+        horizon_end: u64,
+        entity: Entity,
+        f: impl FnOnce(&[SafeInterval]) -> R,
+    ) -> R {
+        let mut cache = self.intervals.borrow_mut();
+        let intervals = cache
+            .entry(pos)
+            .or_insert_with(|| self.intervals_of(pos, start_tick, horizon_end, entity));
+        f(intervals)
+    }
+
+    fn interval_count(&self, pos: GridPos, start_tick: u64, horizon_end: u64, entity: Entity) -> usize {
+        self.with_intervals(pos, start_tick, horizon_end, entity, |iv| iv.len())
+    }
+
+    fn interval(&self, pos: GridPos, index: usize, start_tick: u64, horizon_end: u64, entity: Entity) -> SafeInterval {
+        self.with_intervals(pos, start_tick, horizon_end, entity, |iv| iv[index])
+    }
+
+    fn interval_at(&self, pos: GridPos, tick: u64, start_tick: u64, horizon_end: u64, entity: Entity) -> Option<usize> {
+        self.with_intervals(pos, start_tick, horizon_end, entity, |iv| {
+            iv.iter().position(|i| i.start <= tick && tick <= i.end)
+        })
+    }
+
+    #[inline]
+    fn heuristic(&self, from: GridPos, to: GridPos) -> f32 {
+        from.manhattan_distance(&to) as f32
+    }
+
+    /// Chemin tick par tick : les attentes entre deux nœuds sont développées
+    fn reconstruct_path(&self, closed: &FxHashMap<(GridPos, usize), IntervalNode>, end: IntervalNode) -> Path {
+        let mut nodes = vec![end];
+        let mut current = end.parent;
+        while let Some(key) = current {
+            let Some(&node) = closed.get(&key) else { break };
+            nodes.push(node);
+            current = node.parent;
+        }
+        nodes.reverse();
+
+        let mut path = vec![(nodes[0].pos, nodes[0].arrival)];
+        for pair in nodes.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            for tick in from.arrival + 1..to.arrival {
+                path.push((from.pos, tick));
+            }
+            path.push((to.pos, to.arrival));
+        }
+        path
+    }
+}
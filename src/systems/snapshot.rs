@@ -0,0 +1,407 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    ActionTimer, Battery, BatteryModel, BatteryUsage, Breakdown, Destination, GridPosition, Loaded, Mission,
+    MissionPhase, NextFailure, NextTask, OrderLine, PlannedPath, Priority, Robot, RobotId, RobotState, State,
+    Velocity,
+};
+use crate::core::{GridPos, SimRng, SpaceTimeTable, WarehouseLayout, WarehouseZones};
+use crate::systems::allocation::AllocatorRegistry;
+use crate::systems::inventory::Inventory;
+use crate::systems::metrics::SimMetrics;
+use crate::systems::orders::OrderBook;
+use crate::systems::planner::PlannerRegistry;
+use crate::systems::spawner::SpawnQueue;
+
+/// Version courante du format de snapshot
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MissionSnapshot {
+    pub phase: MissionPhase,
+    pub storage: GridPos,
+    pub cargo: GridPos,
+    pub charger: Option<GridPos>,
+    pub started_at: u64,
+    pub order: Option<OrderLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RobotSnapshot {
+    /// Index de l'entité à la sauvegarde, référencé par les réservations
+    pub id: u32,
+    /// Numéro `RobotId`, absent pour un robot créé hors du spawner
+    pub number: Option<u32>,
+    pub pos: GridPos,
+    pub destination: GridPos,
+    pub state: RobotState,
+    pub priority: u8,
+    pub loaded: bool,
+    pub battery: f32,
+    pub battery_model: BatteryModel,
+    pub battery_usage: BatteryUsage,
+    pub velocity: f32,
+    pub path: Vec<(GridPos, u64)>,
+    pub path_index: usize,
+    pub mission: Option<MissionSnapshot>,
+    /// Ticks restants et durée totale de l'action en cours
+    pub timer: Option<(u64, u64)>,
+    /// Panne en cours et réparation prévue
+    pub breakdown: Option<Breakdown>,
+    /// Tick de la prochaine panne aléatoire, s'il a déjà été tiré
+    pub next_failure: Option<u64>,
+    /// Tâche affectée à l'avance par l'allocateur
+    pub next_task: Option<NextTask>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZonesSnapshot {
+    pub reserved_storage: Vec<GridPos>,
+    pub reserved_cargo: Vec<GridPos>,
+    pub reserved_chargers: Vec<GridPos>,
+    pub spawn_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnQueueSnapshot {
+    pub total: u32,
+    pub spawned_count: u32,
+    pub cooldown_ticks: u64,
+    pub last_spawn_tick: u64,
+}
+
+/// Graine et position dans la suite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RngSnapshot {
+    pub seed: u64,
+    /// Position sur 128 bits, `(poids fort, poids faible)`
+    pub word_pos: (u64, u64),
+}
+
+/// État complet d'une simulation en fin de tick, sérialisé en RON.
+/// Recharger un snapshot redonne exactement la même suite de ticks, que l'on peut faire
+/// diverger en changeant le solveur, la configuration ou la graine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationSnapshot {
+    pub version: u32,
+    pub layout: WarehouseLayout,
+    pub tick: u64,
+    pub planner: String,
+    /// Allocateur de tâches actif
+    pub allocator: Option<String>,
+    /// Dans l'ordre de stockage de l'ECS, que les systèmes suivent lors de leurs itérations
+    pub robots: Vec<RobotSnapshot>,
+    /// `(position, tick, robot)`
+    pub reservations: Vec<(GridPos, u64, u32)>,
+    /// `(départ, arrivée, tick, robot)`
+    pub edge_reservations: Vec<(GridPos, GridPos, u64, u32)>,
+    /// Compteurs internes du solveur, `(robot, valeur)`
+    pub planner_counters: Vec<(u32, u32)>,
+    pub zones: ZonesSnapshot,
+    pub spawn_queue: SpawnQueueSnapshot,
+    pub rng: RngSnapshot,
+    pub metrics: SimMetrics,
+    pub orders: OrderBook,
+    pub inventory: Inventory,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Parse(String),
+    UnsupportedVersion(u32),
+    /// Le monde n'a pas les ressources de `SimulationCorePlugin`
+    MissingResource(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Parse(message) => write!(f, "parse error: {message}"),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "unsupported snapshot version {v} (expected {SNAPSHOT_VERSION})")
+            }
+            Self::MissingResource(name) => write!(f, "missing resource {name}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+fn resource<'w, R: Resource>(world: &'w World, name: &'static str) -> Result<&'w R, SnapshotError> {
+    world.get_resource::<R>().ok_or(SnapshotError::MissingResource(name))
+}
+
+impl SimulationSnapshot {
+    /// Capture l'état courant d'un monde monté par `SimulationCorePlugin`
+    pub fn capture(world: &mut World) -> Result<Self, SnapshotError> {
+        let mut query = world.query_filtered::<(
+            (Entity, Option<&RobotId>),
+            &GridPosition,
+            &Destination,
+            &State,
+            &Priority,
+            &Loaded,
+            &Battery,
+            &BatteryModel,
+            &BatteryUsage,
+            &Velocity,
+            &PlannedPath,
+            Option<&Mission>,
+            Option<&ActionTimer>,
+            (Option<&Breakdown>, Option<&NextFailure>),
+            Option<&NextTask>,
+        ), With<Robot>>();
+        let robots: Vec<RobotSnapshot> = query
+            .iter(world)
+            .map(|((entity, number), pos, dest, state, prio, loaded, battery, model, usage, vel, path, mission, timer, (breakdown, next_failure), next)| {
+                RobotSnapshot {
+                    id: entity.index(),
+                    number: number.map(|n| n.0),
+                    pos: pos.0,
+                    destination: dest.0,
+                    state: state.0,
+                    priority: prio.0,
+                    loaded: loaded.0,
+                    battery: battery.0,
+                    battery_model: model.clone(),
+                    battery_usage: *usage,
+                    velocity: vel.0,
+                    path: path.waypoints.clone(),
+                    path_index: path.current_index,
+                    mission: mission.map(|m| MissionSnapshot {
+                        phase: m.phase,
+                        storage: m.storage_target,
+                        cargo: m.cargo_target,
+                        charger: m.charger,
+                        started_at: m.started_at,
+                        order: m.order,
+                    }),
+                    timer: timer.map(|t| (t.remaining, t.total)),
+                    breakdown: breakdown.copied(),
+                    next_failure: next_failure.map(|failure| failure.0),
+                    next_task: next.copied(),
+                }
+            })
+            .collect();
+
+        let space_time = resource::<SpaceTimeTable>(world, "SpaceTimeTable")?;
+        let mut reservations: Vec<_> = space_time
+            .reservations()
+            .map(|(pos, tick, e)| (pos, tick, e.index()))
+            .collect();
+        reservations.sort_unstable_by_key(|&(pos, tick, e)| (tick, pos.x, pos.y, e));
+        let mut edge_reservations: Vec<_> = space_time
+            .edge_reservations()
+            .map(|(key, e)| (key.from, key.to, key.tick, e.index()))
+            .collect();
+        edge_reservations.sort_unstable_by_key(|&(from, to, tick, e)| (tick, from.x, from.y, to.x, to.y, e));
+
+        let zones = resource::<WarehouseZones>(world, "WarehouseZones")?;
+        let queue = resource::<SpawnQueue>(world, "SpawnQueue")?;
+        let rng = resource::<SimRng>(world, "SimRng")?;
+        let registry = resource::<PlannerRegistry>(world, "PlannerRegistry")?;
+
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            layout: resource::<WarehouseLayout>(world, "WarehouseLayout")?.clone(),
+            tick: space_time.current_tick(),
+            planner: registry.active_name().to_string(),
+            allocator: world
+                .get_resource::<AllocatorRegistry>()
+                .and_then(|a| a.active_name())
+                .map(str::to_string),
+            robots,
+            reservations,
+            edge_reservations,
+            planner_counters: registry
+                .agent_counters()
+                .into_iter()
+                .map(|(e, n)| (e.index(), n))
+                .collect(),
+            zones: ZonesSnapshot {
+                reserved_storage: zones.reserved_storage(),
+                reserved_cargo: zones.reserved_cargo(),
+                reserved_chargers: zones.reserved_chargers(),
+                spawn_index: zones.spawn_index(),
+            },
+            spawn_queue: SpawnQueueSnapshot {
+                total: queue.total,
+                spawned_count: queue.spawned_count,
+                cooldown_ticks: queue.cooldown_ticks,
+                last_spawn_tick: queue.last_spawn_tick,
+            },
+            rng: RngSnapshot {
+                seed: rng.seed(),
+                word_pos: ((rng.word_pos() >> 64) as u64, rng.word_pos() as u64),
+            },
+            metrics: resource::<SimMetrics>(world, "SimMetrics")?.clone(),
+            orders: resource::<OrderBook>(world, "OrderBook")?.clone(),
+            inventory: resource::<Inventory>(world, "Inventory")?.clone(),
+        })
+    }
+
+    /// Recrée robots et ressources dans un monde monté par `SimulationCorePlugin` avec le
+    /// layout du snapshot. Les robots déjà présents sont supprimés.
+    pub fn restore(&self, world: &mut World) -> Result<(), SnapshotError> {
+        resource::<WarehouseZones>(world, "WarehouseZones")?;
+        resource::<PlannerRegistry>(world, "PlannerRegistry")?;
+
+        let existing: Vec<Entity> = world
+            .query_filtered::<Entity, With<Robot>>()
+            .iter(world)
+            .collect();
+        for entity in existing {
+            world.despawn(entity);
+        }
+
+        // Robots recréés dans l'ordre de leurs anciens index, pour garder leur ordre relatif
+        let mut robots: Vec<&RobotSnapshot> = self.robots.iter().collect();
+        robots.sort_unstable_by_key(|r| r.id);
+        let mut entities = FxHashMap::default();
+        for robot in robots {
+            let core = (
+                Robot,
+                GridPosition(robot.pos),
+                Destination(robot.destination),
+                State(robot.state),
+                Priority(robot.priority),
+                Loaded(robot.loaded),
+                Battery(robot.battery),
+                robot.battery_model.clone(),
+                robot.battery_usage,
+                Velocity(robot.velocity),
+                PlannedPath { waypoints: robot.path.clone(), current_index: robot.path_index },
+            );
+            let mission = robot.mission.map(|m| Mission {
+                phase: m.phase,
+                storage_target: m.storage,
+                cargo_target: m.cargo,
+                charger: m.charger,
+                started_at: m.started_at,
+                order: m.order,
+            });
+            let timer = robot.timer.map(|(remaining, total)| ActionTimer { remaining, total });
+
+            let mut entity = world.spawn(core);
+            if let Some(mission) = mission {
+                entity.insert(mission);
+            }
+            if let Some(timer) = timer {
+                entity.insert(timer);
+            }
+            if let Some(breakdown) = robot.breakdown {
+                entity.insert(breakdown);
+            }
+            if let Some(number) = robot.number {
+                entity.insert(RobotId(number));
+            }
+            if let Some(at) = robot.next_failure {
+                entity.insert(NextFailure(at));
+            }
+            if let Some(next) = robot.next_task {
+                entity.insert(next);
+            }
+            entities.insert(robot.id, entity.id());
+        }
+
+        let mut space_time = SpaceTimeTable::default();
+        space_time.set_current_tick(self.tick);
+        for &(pos, tick, id) in &self.reservations {
+            if let Some(&entity) = entities.get(&id) {
+                space_time.reserve(pos, tick, entity);
+            }
+        }
+        for &(from, to, tick, id) in &self.edge_reservations {
+            if let Some(&entity) = entities.get(&id) {
+                space_time.reserve_edge(from, to, tick, entity);
+            }
+        }
+        world.insert_resource(space_time);
+
+        let mut zones = world.resource_mut::<WarehouseZones>();
+        zones.set_reservations(
+            &self.zones.reserved_storage,
+            &self.zones.reserved_cargo,
+            &self.zones.reserved_chargers,
+        );
+        zones.set_spawn_index(self.zones.spawn_index);
+
+        world.insert_resource(SpawnQueue {
+            total: self.spawn_queue.total,
+            spawned_count: self.spawn_queue.spawned_count,
+            cooldown_ticks: self.spawn_queue.cooldown_ticks,
+            last_spawn_tick: self.spawn_queue.last_spawn_tick,
+        });
+        let (high, low) = self.rng.word_pos;
+        world.insert_resource(SimRng::resume(self.rng.seed, (high as u128) << 64 | low as u128));
+        world.insert_resource(self.metrics.clone());
+        world.insert_resource(self.orders.clone());
+        world.insert_resource(self.inventory.clone());
+
+        let counters: Vec<(Entity, u32)> = self
+            .planner_counters
+            .iter()
+            .filter_map(|&(id, n)| entities.get(&id).map(|&e| (e, n)))
+            .collect();
+        let mut registry = world.resource_mut::<PlannerRegistry>();
+        registry.select(&self.planner);
+        registry.restore_agent_counters(&counters);
+        if let Some(mut allocators) = world.get_resource_mut::<AllocatorRegistry>() {
+            allocators.select(self.allocator.as_deref());
+        }
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let text = std::fs::read_to_string(path)?;
+        let snapshot: Self =
+            ron::from_str(&text).map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(snapshot.version));
+        }
+        snapshot.layout.validate().map_err(|e| SnapshotError::Parse(format!("layout {e}")))?;
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| SnapshotError::Parse(e.to_string()))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Fichier où écrire un snapshot en fin d'exécution
+#[derive(Resource, Debug, Clone)]
+pub struct SnapshotExport {
+    pub path: PathBuf,
+}
+
+/// Capture et écrit un snapshot dans `path`
+pub fn save_snapshot(world: &mut World, path: &Path) {
+    match SimulationSnapshot::capture(world).and_then(|s| s.save(path)) {
+        Ok(()) => info!("Snapshot saved to {}", path.display()),
+        Err(e) => error!("cannot save snapshot to {}: {e}", path.display()),
+    }
+}
+
+/// Écrit le snapshot de fin d'exécution si un fichier est configuré
+pub fn export_snapshot(world: &mut World) {
+    if let Some(export) = world.get_resource::<SnapshotExport>() {
+        let path = export.path.clone();
+        save_snapshot(world, &path);
+    }
+}
@@ -1,11 +1,20 @@
 use bevy::prelude::*;
 
 use crate::components::{
-    ActionTimer, Destination, GridPosition, Loaded, Mission, MissionPhase,
-    Robot, RobotState, State,
+    ActionTimer, Battery, Destination, GridPosition, Loaded, Mission, MissionPhase,
+    NextTask, Robot, RobotId, RobotState, Sku, State,
 };
-use crate::constants::{DROPOFF_DURATION, PICKUP_DURATION, ROBOT_COUNT};
-use crate::core::{SpaceTimeTable, WarehouseGrid, WarehouseZones};
+use crate::constants::{
+    CHARGE_RESUME_LEVEL, CHARGE_THRESHOLD, DROPOFF_DURATION, PICKUP_DURATION, ROBOT_COUNT,
+};
+use crate::core::GridPos;
+use crate::core::{SimRng, SpaceTimeTable, WarehouseZones};
+use crate::systems::allocation::{release_task, AllocatorRegistry};
+use crate::systems::battery::RobotModels;
+use crate::systems::events::{SimEvent, SimEventKind};
+use crate::systems::inventory::{Inventory, Pick};
+use crate::systems::metrics::SimMetrics;
+use crate::systems::orders::{OrderBook, OrderConfig};
 
 #[derive(Resource)]
 pub struct SpawnQueue {
@@ -26,21 +35,59 @@ impl Default for SpawnQueue {
     }
 }
 
+/// Durées des actions de mission, en secondes
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MissionTimings {
+    pub pickup: f32,
+    pub dropoff: f32,
+}
+
+impl Default for MissionTimings {
+    fn default() -> Self {
+        Self {
+            pickup: PICKUP_DURATION,
+            dropoff: DROPOFF_DURATION,
+        }
+    }
+}
+
+/// Recharge des batteries, niveaux en fraction de la capacité
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ChargingConfig {
+    /// Un robot sans charge part se recharger en dessous de ce niveau
+    pub threshold: f32,
+    /// Niveau auquel le robot quitte le chargeur
+    pub resume_level: f32,
+}
+
+impl Default for ChargingConfig {
+    fn default() -> Self {
+        Self {
+            threshold: CHARGE_THRESHOLD,
+            resume_level: CHARGE_RESUME_LEVEL,
+        }
+    }
+}
+
 impl SpawnQueue {
     pub fn is_complete(&self) -> bool {
         self.spawned_count >= self.total
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn sequential_spawn_system(
     mut commands: Commands,
     mut queue: ResMut<SpawnQueue>,
     mut zones: ResMut<WarehouseZones>,
+    mut rng: ResMut<SimRng>,
+    mut events: MessageWriter<SimEvent>,
     space_time: Res<SpaceTimeTable>,
-    grid: Res<WarehouseGrid>,
+    models: Res<RobotModels>,
+    orders: Res<OrderConfig>,
+    inventory: Res<Inventory>,
+    allocators: Res<AllocatorRegistry>,
     robots: Query<&GridPosition, With<Robot>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     if queue.is_complete() {
         return;
@@ -52,79 +99,322 @@ pub fn sequential_spawn_system(
         return;
     }
 
-    let spawn_pos = zones.next_spawn();
+    let Some(spawn_pos) = zones.next_spawn() else {
+        return;
+    };
 
     if robots.iter().any(|pos| pos.0 == spawn_pos) {
         return;
     }
 
+    // Flux de commandes ou allocateur : le robot attend sa première tâche sur place
+    if !orders.is_continuous() || allocators.is_active() {
+        commands.spawn((
+            Robot,
+            RobotId(queue.spawned_count),
+            GridPosition(spawn_pos),
+            Destination(spawn_pos),
+            State(RobotState::Idle),
+            Loaded(false),
+            models.model(queue.spawned_count),
+            Mission::new(spawn_pos, spawn_pos, current_tick),
+        ));
+        queue.spawned_count += 1;
+        queue.last_spawn_tick = current_tick;
+        return;
+    }
+
     // Réserve storage et cargo - skip si aucun disponible
-    let Some(storage_target) = zones.reserve_storage() else {
+    let Some(storage_target) = zones.reserve_storage_where(&mut rng, |pos| inventory.is_stocked(pos)) else {
         return;
     };
-    let Some(cargo_target) = zones.reserve_cargo() else {
+    let Some(cargo_target) = zones.reserve_cargo(&mut rng) else {
         zones.release_storage(storage_target);
         return;
     };
 
-    let (wx, wz) = grid.grid_to_world(spawn_pos);
-
-    let mesh = meshes.add(Cuboid::new(0.6, 0.4, 0.6));
-    let material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.2, 0.6, 0.2),
-        ..default()
-    });
-
-    commands.spawn((
+    // Le mesh et le transform sont ajoutés par le rendu, s'il est actif
+    let entity = commands.spawn((
         Robot,
+        RobotId(queue.spawned_count),
         GridPosition(spawn_pos),
         Destination(storage_target),
         State(RobotState::Moving),
         Loaded(false),
-        Mission::new(storage_target, cargo_target),
-        Mesh3d(mesh),
-        MeshMaterial3d(material),
-        Transform::from_xyz(wx, 0.2, wz),
-    ));
+        models.model(queue.spawned_count),
+        Mission::new(storage_target, cargo_target, current_tick),
+    )).id();
+    events.write(SimEvent::new(current_tick, entity, storage_target, SimEventKind::MissionAssigned));
 
     queue.spawned_count += 1;
     queue.last_spawn_tick = current_tick;
 }
 
+/// Attribution des missions pendant un tick
+struct Dispatcher<'a, 'w> {
+    zones: &'a mut WarehouseZones,
+    orders: &'a mut OrderBook,
+    order_config: &'a OrderConfig,
+    inventory: &'a mut Inventory,
+    /// Quais, calculés une fois par tick si le flux de commandes est actif
+    docks: Vec<Vec<GridPos>>,
+    /// Les tâches viennent de l'allocateur, jamais d'une attribution immédiate
+    allocated: bool,
+    rng: &'a mut SimRng,
+    events: &'a mut MessageWriter<'w, SimEvent>,
+    charging: &'a ChargingConfig,
+    tick: u64,
+}
+
+impl Dispatcher<'_, '_> {
+    /// Dernière unité d'une référence prélevée : hors flux de commandes la rupture est
+    /// comptée une fois, sinon les lignes en attente de la référence sont closes tout de suite
+    fn detect_stockout(&mut self, sku: Sku, metrics: &mut SimMetrics) {
+        if self.inventory.stock(sku) > 0 {
+            return;
+        }
+        if self.order_config.is_continuous() {
+            warn!("Stock-out: SKU {sku} is exhausted");
+            metrics.stockouts += 1;
+        } else {
+            self.orders.drop_stockouts(self.inventory, self.tick, metrics);
+        }
+    }
+
+    /// Mission suivante d'un robot libre : un cargo s'il est encore chargé (retour de panne),
+    /// sinon recharge si la batterie est basse et qu'un chargeur est libre, sinon la tâche
+    /// donnée par l'allocateur, ou à défaut d'allocateur une ligne de commande, ou un storage
+    /// et un cargo hors flux de commandes. Une tâche `next` non prise est libérée.
+    /// Retourne la destination, ou None si rien n'est disponible.
+    fn next_mission(
+        &mut self,
+        entity: Entity,
+        pos: GridPos,
+        battery: f32,
+        loaded: bool,
+        mission: &mut Mission,
+        next: Option<&NextTask>,
+    ) -> Option<GridPos> {
+        if let Some(task) = next.filter(|_| loaded) {
+            release_task(self.zones, self.orders, task);
+        }
+        if loaded {
+            // Le chargement d'une commande va à son quai
+            let cargo = match mission.order.and_then(|line| self.docks.get(line.dock)) {
+                Some(dock) => self.zones.reserve_cargo_near(dock, pos)?,
+                None => self.zones.reserve_cargo(self.rng)?,
+            };
+            mission.phase = MissionPhase::GoingToCargo;
+            mission.cargo_target = cargo;
+            self.events.write(SimEvent::new(self.tick, entity, cargo, SimEventKind::MissionAssigned));
+            return Some(cargo);
+        }
+
+        if battery < self.charging.threshold {
+            if let Some(charger) = self.zones.reserve_charger(pos) {
+                if let Some(task) = next {
+                    release_task(self.zones, self.orders, task);
+                }
+                mission.phase = MissionPhase::GoingToCharger;
+                mission.charger = Some(charger);
+                self.events.write(SimEvent::new(self.tick, entity, charger, SimEventKind::ChargerAssigned));
+                return Some(charger);
+            }
+            // Aucun chargeur libre : le robot continue tant que sa batterie tient
+        }
+
+        if let Some(task) = next {
+            *mission = Mission { order: task.order, ..Mission::new(task.storage, task.cargo, self.tick) };
+            self.events.write(SimEvent::new(self.tick, entity, task.storage, SimEventKind::MissionAssigned));
+            return Some(task.storage);
+        }
+        if self.allocated {
+            return None;
+        }
+
+        if !self.order_config.is_continuous() {
+            let (line, storage, cargo) = self.orders.take_line(self.inventory, self.zones, &self.docks, pos)?;
+            *mission = Mission { order: Some(line), ..Mission::new(storage, cargo, self.tick) };
+            self.events.write(SimEvent::new(self.tick, entity, storage, SimEventKind::MissionAssigned));
+            return Some(storage);
+        }
+
+        let inventory = &*self.inventory;
+        let new_storage = self.zones.reserve_storage_where(self.rng, |pos| inventory.is_stocked(pos));
+        let new_cargo = self.zones.reserve_cargo(self.rng);
+
+        match (new_storage, new_cargo) {
+            (Some(storage), Some(cargo)) => {
+                *mission = Mission::new(storage, cargo, self.tick);
+                self.events.write(SimEvent::new(self.tick, entity, storage, SimEventKind::MissionAssigned));
+                Some(storage)
+            }
+            (Some(storage), None) => {
+                // Pas de cargo dispo, libère storage et attend
+                self.zones.release_storage(storage);
+                None
+            }
+            (None, Some(cargo)) => {
+                // Pas de storage dispo, libère cargo et attend
+                self.zones.release_cargo(cargo);
+                None
+            }
+            // Rien de dispo, attend
+            (None, None) => None,
+        }
+    }
+}
+
+/// Libère les cellules que la mission réserve encore, quand le robot l'abandonne, et remet
+/// en attente sa ligne de commande si rien n'est encore prélevé.
+/// Un robot `Idle` attend une mission et ne réserve rien ; `keep` reste réservée
+/// (cellule bloquée par un robot en panne) et est renvoyée si la mission la réservait.
+pub fn release_mission(
+    zones: &mut WarehouseZones,
+    orders: &mut OrderBook,
+    mission: &Mission,
+    state: RobotState,
+    keep: Option<GridPos>,
+) -> Option<GridPos> {
+    if state == RobotState::Idle {
+        return None;
+    }
+    let held: [Option<GridPos>; 3] = match mission.phase {
+        MissionPhase::GoingToStorage | MissionPhase::PickingUp => {
+            orders.requeue(mission.order);
+            [Some(mission.storage_target), Some(mission.cargo_target), None]
+        }
+        MissionPhase::GoingToCargo | MissionPhase::DroppingOff => [None, Some(mission.cargo_target), None],
+        MissionPhase::GoingToCharger | MissionPhase::Charging => [None, None, mission.charger],
+    };
+
+    let [storage, cargo, charger] = held.map(|cell| cell.filter(|&pos| Some(pos) != keep));
+    if let Some(storage) = storage {
+        zones.release_storage(storage);
+    }
+    if let Some(cargo) = cargo {
+        zones.release_cargo(cargo);
+    }
+    if let Some(charger) = charger {
+        zones.release_charger(charger);
+    }
+    keep.filter(|pos| held.contains(&Some(*pos)))
+}
+
+/// Robot vu par l'avancement des missions
+type MissionRobot = (
+    Entity,
+    &'static GridPosition,
+    &'static Battery,
+    &'static mut Mission,
+    &'static mut Destination,
+    &'static mut State,
+    &'static mut Loaded,
+    Option<&'static mut ActionTimer>,
+    Option<&'static NextTask>,
+);
+
+#[allow(clippy::too_many_arguments)]
 pub fn mission_progression_system(
     mut commands: Commands,
-    mut robots: Query<(
-        Entity,
-        &GridPosition,
-        &mut Mission,
-        &mut Destination,
-        &mut State,
-        &mut Loaded,
-        Option<&mut ActionTimer>,
-    ), With<Robot>>,
+    mut robots: Query<MissionRobot, With<Robot>>,
     mut zones: ResMut<WarehouseZones>,
-    time: Res<Time>,
+    mut orders: ResMut<OrderBook>,
+    mut inventory: ResMut<Inventory>,
+    mut rng: ResMut<SimRng>,
+    mut metrics: ResMut<SimMetrics>,
+    mut events: MessageWriter<SimEvent>,
+    timings: Res<MissionTimings>,
+    charging: Res<ChargingConfig>,
+    order_config: Res<OrderConfig>,
+    allocators: Res<AllocatorRegistry>,
+    space_time: Res<SpaceTimeTable>,
 ) {
-    for (entity, pos, mut mission, mut dest, mut state, mut loaded, timer) in &mut robots {
+    let current_tick = space_time.current_tick();
+    let docks = if order_config.is_continuous() { Vec::new() } else { zones.docks() };
+    let mut dispatcher = Dispatcher {
+        zones: &mut zones,
+        orders: &mut orders,
+        order_config: &order_config,
+        inventory: &mut inventory,
+        docks,
+        allocated: allocators.is_active(),
+        rng: &mut rng,
+        events: &mut events,
+        charging: &charging,
+        tick: current_tick,
+    };
+
+    // Ordre des entités plutôt que de la requête : les tirages et réservations en dépendent
+    let mut robots: Vec<_> = robots.iter_mut().collect();
+    robots.sort_unstable_by_key(|(entity, ..)| entity.index());
+
+    for (entity, pos, battery, mut mission, mut dest, mut state, mut loaded, timer, next) in robots {
+        match state.0 {
+            RobotState::Fault => continue,
+            // Robot en attente : réessaie à chaque tick
+            RobotState::Idle => {
+                // La tâche affectée à l'avance est prise ou libérée par `next_mission`
+                if next.is_some() {
+                    commands.entity(entity).remove::<NextTask>();
+                }
+                if let Some(target) = dispatcher.next_mission(entity, pos.0, battery.0, loaded.0, &mut mission, next) {
+                    dest.0 = target;
+                    state.0 = RobotState::Moving;
+                }
+                continue;
+            }
+            _ => {}
+        }
+
         match mission.phase {
             MissionPhase::GoingToStorage => {
                 if pos.0 == mission.storage_target {
                     mission.phase = MissionPhase::PickingUp;
                     state.0 = RobotState::Loading;
-                    commands.entity(entity).insert(ActionTimer::new(PICKUP_DURATION));
+                    dispatcher.events.write(SimEvent::new(current_tick, entity, pos.0, SimEventKind::ArrivedAtStorage));
+                    commands.entity(entity).insert(ActionTimer::from_secs(timings.pickup));
                 }
             }
             MissionPhase::PickingUp => {
                 if let Some(mut t) = timer {
-                    if t.tick(time.delta_secs()) {
-                        loaded.0 = true;
-                        mission.phase = MissionPhase::GoingToCargo;
-                        dest.0 = mission.cargo_target;
-                        state.0 = RobotState::Moving;
+                    if t.tick() {
                         commands.entity(entity).remove::<ActionTimer>();
 
-                        // Libère le storage
-                        zones.release_storage(mission.storage_target);
+                        // Prélève une unité et libère le storage
+                        dispatcher.zones.release_storage(mission.storage_target);
+                        let sku = mission.order.map(|line| line.sku);
+                        let picked = dispatcher.inventory.pick(mission.storage_target, sku);
+                        if picked != Pick::Empty {
+                            loaded.0 = true;
+                            mission.phase = MissionPhase::GoingToCargo;
+                            dest.0 = mission.cargo_target;
+                            state.0 = RobotState::Moving;
+                            if let Pick::Picked(sku) = picked {
+                                dispatcher.detect_stockout(sku, &mut metrics);
+                            }
+                            dispatcher.events.write(SimEvent::new(current_tick, entity, pos.0, SimEventKind::PickupDone));
+                            continue;
+                        }
+
+                        // Emplacement vide : la ligne est close en rupture et le robot
+                        // repart à vide, sans rien livrer
+                        warn!("Stock-out: empty slot {:?} picked by {:?}", mission.storage_target, entity);
+                        metrics.stockouts += 1;
+                        dispatcher.zones.release_cargo(mission.cargo_target);
+                        if let Some(line) = mission.order.take() {
+                            dispatcher.orders.close_short(line, current_tick, &mut metrics);
+                        }
+                        if next.is_some() {
+                            commands.entity(entity).remove::<NextTask>();
+                        }
+                        match dispatcher.next_mission(entity, pos.0, battery.0, loaded.0, &mut mission, next) {
+                            Some(target) => {
+                                dest.0 = target;
+                                state.0 = RobotState::Moving;
+                            }
+                            None => state.0 = RobotState::Idle,
+                        }
                     }
                 }
             }
@@ -132,48 +422,64 @@ pub fn mission_progression_system(
                 if pos.0 == mission.cargo_target {
                     mission.phase = MissionPhase::DroppingOff;
                     state.0 = RobotState::Unloading;
-                    commands.entity(entity).insert(ActionTimer::new(DROPOFF_DURATION));
+                    dispatcher.events.write(SimEvent::new(current_tick, entity, pos.0, SimEventKind::ArrivedAtCargo));
+                    commands.entity(entity).insert(ActionTimer::from_secs(timings.dropoff));
                 }
             }
             MissionPhase::DroppingOff => {
                 if let Some(mut t) = timer {
-                    if t.tick(time.delta_secs()) {
+                    if t.tick() {
                         loaded.0 = false;
                         commands.entity(entity).remove::<ActionTimer>();
 
                         // Libère le cargo actuel
-                        zones.release_cargo(mission.cargo_target);
-
-                        // Réserve nouvelle mission
-                        let new_storage = zones.reserve_storage();
-                        let new_cargo = zones.reserve_cargo();
-
-                        match (new_storage, new_cargo) {
-                            (Some(storage), Some(cargo)) => {
-                                mission.storage_target = storage;
-                                mission.cargo_target = cargo;
-                                mission.phase = MissionPhase::GoingToStorage;
-                                dest.0 = storage;
+                        dispatcher.zones.release_cargo(mission.cargo_target);
+                        metrics.record_mission(current_tick - mission.started_at);
+                        if let Some(line) = mission.order.take() {
+                            dispatcher.orders.complete_line(line, current_tick, &mut metrics);
+                        }
+                        dispatcher.events.write(SimEvent::new(current_tick, entity, pos.0, SimEventKind::DropoffDone));
+
+                        // Réserve nouvelle mission, sinon attend
+                        if next.is_some() {
+                            commands.entity(entity).remove::<NextTask>();
+                        }
+                        match dispatcher.next_mission(entity, pos.0, battery.0, loaded.0, &mut mission, next) {
+                            Some(target) => {
+                                dest.0 = target;
                                 state.0 = RobotState::Moving;
                             }
-                            (Some(storage), None) => {
-                                // Pas de cargo dispo, libère storage et attend
-                                zones.release_storage(storage);
-                                state.0 = RobotState::Idle;
-                            }
-                            (None, Some(cargo)) => {
-                                // Pas de storage dispo, libère cargo et attend
-                                zones.release_cargo(cargo);
-                                state.0 = RobotState::Idle;
-                            }
-                            (None, None) => {
-                                // Rien de dispo, attend
-                                state.0 = RobotState::Idle;
-                            }
+                            None => state.0 = RobotState::Idle,
                         }
                     }
                 }
             }
+            MissionPhase::GoingToCharger => {
+                if Some(pos.0) == mission.charger {
+                    mission.phase = MissionPhase::Charging;
+                    state.0 = RobotState::Charging;
+                    dispatcher.events.write(SimEvent::new(current_tick, entity, pos.0, SimEventKind::ArrivedAtCharger));
+                }
+            }
+            MissionPhase::Charging => {
+                if battery.0 >= charging.resume_level {
+                    if let Some(charger) = mission.charger.take() {
+                        dispatcher.zones.release_charger(charger);
+                    }
+                    dispatcher.events.write(SimEvent::new(current_tick, entity, pos.0, SimEventKind::ChargingDone));
+
+                    if next.is_some() {
+                        commands.entity(entity).remove::<NextTask>();
+                    }
+                    match dispatcher.next_mission(entity, pos.0, battery.0, loaded.0, &mut mission, next) {
+                        Some(target) => {
+                            dest.0 = target;
+                            state.0 = RobotState::Moving;
+                        }
+                        None => state.0 = RobotState::Idle,
+                    }
+                }
+            }
         }
     }
-}
\ No newline at end of file
+}
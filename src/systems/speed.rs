@@ -0,0 +1,241 @@
+use std::time::{Duration, Instant};
+
+use bevy::app::FixedMain;
+use bevy::prelude::*;
+
+use crate::constants::{MAX_SPEED_FRAME_BUDGET_MS, SPEED_PRESETS};
+
+/// Plafond par défaut de Bevy pour le delta virtuel d'une frame, à ×1
+const BASE_MAX_DELTA: Duration = Duration::from_millis(250);
+
+/// Vitesse de la simulation fenêtrée. Seul le nombre de ticks par frame change :
+/// minuteries d'action et batterie comptent en ticks et suivent d'elles-mêmes.
+#[derive(Resource, Debug, Clone)]
+pub struct SimSpeed {
+    pub paused: bool,
+    /// Multiplicateur du temps réel, borné par `SPEED_PRESETS`
+    pub multiplier: f64,
+    /// Enchaîne autant de ticks que `frame_budget` le permet
+    pub max_speed: bool,
+    pub frame_budget: Duration,
+    /// Ticks demandés en pas à pas et pas encore exécutés
+    pub pending_steps: u32,
+    /// Ticks avancés par un pas multiple
+    pub step_size: u32,
+}
+
+impl Default for SimSpeed {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            multiplier: 1.0,
+            max_speed: false,
+            frame_budget: Duration::from_millis(MAX_SPEED_FRAME_BUDGET_MS),
+            pending_steps: 0,
+            step_size: 10,
+        }
+    }
+}
+
+impl SimSpeed {
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending_steps = 0;
+    }
+
+    /// Met en pause puis avance de `ticks` ticks
+    pub fn step(&mut self, ticks: u32) {
+        self.paused = true;
+        self.max_speed = false;
+        self.pending_steps = self.pending_steps.saturating_add(ticks);
+    }
+
+    pub fn set_multiplier(&mut self, multiplier: f64) {
+        let (min, max) = (SPEED_PRESETS[0], SPEED_PRESETS[SPEED_PRESETS.len() - 1]);
+        self.multiplier = multiplier.clamp(min, max);
+        self.max_speed = false;
+    }
+
+    pub fn set_max_speed(&mut self, enabled: bool) {
+        self.max_speed = enabled;
+        if enabled {
+            self.paused = false;
+        }
+    }
+
+    /// Passe au multiplicateur prédéfini suivant
+    pub fn faster(&mut self) {
+        if let Some(&next) = SPEED_PRESETS.iter().find(|&&p| p > self.multiplier * 1.001) {
+            self.set_multiplier(next);
+        }
+    }
+
+    /// Revient au multiplicateur prédéfini précédent
+    pub fn slower(&mut self) {
+        if let Some(&prev) = SPEED_PRESETS.iter().rev().find(|&&p| p < self.multiplier * 0.999) {
+            self.set_multiplier(prev);
+        }
+    }
+
+    /// Avance des ticks par rapport au temps réel, pour caler le lissage du rendu ;
+    /// le pas à pas s'anime à ×1
+    pub fn visual_rate(&self) -> f32 {
+        if self.paused {
+            1.0
+        } else if self.max_speed {
+            f32::INFINITY
+        } else {
+            self.multiplier as f32
+        }
+    }
+
+    pub fn label(&self) -> String {
+        if self.paused {
+            "pause".to_string()
+        } else if self.max_speed {
+            "max".to_string()
+        } else {
+            format!("×{}", self.multiplier)
+        }
+    }
+}
+
+/// Répercute la vitesse sur le temps virtuel qui cadence `FixedUpdate`, avant son
+/// avancement pour que la frame courante en tienne compte
+pub fn apply_sim_speed_system(speed: Res<SimSpeed>, mut time: ResMut<Time<Virtual>>) {
+    if !speed.is_changed() {
+        return;
+    }
+    if speed.paused || speed.max_speed {
+        // Ticks lancés par `manual_ticks_system`
+        time.pause();
+    } else {
+        time.unpause();
+        time.set_relative_speed_f64(speed.multiplier);
+        // Sans relever le plafond, le temps virtuel serait tronqué au-delà de ×15 à 60 FPS
+        time.set_max_delta(BASE_MAX_DELTA.mul_f64(speed.multiplier.max(1.0)));
+    }
+}
+
+/// Exécute les ticks du pas à pas et de la vitesse max, en dehors de l'accumulateur de
+/// `Time<Fixed>`. Un pas à pas trop long se répartit sur plusieurs frames.
+pub fn manual_ticks_system(world: &mut World) {
+    let speed = world.resource::<SimSpeed>();
+    let max_speed = speed.max_speed && !speed.paused;
+    let (budget, steps) = (speed.frame_budget, speed.pending_steps);
+    if !max_speed && steps == 0 {
+        return;
+    }
+
+    let started = Instant::now();
+    let mut ran = 0;
+    let _ = world.try_schedule_scope(FixedMain, |world, schedule| {
+        while (max_speed || ran < steps) && (ran == 0 || started.elapsed() < budget) {
+            *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+            schedule.run(world);
+            ran += 1;
+        }
+    });
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+
+    if !max_speed {
+        world.resource_mut::<SimSpeed>().bypass_change_detection().pending_steps -= ran;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Ticks(u32);
+
+    /// Monde minimal dont `FixedMain` ne fait que compter ses exécutions
+    fn world(speed: SimSpeed) -> World {
+        let mut world = World::new();
+        world.insert_resource(speed);
+        world.init_resource::<Ticks>();
+        world.init_resource::<Time>();
+        world.init_resource::<Time<Fixed>>();
+        world.init_resource::<Time<Virtual>>();
+        let mut schedule = Schedule::new(FixedMain);
+        schedule.add_systems(|mut ticks: ResMut<Ticks>| ticks.0 += 1);
+        world.add_schedule(schedule);
+        world
+    }
+
+    #[test]
+    fn presets_are_walked_in_order_and_clamped() {
+        let mut speed = SimSpeed::default();
+        let mut seen = vec![speed.multiplier];
+        for _ in 0..SPEED_PRESETS.len() {
+            speed.faster();
+            seen.push(speed.multiplier);
+        }
+        assert_eq!(&seen[..7], &SPEED_PRESETS[3..]);
+        assert_eq!(speed.multiplier, 100.0);
+
+        for _ in 0..SPEED_PRESETS.len() {
+            speed.slower();
+        }
+        assert_eq!(speed.multiplier, 0.1);
+
+        // Hors preset : on rejoint le voisin dans chaque sens
+        speed.set_multiplier(3.0);
+        speed.faster();
+        assert_eq!(speed.multiplier, 5.0);
+        speed.set_multiplier(3.0);
+        speed.slower();
+        assert_eq!(speed.multiplier, 2.0);
+
+        speed.set_max_speed(true);
+        speed.set_multiplier(1000.0);
+        assert_eq!((speed.multiplier, speed.max_speed), (100.0, false));
+        speed.set_multiplier(0.0);
+        assert_eq!(speed.multiplier, 0.1);
+    }
+
+    #[test]
+    fn steps_pause_and_accumulate() {
+        let mut speed = SimSpeed::default();
+        speed.set_max_speed(true);
+        speed.step(3);
+        speed.step(speed.step_size);
+        assert!(speed.paused && !speed.max_speed);
+        assert_eq!(speed.pending_steps, 13);
+        assert_eq!(speed.label(), "pause");
+
+        speed.step(u32::MAX);
+        assert_eq!(speed.pending_steps, u32::MAX);
+        speed.toggle_pause();
+        assert!(!speed.paused);
+        assert_eq!(speed.pending_steps, 0);
+    }
+
+    #[test]
+    fn manual_ticks_run_exactly_the_pending_steps() {
+        let mut speed = SimSpeed { frame_budget: Duration::from_secs(60), ..default() };
+        speed.step(7);
+        let mut world = world(speed);
+        manual_ticks_system(&mut world);
+        assert_eq!(world.resource::<Ticks>().0, 7);
+        assert_eq!(world.resource::<SimSpeed>().pending_steps, 0);
+
+        // Rien à faire une fois les pas consommés
+        manual_ticks_system(&mut world);
+        assert_eq!(world.resource::<Ticks>().0, 7);
+    }
+
+    #[test]
+    fn long_steps_are_spread_over_frames() {
+        // Budget nul : un seul tick par frame, décompté à chaque fois
+        let mut speed = SimSpeed { frame_budget: Duration::ZERO, ..default() };
+        speed.step(3);
+        let mut world = world(speed);
+        for remaining in [2, 1, 0, 0] {
+            manual_ticks_system(&mut world);
+            assert_eq!(world.resource::<SimSpeed>().pending_steps, remaining);
+        }
+        assert_eq!(world.resource::<Ticks>().0, 3);
+    }
+}
@@ -2,35 +2,62 @@ use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 
 use crate::components::{
-    ActionTimer, Destination, GridPosition, Loaded, Mission, MissionPhase,
-    PlannedPath, Robot, RobotState, State, Velocity,
+    ActionTimer, Breakdown, Destination, GridPosition, Loaded, Mission, MissionPhase,
+    PlannedPath, Robot, RobotId, RobotState, State,
 };
-use crate::constants::{DROPOFF_DURATION, PICKUP_DURATION};
+use crate::constants::{SNAPSHOT_DIR, SPEED_PRESETS, TICK_RATE_HZ};
 use crate::core::SpaceTimeTable;
+use crate::systems::allocation::AllocatorRegistry;
+use crate::systems::faults::FaultRequests;
+use crate::systems::inventory::Inventory;
+use crate::systems::metrics::SimMetrics;
+use crate::systems::orders::OrderBook;
+use crate::systems::planner::PlannerRegistry;
+use crate::systems::replay::Playback;
+use crate::systems::snapshot::save_snapshot;
 use crate::systems::spawner::SpawnQueue;
+use crate::systems::speed::SimSpeed;
+use crate::systems::validation::MotionValidation;
 
 #[derive(Resource, Default)]
 pub struct UiState {
     pub collapsed: bool,
     pub selected_robot: Option<Entity>,
+    /// Tick saisi pour le saut de la relecture
+    pub jump_tick: u64,
 }
 
+/// Robot affiché dans le panneau
+type PanelRobot = (
+    Entity,
+    Option<&'static RobotId>,
+    &'static GridPosition,
+    &'static Destination,
+    &'static State,
+    &'static Loaded,
+    &'static Mission,
+    &'static PlannedPath,
+    Option<&'static ActionTimer>,
+    Option<&'static Breakdown>,
+);
+
+#[allow(clippy::too_many_arguments)]
 pub fn supervisor_panel(
+    mut commands: Commands,
     mut contexts: EguiContexts,
-    robots: Query<(
-        Entity,
-        &GridPosition,
-        &Destination,
-        &State,
-        &Loaded,
-        &Velocity,
-        &Mission,
-        &PlannedPath,
-        Option<&ActionTimer>,
-    ), With<Robot>>,
+    robots: Query<PanelRobot, With<Robot>>,
     space_time: Res<SpaceTimeTable>,
     spawn_queue: Res<SpawnQueue>,
+    metrics: Res<SimMetrics>,
+    mut planners: ResMut<PlannerRegistry>,
+    mut allocators: ResMut<AllocatorRegistry>,
     mut ui_state: ResMut<UiState>,
+    mut fault_requests: ResMut<FaultRequests>,
+    orders: Res<OrderBook>,
+    inventory: Res<Inventory>,
+    playback: Option<ResMut<Playback>>,
+    speed: Option<ResMut<SimSpeed>>,
+    validation: Option<Res<MotionValidation>>,
 ) -> Result {
     let ctx = contexts.ctx_mut()?;
 
@@ -42,7 +69,10 @@ pub fn supervisor_panel(
         style.spacing.item_spacing = egui::vec2(4.0, 4.0);
     });
 
-    let screen = ctx.screen_rect();
+    // Pas de panne à la main pendant une relecture
+    let live = playback.is_none();
+    let tick = space_time.current_tick();
+    let screen = ctx.content_rect();
     let panel_width = 280.0;
     let padding = 12.0;
 
@@ -57,14 +87,45 @@ pub fn supervisor_panel(
             ui.horizontal(|ui| {
                 let spawned = spawn_queue.spawned_count;
                 let total = spawn_queue.total;
-                let tick = space_time.current_tick();
-                let loaded_count = robots.iter().filter(|r| r.4.0).count();
+                let loaded_count = robots.iter().filter(|r| r.5.0).count();
+                let charging_count = robots.iter().filter(|r| r.4.0 == RobotState::Charging).count();
 
                 compact_stat(ui, "🤖", format!("{}/{}", spawned, total), egui::Color32::from_rgb(59, 130, 246));
                 compact_stat(ui, "📦", loaded_count.to_string(), egui::Color32::from_rgb(234, 88, 12));
+                compact_stat(ui, "🔋", charging_count.to_string(), egui::Color32::from_rgb(168, 85, 247));
+                if !inventory.slots().is_empty() {
+                    let color = if inventory.empty_slots() > 0 {
+                        egui::Color32::from_rgb(239, 68, 68)
+                    } else {
+                        egui::Color32::from_rgb(34, 197, 94)
+                    };
+                    compact_stat(ui, "🏷", format!("{:.0}%", inventory.fill() * 100.0), color);
+                }
+                if !orders.open.is_empty() {
+                    compact_stat(ui, "🧾", orders.pending.len().to_string(), egui::Color32::from_rgb(20, 184, 166));
+                }
                 compact_stat(ui, "⏱", format!("{}", tick), egui::Color32::from_rgb(107, 114, 128));
             });
 
+            ui.add_space(4.0);
+            metrics_summary(ui, &metrics);
+            if let Some(validation) = validation.as_deref().filter(|v| v.count > 0) {
+                violation_warning(ui, validation);
+            }
+
+            ui.add_space(6.0);
+            match playback {
+                Some(mut playback) => playback_controls(ui, &mut playback, &mut ui_state.jump_tick),
+                None => {
+                    if let Some(mut speed) = speed {
+                        speed_controls(ui, &mut speed);
+                    }
+                    planner_selector(ui, &mut planners);
+                    allocator_selector(ui, &mut allocators, &metrics);
+                    snapshot_button(ui, &mut commands, space_time.current_tick());
+                }
+            }
+
             ui.add_space(6.0);
             ui.separator();
             ui.add_space(4.0);
@@ -76,10 +137,10 @@ pub fn supervisor_panel(
                     let mut sorted: Vec<_> = robots.iter().collect();
                     sorted.sort_by_key(|r| r.0.index());
 
-                    for (entity, pos, dest, state, loaded, vel, mission, path, timer) in sorted {
+                    for (entity, id, pos, dest, state, loaded, mission, path, timer, breakdown) in sorted {
                         let is_selected = ui_state.selected_robot == Some(entity);
 
-                        let frame = egui::Frame::none()
+                        let frame = egui::Frame::NONE
                             .fill(if is_selected {
                                 egui::Color32::from_rgb(239, 246, 255)
                             } else {
@@ -110,7 +171,7 @@ pub fn supervisor_panel(
                                     ("○", egui::Color32::from_rgb(34, 197, 94))
                                 };
 
-                                ui.label(egui::RichText::new(format!("{} #{}", icon, entity.index()))
+                                ui.label(egui::RichText::new(format!("{} #{}", icon, id.map_or(entity.index(), |id| id.0)))
                                     .size(11.0).strong().color(color));
 
                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -120,12 +181,7 @@ pub fn supervisor_panel(
 
                             // Barre de progression si action en cours
                             if let Some(t) = timer {
-                                let total_duration = match mission.phase {
-                                    MissionPhase::PickingUp => PICKUP_DURATION,
-                                    MissionPhase::DroppingOff => DROPOFF_DURATION,
-                                    _ => 1.0,
-                                };
-                                let progress = t.progress(total_duration);
+                                let progress = t.progress();
 
                                 ui.add_space(2.0);
                                 let bar_color = match mission.phase {
@@ -162,6 +218,10 @@ pub fn supervisor_panel(
                                         .size(10.0).color(egui::Color32::from_gray(140)));
                                 }
                             });
+
+                            if is_selected && live {
+                                fault_controls(ui, &mut fault_requests, entity, state.0, breakdown, tick);
+                            }
                         });
 
                         ui.add_space(2.0);
@@ -172,8 +232,217 @@ pub fn supervisor_panel(
     Ok(())
 }
 
+/// Choix du solveur actif et résumé de la dernière planification
+fn planner_selector(ui: &mut egui::Ui, planners: &mut PlannerRegistry) {
+    let mut selected = planners.active_name().to_string();
+
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("Solveur").size(10.0).color(egui::Color32::from_gray(120)));
+        egui::ComboBox::from_id_salt("planner")
+            .selected_text(selected.to_uppercase())
+            .width(90.0)
+            .show_ui(ui, |ui| {
+                for name in planners.names() {
+                    ui.selectable_value(&mut selected, name.to_string(), name.to_uppercase());
+                }
+            });
+
+        if let Some(d) = planners.last_diagnostics() {
+            let mut text = format!("{} rob. · {:.1} ms", d.agents, d.elapsed.as_secs_f64() * 1000.0);
+            if let Some(fallback) = &d.fallback {
+                text.push_str(&format!(" · ↩ {fallback}"));
+            }
+            ui.label(egui::RichText::new(text).size(10.0).color(egui::Color32::from_gray(140)));
+        }
+    });
+
+    if selected != planners.active_name() {
+        planners.select(&selected);
+    }
+}
+
+/// Choix de l'allocateur de tâches ; « immédiate » rend chaque robot libre au premier
+/// storage disponible
+fn allocator_selector(ui: &mut egui::Ui, allocators: &mut AllocatorRegistry, metrics: &SimMetrics) {
+    let mut selected = allocators.active_name().map(str::to_string);
+
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("Affectation").size(10.0).color(egui::Color32::from_gray(120)));
+        egui::ComboBox::from_id_salt("allocator")
+            .selected_text(selected.as_deref().unwrap_or("immédiate"))
+            .width(90.0)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, "immédiate");
+                for name in allocators.names() {
+                    ui.selectable_value(&mut selected, Some(name.to_string()), name);
+                }
+            });
+
+        if let Some(steps) = metrics.mean_assignment_steps() {
+            let text = format!("{} tâches · {:.1} pas", metrics.assignments, steps);
+            ui.label(egui::RichText::new(text).size(10.0).color(egui::Color32::from_gray(140)));
+        }
+    });
+
+    if selected.as_deref() != allocators.active_name() {
+        allocators.select(selected.as_deref());
+    }
+}
+
+/// Timeline, lecture/pause, vitesse et saut à un tick de la relecture
+fn playback_controls(ui: &mut egui::Ui, playback: &mut Playback, jump_tick: &mut u64) {
+    let first = playback.replay.first_tick();
+    let last = playback.replay.last_tick;
+
+    let mut tick = playback.tick();
+    let slider = egui::Slider::new(&mut tick, first..=last).show_value(true);
+    if ui.add_sized([ui.available_width(), 16.0], slider).changed() {
+        playback.seek(tick);
+    }
+
+    ui.horizontal(|ui| {
+        let label = if playback.playing { "⏸" } else { "▶" };
+        if ui.button(label).clicked() {
+            if !playback.playing && playback.tick() >= last {
+                playback.seek(first);
+            }
+            playback.playing = !playback.playing;
+        }
+
+        egui::ComboBox::from_id_salt("playback_speed")
+            .selected_text(format!("×{}", playback.speed))
+            .width(56.0)
+            .show_ui(ui, |ui| {
+                for speed in [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 64.0] {
+                    ui.selectable_value(&mut playback.speed, speed, format!("×{speed}"));
+                }
+            });
+
+        ui.add(egui::DragValue::new(jump_tick).range(first..=last).speed(10.0));
+        if ui.button("Aller").clicked() {
+            playback.seek(*jump_tick);
+        }
+    });
+}
+
+/// Pause, pas à pas et vitesse de la simulation ; les champs sont copiés pour ne
+/// signaler un changement qu'à la modification
+fn speed_controls(ui: &mut egui::Ui, speed: &mut ResMut<SimSpeed>) {
+    ui.horizontal(|ui| {
+        let label = if speed.paused { "▶" } else { "⏸" };
+        if ui.button(label).clicked() {
+            speed.toggle_pause();
+        }
+        if ui.button("+1").on_hover_text("Avancer d'un tick").clicked() {
+            speed.step(1);
+        }
+        let step_size = speed.step_size;
+        if ui.button(format!("+{step_size}")).on_hover_text("Avancer de plusieurs ticks").clicked() {
+            speed.step(step_size);
+        }
+        let mut step_size = step_size;
+        if ui.add(egui::DragValue::new(&mut step_size).range(1..=10_000)).changed() {
+            speed.step_size = step_size;
+        }
+        ui.label(egui::RichText::new(speed.label()).size(10.0).color(egui::Color32::from_gray(120)));
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("Vitesse").size(10.0).color(egui::Color32::from_gray(120)));
+        let mut multiplier = speed.multiplier;
+        let range = SPEED_PRESETS[0]..=SPEED_PRESETS[SPEED_PRESETS.len() - 1];
+        let slider = egui::Slider::new(&mut multiplier, range)
+            .logarithmic(true)
+            .prefix("×")
+            .max_decimals(2);
+        if ui.add_enabled(!speed.max_speed, slider).changed() {
+            speed.set_multiplier(multiplier);
+        }
+        let mut max_speed = speed.max_speed;
+        if ui.checkbox(&mut max_speed, "max").changed() {
+            speed.set_max_speed(max_speed);
+        }
+    });
+}
+
+/// Nombre de violations de mouvement et détail de la dernière
+fn violation_warning(ui: &mut egui::Ui, validation: &MotionValidation) {
+    let label = ui.label(
+        egui::RichText::new(format!("⚠ {} violations", validation.count))
+            .size(11.0)
+            .strong()
+            .color(egui::Color32::from_rgb(220, 38, 38)),
+    );
+    if let Some(last) = validation.last {
+        label.on_hover_text(last.to_string());
+    }
+}
+
+/// Sauvegarde l'état complet en fin de frame, dans `SNAPSHOT_DIR`
+fn snapshot_button(ui: &mut egui::Ui, commands: &mut Commands, tick: u64) {
+    if ui.small_button("💾 Snapshot").clicked() {
+        commands.queue(move |world: &mut World| {
+            if let Err(e) = std::fs::create_dir_all(SNAPSHOT_DIR) {
+                error!("cannot create {SNAPSHOT_DIR}: {e}");
+                return;
+            }
+            let path = std::path::Path::new(SNAPSHOT_DIR).join(format!("tick_{tick}.ron"));
+            save_snapshot(world, &path);
+        });
+    }
+}
+
+/// Indicateurs de débit et d'utilisation depuis le début de l'exécution
+/// Panne manuelle du robot sélectionné, ou réparation anticipée
+fn fault_controls(
+    ui: &mut egui::Ui,
+    requests: &mut FaultRequests,
+    entity: Entity,
+    state: RobotState,
+    breakdown: Option<&Breakdown>,
+    tick: u64,
+) {
+    ui.add_space(2.0);
+    ui.horizontal(|ui| match breakdown {
+        Some(breakdown) => {
+            let left = breakdown.repair_at.saturating_sub(tick) as f64 / TICK_RATE_HZ;
+            ui.label(egui::RichText::new(format!("🔧 réparé dans {:.0}s", left))
+                .size(10.0).color(egui::Color32::from_rgb(239, 68, 68)));
+            if ui.small_button("Réparer").clicked() {
+                requests.repairs.push(entity);
+            }
+        }
+        // Panne de batterie de ce tick, pas encore enregistrée
+        None if state == RobotState::Fault => {}
+        None => {
+            if ui.small_button("Mettre en panne").clicked() {
+                requests.breakdowns.push(entity);
+            }
+        }
+    });
+}
+
+fn metrics_summary(ui: &mut egui::Ui, metrics: &SimMetrics) {
+    let secs = |t: Option<f64>| t.map_or("-".to_string(), |t| format!("{t:.0}s"));
+
+    ui.horizontal(|ui| {
+        compact_stat(ui, "✅", format!("{} · {:.0}/h", metrics.completed_missions, metrics.missions_per_hour()), egui::Color32::from_rgb(34, 197, 94));
+        compact_stat(ui, "⚙", format!("{:.0}%", metrics.utilization() * 100.0), egui::Color32::from_rgb(168, 85, 247));
+    });
+
+    let text = format!(
+        "cycle {} · p90 {} · attente {:.0}s · replans {} · échecs {}",
+        secs(metrics.mean_cycle_time()),
+        secs(metrics.cycle_time_percentile(90.0)),
+        metrics.wait_ticks as f64 / TICK_RATE_HZ,
+        metrics.replans,
+        metrics.planner_failures,
+    );
+    ui.label(egui::RichText::new(text).size(10.0).color(egui::Color32::from_gray(140)));
+}
+
 fn compact_stat(ui: &mut egui::Ui, icon: &str, value: String, color: egui::Color32) {
-    egui::Frame::none()
+    egui::Frame::NONE
         .fill(color.gamma_multiply(0.1))
         .inner_margin(egui::Margin::symmetric(6, 3))
         .corner_radius(3.0)
@@ -196,7 +465,7 @@ fn state_badge(ui: &mut egui::Ui, state: RobotState) {
         RobotState::Fault => ("ERR", egui::Color32::from_rgb(239, 68, 68)),
     };
 
-    egui::Frame::none()
+    egui::Frame::NONE
         .fill(color)
         .inner_margin(egui::Margin::symmetric(4, 1))
         .corner_radius(2.0)
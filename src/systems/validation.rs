@@ -0,0 +1,208 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::components::{GridPosition, Robot};
+use crate::core::{CellType, GridPos, SpaceTimeTable, WarehouseGrid};
+use crate::systems::speed::SimSpeed;
+
+/// Nature d'une violation du mouvement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Deux robots occupent la même cellule
+    Vertex { other: Entity },
+    /// Deux robots échangent leurs cellules pendant le même tick
+    Swap { other: Entity },
+    /// Cellule non praticable (`Rack` ou `Blocked`)
+    Obstacle(CellType),
+    OutOfGrid,
+    /// Déplacement vers une cellule non adjacente en un seul tick
+    Jump { from: GridPos },
+    /// Plus de cellules parcourues que de ticks écoulés depuis la dernière position connue
+    Teleport { from: GridPos, ticks: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub tick: u64,
+    pub entity: Entity,
+    pub pos: GridPos,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tick {}: robot #{} at ({}, {}) ",
+            self.tick,
+            self.entity.index(),
+            self.pos.x,
+            self.pos.y
+        )?;
+        match self.kind {
+            ViolationKind::Vertex { other } => write!(f, "shares its cell with robot #{}", other.index()),
+            ViolationKind::Swap { other } => write!(f, "swaps cells with robot #{}", other.index()),
+            ViolationKind::Obstacle(cell) => write!(f, "is inside a {cell:?} cell"),
+            ViolationKind::OutOfGrid => write!(f, "is outside the grid"),
+            ViolationKind::Jump { from } => write!(f, "jumped from ({}, {})", from.x, from.y),
+            ViolationKind::Teleport { from, ticks } => {
+                write!(f, "teleported from ({}, {}) in {ticks} ticks", from.x, from.y)
+            }
+        }
+    }
+}
+
+/// Contrôle les positions tick après tick, en gardant la dernière position connue de
+/// chaque robot. Un robot absent quelques ticks est comparé à sa dernière position.
+#[derive(Debug, Clone, Default)]
+pub struct MotionValidator {
+    last: FxHashMap<Entity, (GridPos, u64)>,
+}
+
+impl MotionValidator {
+    /// Positions de tous les robots à `tick` ; les ticks doivent être croissants
+    pub fn check_tick(
+        &mut self,
+        grid: &WarehouseGrid,
+        tick: u64,
+        positions: &[(Entity, GridPos)],
+    ) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let mut report = |entity, pos, kind| violations.push(Violation { tick, entity, pos, kind });
+
+        // Occupants du tick précédent, pour repérer les échanges
+        let previous: FxHashMap<GridPos, Entity> = self
+            .last
+            .iter()
+            .filter(|(_, &(_, at))| at + 1 == tick)
+            .map(|(&entity, &(pos, _))| (pos, entity))
+            .collect();
+        let current: FxHashMap<Entity, GridPos> = positions.iter().copied().collect();
+        let mut occupied: FxHashMap<GridPos, Entity> = FxHashMap::default();
+
+        for &(entity, pos) in positions {
+            match grid.get(pos) {
+                None => report(entity, pos, ViolationKind::OutOfGrid),
+                Some(cell) if !cell.is_passable() => report(entity, pos, ViolationKind::Obstacle(cell)),
+                Some(_) => {}
+            }
+            if let Some(other) = occupied.insert(pos, entity) {
+                report(entity, pos, ViolationKind::Vertex { other });
+            }
+
+            let Some(&(from, at)) = self.last.get(&entity) else { continue };
+            if at >= tick || from == pos {
+                continue;
+            }
+            let (ticks, distance) = (tick - at, from.manhattan_distance(&pos) as u64);
+            if ticks == 1 && distance > 1 {
+                report(entity, pos, ViolationKind::Jump { from });
+            } else if distance > ticks {
+                report(entity, pos, ViolationKind::Teleport { from, ticks });
+            }
+
+            // Un seul signalement par paire
+            if ticks == 1 {
+                if let Some(&other) = previous.get(&pos) {
+                    if entity.index() < other.index() && current.get(&other) == Some(&from) {
+                        report(entity, pos, ViolationKind::Swap { other });
+                    }
+                }
+            }
+        }
+
+        for &(entity, pos) in positions {
+            self.last.insert(entity, (pos, tick));
+        }
+        violations
+    }
+}
+
+/// Positions exécutées, regroupées par tick croissant
+pub fn validate_positions<'a>(
+    grid: &WarehouseGrid,
+    ticks: impl IntoIterator<Item = (u64, &'a [(Entity, GridPos)])>,
+) -> Vec<Violation> {
+    let mut validator = MotionValidator::default();
+    ticks
+        .into_iter()
+        .flat_map(|(tick, positions)| validator.check_tick(grid, tick, positions))
+        .collect()
+}
+
+/// Robot, position de départ et waypoints restants
+pub type RobotPlan<'a> = (Entity, GridPos, &'a [(GridPos, u64)]);
+
+/// Chemins planifiés, rejoués comme par `path_execution_system` à partir de `tick` : chaque
+/// robot part de sa position, avance d'au plus un waypoint par tick une fois son tick atteint,
+/// puis reste sur place jusqu'à la fin du plus long chemin
+pub fn validate_paths(
+    grid: &WarehouseGrid,
+    tick: u64,
+    robots: &[RobotPlan],
+) -> Vec<Violation> {
+    let end = robots
+        .iter()
+        .filter_map(|(_, _, path)| path.last().map(|&(_, at)| at))
+        .fold(tick, u64::max);
+
+    let mut validator = MotionValidator::default();
+    let mut positions: Vec<(Entity, GridPos)> = robots.iter().map(|&(e, pos, _)| (e, pos)).collect();
+    let mut cursors = vec![0; robots.len()];
+    let mut violations = validator.check_tick(grid, tick, &positions);
+
+    for t in tick + 1..=end {
+        for (i, (_, _, path)) in robots.iter().enumerate() {
+            if let Some(&(next, at)) = path.get(cursors[i]) {
+                if t >= at {
+                    positions[i].1 = next;
+                    cursors[i] += 1;
+                }
+            }
+        }
+        violations.extend(validator.check_tick(grid, t, &positions));
+    }
+    violations
+}
+
+/// Validation des mouvements exécutés pendant la simulation
+#[derive(Resource, Debug, Clone, Default)]
+pub struct MotionValidation {
+    /// Met la simulation fenêtrée en pause dès qu'une violation est relevée
+    pub pause_on_violation: bool,
+    /// Violations relevées depuis le début
+    pub count: u64,
+    pub last: Option<Violation>,
+    pub validator: MotionValidator,
+}
+
+/// Contrôle les positions après l'exécution des chemins ; journalise chaque violation
+pub fn motion_validation_system(
+    robots: Query<(Entity, &GridPosition), With<Robot>>,
+    grid: Res<WarehouseGrid>,
+    space_time: Res<SpaceTimeTable>,
+    mut validation: ResMut<MotionValidation>,
+    speed: Option<ResMut<SimSpeed>>,
+) {
+    let positions: Vec<(Entity, GridPos)> = robots.iter().map(|(e, pos)| (e, pos.0)).collect();
+    let violations = validation.validator.check_tick(&grid, space_time.current_tick(), &positions);
+    let Some(&last) = violations.last() else {
+        return;
+    };
+
+    for violation in &violations {
+        error!("Motion violation, {violation}");
+    }
+    validation.count += violations.len() as u64;
+    validation.last = Some(last);
+
+    if validation.pause_on_violation {
+        if let Some(mut speed) = speed.filter(|s| !s.paused) {
+            warn!("Simulation paused on motion violation");
+            speed.paused = true;
+            speed.pending_steps = 0;
+        }
+    }
+}
@@ -1,11 +1,44 @@
 use bevy::prelude::*;
 
-use crate::components::{GridPosition, Loaded, PlannedPath, Robot};
-use crate::core::WarehouseGrid;
+use crate::components::{GridPosition, Loaded, PlannedPath, Robot, Velocity};
+use crate::constants::{ROBOT_ACCELERATION, ROBOT_DECELERATION, ROBOT_MAX_VELOCITY};
+use crate::core::{SpaceTimeTable, WarehouseGrid};
+use crate::systems::speed::SimSpeed;
+
+/// Donne un mesh et un transform aux robots qui viennent d'apparaître
+pub fn attach_robot_visuals(
+    mut commands: Commands,
+    robots: Query<(Entity, &GridPosition), Added<Robot>>,
+    grid: Res<WarehouseGrid>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut mesh: Local<Option<Handle<Mesh>>>,
+) {
+    for (entity, pos) in &robots {
+        let mesh = mesh
+            .get_or_insert_with(|| meshes.add(Cuboid::new(0.6, 0.4, 0.6)))
+            .clone();
+        // Un matériau par robot : sa couleur suit son chargement
+        let material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.2, 0.6, 0.2),
+            ..default()
+        });
+        let (wx, wz) = grid.grid_to_world(pos.0);
+
+        commands.entity(entity).insert((
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            Transform::from_xyz(wx, 0.2, wz),
+        ));
+    }
+}
+
+/// Robot dont le chargement vient de changer
+type RecoloredRobot = (&'static Loaded, &'static MeshMaterial3d<StandardMaterial>);
 
 /// Met à jour la couleur des robots selon leur état de chargement
 pub fn robot_color_system(
-    robots: Query<(&Loaded, &MeshMaterial3d<StandardMaterial>), (With<Robot>, Changed<Loaded>)>,
+    robots: Query<RecoloredRobot, (With<Robot>, Changed<Loaded>)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     for (loaded, material_handle) in &robots {
@@ -48,3 +81,39 @@ pub fn draw_robot_paths(
         }
     }
 }
+
+pub fn visual_interpolation_system(
+    mut robots: Query<(&GridPosition, &PlannedPath, &mut Transform, &mut Velocity), With<Robot>>,
+    grid: Res<WarehouseGrid>,
+    space_time: Res<SpaceTimeTable>,
+    time: Res<Time<Real>>,
+    speed: Option<Res<SimSpeed>>,
+) {
+    let current_tick = space_time.current_tick();
+    // Temps réel : le rendu continue de converger en pause et lors du pas à pas.
+    // Le lissage suit la vitesse pour ne pas traîner derrière la simulation accélérée.
+    let dt = time.delta_secs();
+    let rate = speed.map_or(1.0, |s| s.visual_rate());
+
+    for (grid_pos, path, mut transform, mut vel) in &mut robots {
+        let current_world = grid.grid_to_world(grid_pos.0);
+        let target = Vec3::new(current_world.0, 0.2, current_world.1);
+
+        if let Some((next_pos, target_tick)) = path.current() {
+            if target_tick > current_tick {
+                let next_world = grid.grid_to_world(next_pos);
+                let next_target = Vec3::new(next_world.0, 0.2, next_world.1);
+
+                let t = (dt * 10.0 * rate).min(1.0);
+                transform.translation = transform.translation.lerp(next_target, t);
+
+                vel.0 = (vel.0 + ROBOT_ACCELERATION * dt).min(ROBOT_MAX_VELOCITY);
+            } else {
+                transform.translation = target;
+            }
+        } else {
+            vel.0 = (vel.0 - ROBOT_DECELERATION * dt).max(0.0);
+            transform.translation = transform.translation.lerp(target, (5.0 * dt * rate).min(1.0));
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Allocateurs de tâches : contraintes d'affectation et optimalité.
+
+use rand::Rng;
+use warehouse_sim::constants::ALLOCATION_DISTANCE_CACHE;
+use warehouse_sim::core::{CellType, GridPos, HighwayGraph, SimRng, WarehouseGrid};
+use warehouse_sim::systems::allocation::{
+    AllocatorRegistry, Auction, CostMatrix, DistanceCache, Hungarian, NearestFirst, TaskAllocator,
+};
+
+/// Nombre maximal de robots affectés et coût minimal, par énumération
+fn brute_force(costs: &CostMatrix) -> (usize, u64) {
+    fn search(costs: &CostMatrix, row: usize, taken: &mut Vec<bool>) -> (usize, u64) {
+        if row == costs.rows() {
+            return (0, 0);
+        }
+        // Robot sans tâche
+        let mut best = search(costs, row + 1, taken);
+        for col in 0..costs.cols() {
+            let Some(cost) = costs.get(row, col).filter(|_| !taken[col]) else {
+                continue;
+            };
+            taken[col] = true;
+            let (count, total) = search(costs, row + 1, taken);
+            taken[col] = false;
+            // Plus de robots affectés d'abord, puis le coût le plus bas
+            let candidate = (count + 1, total + cost as u64);
+            if (candidate.0, std::cmp::Reverse(candidate.1)) > (best.0, std::cmp::Reverse(best.1)) {
+                best = candidate;
+            }
+        }
+        best
+    }
+    search(costs, 0, &mut vec![false; costs.cols()])
+}
+
+fn random_costs(rng: &mut SimRng, rows: usize, cols: usize) -> CostMatrix {
+    let mut costs = CostMatrix::new(rows, cols);
+    for r in 0..rows {
+        for c in 0..cols {
+            // Une paire sur six inatteignable
+            let cost = (rng.random_range(0..6) != 0).then(|| rng.random_range(0..40));
+            costs.set(r, c, cost);
+        }
+    }
+    costs
+}
+
+/// Au plus un robot par tâche, jamais une tâche inatteignable
+fn check_valid(costs: &CostMatrix, assignment: &[Option<usize>]) {
+    assert_eq!(assignment.len(), costs.rows());
+    let mut taken = vec![false; costs.cols()];
+    for (r, col) in assignment.iter().enumerate() {
+        if let Some(c) = *col {
+            assert!(costs.get(r, c).is_some(), "robot {r} assigned to unreachable task {c}");
+            assert!(!taken[c], "task {c} assigned twice");
+            taken[c] = true;
+        }
+    }
+}
+
+#[test]
+fn nearest_first_is_greedy() {
+    // Le premier robot prend la tâche que le second aurait dû prendre
+    let costs = CostMatrix::from_rows(&[vec![Some(1), Some(2)], vec![Some(1), Some(10)]]);
+    let nearest = NearestFirst.assign(&costs);
+    assert_eq!(nearest, vec![Some(0), Some(1)]);
+    assert_eq!(costs.total(&nearest), 11);
+
+    let optimal = vec![Some(1), Some(0)];
+    assert_eq!(Hungarian.assign(&costs), optimal);
+    assert_eq!(Auction.assign(&costs), optimal);
+}
+
+#[test]
+fn hungarian_and_auction_are_optimal() {
+    let mut rng = SimRng::new(3);
+    for (rows, cols) in [(3, 3), (4, 6), (6, 4), (5, 5), (1, 4), (4, 1), (6, 6)] {
+        for _ in 0..25 {
+            let costs = random_costs(&mut rng, rows, cols);
+            let (count, total) = brute_force(&costs);
+            for allocator in [&mut Hungarian as &mut dyn TaskAllocator, &mut Auction] {
+                let assignment = allocator.assign(&costs);
+                check_valid(&costs, &assignment);
+                let assigned = assignment.iter().flatten().count();
+                assert_eq!((assigned, costs.total(&assignment)), (count, total), "{} on {costs:?}", allocator.name());
+            }
+            let nearest = NearestFirst.assign(&costs);
+            check_valid(&costs, &nearest);
+        }
+    }
+}
+
+#[test]
+fn unreachable_tasks_stay_unassigned() {
+    let costs = CostMatrix::from_rows(&[vec![None, None], vec![Some(4), None], vec![Some(2), None]]);
+    for allocator in [&mut NearestFirst as &mut dyn TaskAllocator, &mut Hungarian, &mut Auction] {
+        let assignment = allocator.assign(&costs);
+        check_valid(&costs, &assignment);
+        assert_eq!(assignment[0], None, "{}", allocator.name());
+        assert_eq!(assignment.iter().flatten().count(), 1, "{}", allocator.name());
+    }
+    assert_eq!(Hungarian.assign(&costs), vec![None, None, Some(0)]);
+    assert_eq!(Hungarian.assign(&CostMatrix::new(3, 0)), vec![None; 3]);
+    assert_eq!(Auction.assign(&CostMatrix::new(0, 2)), Vec::new());
+}
+
+#[test]
+fn registry_selects_allocators_by_name() {
+    let mut registry = AllocatorRegistry::default();
+    assert!(!registry.is_active());
+    assert_eq!(registry.names().collect::<Vec<_>>(), ["nearest", "hungarian", "auction"]);
+    let costs = CostMatrix::from_rows(&[vec![Some(1)]]);
+    assert_eq!(registry.assign(&costs), vec![None]);
+
+    assert!(registry.select(Some("auction")));
+    assert_eq!(registry.active_name(), Some("auction"));
+    assert_eq!(registry.assign(&costs), vec![Some(0)]);
+    assert!(!registry.select(Some("fifo")));
+    assert_eq!(registry.active_name(), Some("auction"));
+    assert!(registry.select(None));
+    assert!(!registry.is_active());
+}
+
+/// Le cache des distances reste borné et suit les obstacles de la grille
+#[test]
+fn distance_cache_is_bounded_and_follows_the_grid() {
+    let mut grid = WarehouseGrid::new(24, 24);
+    let highways = HighwayGraph::new(24, 24);
+    let mut cache = DistanceCache::default();
+    let origin = GridPos::new(0, 0);
+    let targets: Vec<GridPos> = (0..24).flat_map(|y| (0..24).map(move |x| GridPos::new(x, y))).collect();
+    for &target in &targets {
+        cache.steps(&grid, &highways, origin, target);
+    }
+    assert_eq!(cache.len(), ALLOCATION_DISTANCE_CACHE);
+
+    let target = GridPos::new(2, 0);
+    assert_eq!(cache.steps(&grid, &highways, origin, target), Some(2));
+    grid.set(GridPos::new(1, 0), CellType::Blocked);
+    assert_eq!(cache.steps(&grid, &highways, origin, target), Some(4));
+    assert_eq!(cache.len(), 1);
+}
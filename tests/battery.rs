@@ -0,0 +1,150 @@
+//! Le modèle de batterie avance au tick : même énergie quelle que soit la cadence d'affichage.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use warehouse_sim::components::*;
+use warehouse_sim::constants::TICK_DELTA;
+use warehouse_sim::core::{GridPos, SpaceTimeTable};
+use warehouse_sim::plugins::simulation::SimulationSettings;
+use warehouse_sim::systems::events::{SimEvent, SimEventKind};
+use warehouse_sim::systems::metrics::SimMetrics;
+
+mod common;
+
+/// Simulation sans flotte, sur un robot ajouté et déplacé à la main
+fn battery_app(model: BatteryModel, state: RobotState) -> (App, Entity) {
+    let mut app = common::headless_app(common::fleet(0), u64::MAX);
+    // La première update n'avance pas l'horloge : ensuite, un tick par update
+    app.update();
+    let robot = app
+        .world_mut()
+        .spawn((Robot, GridPosition(GridPos::new(0, 0)), State(state), model))
+        .id();
+    (app, robot)
+}
+
+fn level(app: &App, robot: Entity) -> f32 {
+    app.world().get::<Battery>(robot).unwrap().0
+}
+
+#[test]
+fn moves_and_standby_draw_energy() {
+    let model = BatteryModel { capacity_wh: 100.0, wh_per_cell: 0.5, loaded_extra: 1.0, standby_w: 0.0, ..default() };
+    let (mut app, robot) = battery_app(model, RobotState::Moving);
+
+    // Premier tick : position de référence, puis une cellule à vide et une en charge
+    app.update();
+    assert_eq!(level(&app, robot), 1.0);
+    app.world_mut().get_mut::<GridPosition>(robot).unwrap().0 = GridPos::new(1, 0);
+    app.update();
+    assert!((level(&app, robot) - 0.995).abs() < 1e-6);
+    app.world_mut().get_mut::<Loaded>(robot).unwrap().0 = true;
+    app.world_mut().get_mut::<GridPosition>(robot).unwrap().0 = GridPos::new(2, 0);
+    app.update();
+    assert!((level(&app, robot) - 0.985).abs() < 1e-6);
+
+    // Sur place, seul le standby compte : ici nul
+    app.update();
+    assert!((level(&app, robot) - 0.985).abs() < 1e-6);
+    let metrics = app.world().resource::<SimMetrics>();
+    assert!((metrics.energy_used_wh - 1.5).abs() < 1e-6);
+
+    // Une seconde de standby à 3600 W vide une batterie de 1 Wh, tick après tick
+    let model = BatteryModel { capacity_wh: 1.0, standby_w: 3600.0, ..default() };
+    let (mut app, robot) = battery_app(model, RobotState::Idle);
+    let ticks = (1.0 / TICK_DELTA).round() as usize;
+    for _ in 0..ticks - 1 {
+        app.update();
+    }
+    assert!((level(&app, robot) - TICK_DELTA).abs() < 1e-4);
+    assert_eq!(app.world().get::<State>(robot).unwrap().0, RobotState::Idle);
+    app.update();
+    assert_eq!(app.world().get::<State>(robot).unwrap().0, RobotState::Fault);
+    let events = app.world().resource::<Messages<SimEvent>>();
+    assert!(events.iter_current_update_messages().any(|e| e.kind == SimEventKind::BatteryDepleted));
+}
+
+#[test]
+fn charging_tapers_above_cv_level() {
+    let model = BatteryModel { charge_power_w: 100.0, cv_level: 0.8, cutoff: 0.1, ..default() };
+    assert_eq!(model.charge_power(0.2), 100.0);
+    assert_eq!(model.charge_power(0.79), 100.0);
+    assert!((model.charge_power(0.9) - 50.0).abs() < 1e-3);
+    assert!((model.charge_power(0.99) - 10.0).abs() < 1e-3);
+
+    // La batterie atteint le plein sans le dépasser
+    let model = BatteryModel { capacity_wh: 1.0, charge_power_w: 360.0, ..default() };
+    let (mut app, robot) = battery_app(model, RobotState::Charging);
+    app.world_mut().get_mut::<Battery>(robot).unwrap().0 = 0.5;
+    let mut previous = 0.5;
+    let mut gains = Vec::new();
+    for _ in 0..900 {
+        app.update();
+        gains.push(level(&app, robot) - previous);
+        previous = level(&app, robot);
+    }
+    assert_eq!(level(&app, robot), 1.0);
+    // Phase à courant constant, puis gains décroissants
+    assert!((gains[0] - 360.0 * TICK_DELTA / 3600.0).abs() < 1e-5);
+    let cv = gains.iter().position(|&g| g < gains[0] * 0.99).unwrap();
+    assert!(gains[cv..].windows(2).all(|w| w[1] <= w[0] + 1e-6));
+    assert!(app.world().resource::<SimMetrics>().energy_charged_wh > 0.49);
+}
+
+#[test]
+fn capacity_fades_with_cycles() {
+    let model = BatteryModel { capacity_wh: 100.0, fade_per_cycle: 0.001, ..default() };
+    let fresh = BatteryUsage::default();
+    let worn = BatteryUsage { discharged_wh: 100_000.0, ..default() };
+    assert_eq!(worn.cycles(&model), 1000.0);
+    assert_eq!(model.capacity(&fresh), 100.0);
+    assert!((model.capacity(&worn) - 1.0).abs() < 1e-3);
+
+    // Même déplacement, batterie usée : le niveau baisse plus vite
+    let drop = |usage: BatteryUsage| {
+        let (mut app, robot) = battery_app(BatteryModel { standby_w: 0.0, ..model.clone() }, RobotState::Moving);
+        app.world_mut().entity_mut(robot).insert(usage);
+        app.update();
+        app.world_mut().get_mut::<GridPosition>(robot).unwrap().0 = GridPos::new(1, 0);
+        app.update();
+        1.0 - level(&app, robot)
+    };
+    let worn = BatteryUsage { discharged_wh: 50_000.0, ..default() };
+    assert!((drop(worn) - 2.0 * drop(fresh)).abs() < 1e-5);
+}
+
+/// Tick atteint, énergie consommée et niveaux des batteries après au moins `ticks` ticks,
+/// avec des frames de `frame` ticks
+fn energy_after(ticks: u64, frame: f64) -> (u64, f64, Vec<u32>) {
+    let settings = SimulationSettings { grid_size: Some((40, 30)), robots: 12, seed: Some(3), ..default() };
+    let mut app = common::headless_app(settings, ticks);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        TICK_DELTA as f64 * frame,
+    )));
+    while app.world().resource::<SpaceTimeTable>().current_tick() < ticks {
+        app.update();
+    }
+
+    let world = app.world_mut();
+    let tick = world.resource::<SpaceTimeTable>().current_tick();
+    let energy = world.resource::<SimMetrics>().energy_used_wh;
+    let mut levels: Vec<(Entity, u32)> = world
+        .query_filtered::<(Entity, &Battery), With<Robot>>()
+        .iter(world)
+        .map(|(e, b)| (e, b.0.to_bits()))
+        .collect();
+    levels.sort_by_key(|&(e, _)| e.index());
+    (tick, energy, levels.into_iter().map(|(_, bits)| bits).collect())
+}
+
+#[test]
+fn energy_does_not_depend_on_frame_rate() {
+    // Plusieurs ticks par frame, puis plusieurs frames par tick, arrêtées au même tick
+    let slow = energy_after(600, 4.0);
+    let fast = energy_after(slow.0, 0.5);
+    assert_eq!(slow.0, fast.0, "runs stopped on different ticks");
+    assert!(slow.1 > 0.0);
+    assert_eq!(slow, fast);
+}
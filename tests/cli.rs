@@ -0,0 +1,151 @@
+//! Les options invalides sont refusées par la ligne de commande, avant toute simulation.
+
+use bevy::prelude::*;
+use clap::Parser;
+use warehouse_sim::cli::{Cli, Command};
+use warehouse_sim::constants::{PROCEDURAL_MIN_HEIGHT, PROCEDURAL_MIN_WIDTH};
+use warehouse_sim::core::{HighwayGraph, HighwayMode, SpaceTimeTable};
+use warehouse_sim::plugins::movingai::{MovingAiError, MovingAiPlugin};
+use warehouse_sim::plugins::simulation::{HeadlessPlugin, SimulationCorePlugin};
+use warehouse_sim::systems::inventory::Inventory;
+use warehouse_sim::systems::scenario::ScenarioResults;
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+    Cli::try_parse_from(["warehouse_sim", "headless"].iter().chain(args))
+}
+
+#[test]
+fn procedural_layout_has_a_minimum_size() {
+    assert!(parse(&["--width", "5"]).is_err());
+    assert!(parse(&["--height", "4"]).is_err());
+
+    let (width, height) = (PROCEDURAL_MIN_WIDTH.to_string(), PROCEDURAL_MIN_HEIGHT.to_string());
+    let cli = parse(&["--width", &width, "--height", &height]).unwrap();
+    let Some(Command::Headless { sim, .. }) = cli.command else {
+        panic!("expected the headless command");
+    };
+    assert_eq!(sim.settings().grid_size, Some((PROCEDURAL_MIN_WIDTH, PROCEDURAL_MIN_HEIGHT)));
+}
+
+#[test]
+fn unreadable_files_are_cli_errors() {
+    let dir = std::env::temp_dir().join(format!("warehouse_sim_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let empty = dir.join("empty.ron");
+    std::fs::write(
+        &empty,
+        "(version: 1, width: 5, height: 5, spawn_points: [], storage_cells: [], cargo_cells: [])",
+    )
+    .unwrap();
+    let empty = empty.to_str().unwrap();
+    let duplicates = dir.join("duplicates.ron");
+    std::fs::write(
+        &duplicates,
+        "[(cell: (x: 3, y: 1), sku: 1, quantity: 0, capacity: 10), (cell: (x: 3, y: 1), sku: 2, quantity: 10, capacity: 10)]",
+    )
+    .unwrap();
+    let duplicates = duplicates.to_str().unwrap();
+
+    for args in [
+        ["--layout", "/nonexistent/layout.ron"],
+        ["--layout", empty],
+        ["--resume", "/nonexistent/snapshot.ron"],
+        ["--resume", empty],
+        ["--inventory", duplicates],
+    ] {
+        let error = parse(&args).unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation, "{args:?}");
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn order_rate_must_be_positive() {
+    for rate in ["0", "-3", "inf", "fast"] {
+        assert!(parse(&["--order-rate", rate]).is_err(), "{rate}");
+    }
+    assert!(parse(&["--order-rate", "2.5"]).is_ok());
+}
+
+/// Couloirs à sens unique stricts par défaut, mode souple ou libre sur demande
+#[test]
+fn highway_mode_reaches_the_simulation() {
+    let mode = |args: &[&str]| {
+        let Some(Command::Headless { sim, .. }) = parse(args).unwrap().command else {
+            panic!("expected the headless command");
+        };
+        sim.settings().highway_mode
+    };
+    assert_eq!(mode(&[]), HighwayMode::Strict);
+    assert_eq!(mode(&["--highways", "open"]), HighwayMode::Open);
+    assert_eq!(mode(&["--highways", "soft", "--against-flow-cost", "5"]), HighwayMode::Soft { against_flow_cost: 5.0 });
+    assert!(parse(&["--highways", "reversed"]).is_err());
+    assert_eq!(mode(&["--highways", "soft", "--against-flow-cost=-2"]), HighwayMode::Soft { against_flow_cost: 0.0 });
+    assert!(parse(&["--against-flow-cost", "5"]).is_err());
+
+    let Some(Command::Headless { sim, .. }) = parse(&["--highways", "soft"]).unwrap().command else {
+        panic!("expected the headless command");
+    };
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugins(SimulationCorePlugin { settings: sim.settings() });
+    assert_eq!(app.world().resource::<HighwayGraph>().mode(), HighwayMode::Soft { against_flow_cost: 3.0 });
+}
+
+/// Une instance MovingAI se lance depuis la ligne de commande et chaque agent atteint son but
+#[test]
+fn movingai_instances_run_from_the_command_line() {
+    let dir = std::env::temp_dir().join(format!("warehouse_sim_movingai_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let map = dir.join("room.map");
+    std::fs::write(&map, "type octile\nheight 5\nwidth 6\nmap\n......\n.@@.T.\n......\n.T.@@.\n......\n").unwrap();
+    let scen = dir.join("room.scen");
+    std::fs::write(
+        &scen,
+        "version 1\n\
+         0\troom.map\t6\t5\t0\t0\t5\t4\t9\n\
+         0\troom.map\t6\t5\t5\t0\t0\t4\t9\n\
+         0\troom.map\t6\t5\t0\t2\t5\t2\t5\n",
+    )
+    .unwrap();
+    let (map, scen) = (map.to_str().unwrap(), scen.to_str().unwrap());
+
+    // Les deux fichiers vont ensemble et remplacent l'entrepôt
+    assert!(parse(&["--movingai-map", map]).is_err());
+    assert!(parse(&["--movingai-map", map, "--movingai-scen", scen, "--width", "40"]).is_err());
+    let error = parse(&["--movingai-map", scen, "--movingai-scen", scen]).unwrap_err();
+    assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+
+    let cli = parse(&["--movingai-map", map, "--movingai-scen", scen, "--agents", "2"]).unwrap();
+    let Some(Command::Headless { sim, .. }) = cli.command else {
+        panic!("expected the headless command");
+    };
+    let movingai = sim.movingai().unwrap().unwrap();
+    assert_eq!(movingai.scenario.agents.len(), 2);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SimulationCorePlugin { settings: sim.settings() })
+        .add_plugins(movingai)
+        .add_plugins(HeadlessPlugin { ticks: 200 });
+    app.finish();
+    app.cleanup();
+    // Le stock de l'entrepôt remplacé ne survit pas à ses zones
+    assert!(app.world().resource::<Inventory>().slots().is_empty());
+    while !app.world().resource::<ScenarioResults>().is_complete() {
+        assert!(app.world().resource::<SpaceTimeTable>().current_tick() < 200, "agents never arrived");
+        app.update();
+    }
+
+    // Un agent sur un obstacle est rendu à l'appelant, sans panique
+    let blocked = dir.join("blocked.scen");
+    std::fs::write(&blocked, "version 1\n0\troom.map\t6\t5\t1\t1\t5\t4\t9\n").unwrap();
+    let error = MovingAiPlugin::load(map, &blocked, usize::MAX).err().unwrap();
+    assert!(matches!(error, MovingAiError::Scenario { .. }));
+    assert!(MovingAiPlugin::load(&blocked, scen, usize::MAX).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let results = app.world().resource::<ScenarioResults>();
+    assert_eq!(results.arrivals.len(), 2);
+    assert!(results.makespan() >= 9);
+}
@@ -0,0 +1,30 @@
+//! Fixtures partagées par les tests d'intégration ; chaque test n'en utilise qu'une partie.
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use warehouse_sim::plugins::simulation::{HeadlessPlugin, SimulationCorePlugin, SimulationSettings};
+
+/// Flotte de `robots` robots sur le layout procédural 60×40
+pub fn fleet(robots: u32) -> SimulationSettings {
+    SimulationSettings {
+        grid_size: Some((60, 40)),
+        robots,
+        ..default()
+    }
+}
+
+/// App headless prête à avancer d'un tick par update, pendant `ticks` ticks
+pub fn headless_app(settings: SimulationSettings, ticks: u64) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SimulationCorePlugin { settings })
+        .add_plugins(HeadlessPlugin { ticks });
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Entité d'index `index`, pour les tests qui appellent les solveurs sans monde
+pub fn entity(index: u32) -> Entity {
+    Entity::from_raw_u32(index).unwrap()
+}
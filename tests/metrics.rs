@@ -0,0 +1,34 @@
+//! Les indicateurs suivent le déroulé réel des chemins, attentes planifiées comprises.
+
+use bevy::prelude::*;
+use warehouse_sim::components::*;
+use warehouse_sim::core::{GridPos, SpaceTimeTable};
+use warehouse_sim::systems::metrics::{metrics_sampling_system, SimMetrics};
+use warehouse_sim::systems::navigation::{path_execution_system, simulation_tick_system};
+
+#[test]
+fn planned_waits_count_as_waiting() {
+    let mut app = App::new();
+    app.init_resource::<SpaceTimeTable>()
+        .init_resource::<SimMetrics>()
+        .add_systems(
+            Update,
+            (simulation_tick_system, path_execution_system, metrics_sampling_system).chain(),
+        );
+
+    // Un pas, deux attentes sur place, un pas
+    let (a, b, c) = (GridPos::new(0, 0), GridPos::new(1, 0), GridPos::new(2, 0));
+    app.world_mut().spawn((
+        Robot,
+        GridPosition(a),
+        State(RobotState::Moving),
+        PlannedPath::new(vec![(b, 1), (b, 2), (b, 3), (c, 4)]),
+    ));
+
+    for _ in 0..4 {
+        app.update();
+    }
+    let metrics = app.world().resource::<SimMetrics>();
+    assert_eq!(metrics.busy_ticks, 4);
+    assert_eq!(metrics.wait_ticks, 2);
+}
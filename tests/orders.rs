@@ -0,0 +1,252 @@
+//! Catalogue des références et quais du flux de commandes.
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use warehouse_sim::components::OrderLine;
+use warehouse_sim::core::{GridPos, SimRng, SpaceTimeTable, WarehouseLayout};
+use warehouse_sim::systems::inventory::{Inventory, InventoryConfig, InventoryError, Pick, Slot};
+use warehouse_sim::systems::metrics::SimMetrics;
+use warehouse_sim::systems::orders::{
+    order_arrival_system, OrderBook, OrderConfig, OrderError, OrderSource, OrderSpec,
+};
+
+const LAYOUT: &str = "
+    S.......C
+    ..A.A...C
+    ..ARA....
+    ..A.A...C
+    S.......C
+";
+
+#[test]
+fn docks_group_contiguous_cargo_cells() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let docks = zones.docks();
+    assert_eq!(
+        docks,
+        vec![
+            vec![GridPos::new(8, 0), GridPos::new(8, 1)],
+            vec![GridPos::new(8, 3), GridPos::new(8, 4)],
+        ]
+    );
+}
+
+#[test]
+fn skus_are_spread_over_storage_cells() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let inventory = InventoryConfig::default().build(&zones, 2).unwrap();
+    let even = inventory.stocked_cells(0);
+    let odd = inventory.stocked_cells(1);
+    assert_eq!(even.len() + odd.len(), zones.storage_cells.len());
+    assert!(even.iter().all(|cell| !odd.contains(cell)));
+    // Une référence par storage par défaut
+    let config = OrderConfig::default();
+    let skus = config.sku_count(&zones);
+    assert_eq!(skus as usize, zones.storage_cells.len());
+    let inventory = InventoryConfig::default().build(&zones, skus).unwrap();
+    assert_eq!(inventory.stocked_cells(3), vec![zones.storage_cells[3]]);
+}
+
+#[test]
+fn popular_skus_are_ordered_more() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let mut rng = SimRng::new(1);
+    let mut counts = [0u32; 6];
+    let skewed = OrderConfig { skus: Some(6), sku_skew: 1.5, ..Default::default() };
+    for _ in 0..6000 {
+        counts[skewed.sample_sku(&zones, &mut rng) as usize] += 1;
+    }
+    assert!(counts.windows(2).all(|w| w[0] > w[1]), "{counts:?}");
+
+    let mut counts = [0u32; 6];
+    let uniform = OrderConfig { sku_skew: 0.0, ..skewed };
+    for _ in 0..6000 {
+        counts[uniform.sample_sku(&zones, &mut rng) as usize] += 1;
+    }
+    assert!(counts.iter().all(|&n| (800..1200).contains(&n)), "{counts:?}");
+}
+
+#[test]
+fn orders_close_after_their_last_line() {
+    let mut book = OrderBook::default();
+    let mut metrics = SimMetrics::default();
+    let id = book.publish(10, &[4, 2], 1);
+    assert_eq!(book.pending.len(), 2);
+
+    book.complete_line(OrderLine { order: id, sku: 4, dock: 1 }, 50, &mut metrics);
+    assert_eq!(book.open.len(), 1);
+    book.complete_line(OrderLine { order: id, sku: 2, dock: 1 }, 70, &mut metrics);
+    assert!(book.open.is_empty());
+    assert_eq!(metrics.order_lead_times, vec![60]);
+}
+
+#[test]
+fn picks_empty_slots_then_skip_them() {
+    let (_, mut zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let config = InventoryConfig { capacity: 2, ..Default::default() };
+    let mut inventory = config.build(&zones, 2).unwrap();
+    let cell = inventory.stocked_cells(0)[0];
+    let faces: Vec<_> = inventory.slots_at(GridPos::new(2, 2)).map(|slot| slot.face).collect();
+    assert_eq!(faces, vec![Some(GridPos::new(3, 2))]);
+    assert_eq!(inventory.cell_fill(cell), Some(1.0));
+
+    assert_eq!(inventory.pick(cell, Some(1)), Pick::Empty);
+    assert_eq!(inventory.pick(cell, Some(0)), Pick::Picked(0));
+    assert_eq!(inventory.pick(cell, None), Pick::Picked(0));
+    assert_eq!(inventory.pick(cell, None), Pick::Empty);
+    assert!(!inventory.is_stocked(cell));
+    assert_eq!(inventory.empty_slots(), 1);
+    assert!(!inventory.stocked_cells(0).contains(&cell));
+
+    // La ligne va vers un autre storage de la référence
+    let mut book = OrderBook::default();
+    book.publish(0, &[0], 0);
+    let docks = zones.docks();
+    let (_, storage, _) = book.take_line(&inventory, &mut zones, &docks, cell).unwrap();
+    assert_ne!(storage, cell);
+    assert!(inventory.slots_at(storage).any(|slot| slot.sku == 0));
+}
+
+#[test]
+fn stockouts_close_lines_short() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let slots = vec![Slot { cell: zones.storage_cells[0], face: None, sku: 1, quantity: 0, capacity: 10 }];
+    let inventory = InventoryConfig { slots: Some(slots), ..Default::default() }.build(&zones, 2).unwrap();
+    assert_eq!(inventory.slots().len(), 1);
+    assert!(inventory.is_stocked(zones.storage_cells[1]), "untracked storage");
+
+    let mut book = OrderBook::default();
+    let mut metrics = SimMetrics::default();
+    let id = book.publish(10, &[1, 1], 0);
+    book.drop_stockouts(&inventory, 40, &mut metrics);
+    assert!(book.pending.is_empty() && book.open.is_empty());
+    assert_eq!(metrics.stockouts, 2);
+    assert_eq!(metrics.order_lead_times, vec![30], "order {id}");
+}
+
+/// Un second emplacement sur le même storage ne serait jamais prélevé : le plan est refusé
+#[test]
+fn duplicate_slots_are_rejected() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let cell = zones.storage_cells[0];
+    let slots = vec![
+        Slot { cell, face: None, sku: 1, quantity: 0, capacity: 10 },
+        Slot { cell, face: None, sku: 2, quantity: 10, capacity: 10 },
+    ];
+    let duplicate = InventoryError::DuplicateSlot { cell, face: None };
+    assert_eq!(InventoryConfig::check_slots(&slots), Err(duplicate.clone()));
+    let config = InventoryConfig { slots: Some(slots), ..Default::default() };
+    assert_eq!(config.build(&zones, 3), Err(duplicate));
+}
+
+#[test]
+fn scripts_are_sorted_and_checked_against_the_layout() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let order = |tick, lines: &[u32], dock| OrderSpec { tick, lines: lines.to_vec(), dock };
+    let script = |source| OrderConfig { source, skus: Some(4), ..Default::default() };
+
+    let specs = vec![order(90, &[1], None), order(30, &[0], Some(1)), order(30, &[3], None)];
+    let OrderSource::Script(sorted) = OrderSource::script(specs.clone()) else {
+        unreachable!();
+    };
+    assert_eq!(sorted, vec![specs[1].clone(), specs[2].clone(), specs[0].clone()]);
+    assert_eq!(script(OrderSource::Script(sorted)).validate(&zones), Ok(()));
+
+    assert_eq!(script(OrderSource::Script(specs)).validate(&zones), Err(OrderError::Unsorted { tick: 30 }));
+    assert_eq!(
+        script(OrderSource::script(vec![order(30, &[0], Some(2))])).validate(&zones),
+        Err(OrderError::UnknownDock { tick: 30, dock: 2, docks: 2 })
+    );
+    assert_eq!(
+        script(OrderSource::script(vec![order(30, &[0, 4], None)])).validate(&zones),
+        Err(OrderError::UnknownSku { tick: 30, sku: 4, skus: 4 })
+    );
+}
+
+/// Les emplacements se retrouvent par cellule, y compris après un aller-retour en RON
+#[test]
+fn slots_are_indexed_by_cell() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let inventory = InventoryConfig { capacity: 2, ..Default::default() }.build(&zones, 3).unwrap();
+    let mut restored: Inventory = ron::from_str(&ron::to_string(&inventory).unwrap()).unwrap();
+    assert_eq!(restored, inventory);
+
+    for slot in inventory.slots() {
+        assert_eq!(restored.slot(slot.cell, slot.face), Some(slot));
+    }
+    assert_eq!(restored.slot(GridPos::new(0, 0), None), None);
+    assert_eq!(restored.pick(GridPos::new(0, 0), None), Pick::Untracked);
+
+    let Slot { cell, face, sku, .. } = inventory.slots()[2];
+    for _ in 0..2 {
+        assert_eq!(restored.pick(cell, Some(sku)), Pick::Picked(sku));
+    }
+    assert_eq!(restored.pick(cell, Some(sku)), Pick::Empty);
+    assert_eq!(restored.slot(cell, face).unwrap().quantity, 0);
+    assert_eq!(restored.slot_mut(cell, face).map(|slot| slot.capacity), Some(2));
+}
+
+/// Un storage entre deux racks a un emplacement par face, dans l'ordre nord, est, sud,
+/// ouest ; dans un plan explicite, un emplacement sans face prend la première
+#[test]
+fn storage_between_two_racks_serves_both_faces() {
+    let (_, zones) = WarehouseLayout::from_ascii(
+        "
+        S.....C
+        .RAR...
+        S.....C
+        ",
+    )
+    .unwrap()
+    .build();
+    let cell = GridPos::new(2, 1);
+    let (east, west) = (GridPos::new(3, 1), GridPos::new(1, 1));
+    let mut inventory = InventoryConfig { capacity: 1, ..Default::default() }.build(&zones, 2).unwrap();
+    let faces: Vec<_> = inventory.slots_at(cell).map(|slot| (slot.face, slot.sku)).collect();
+    assert_eq!(faces, vec![(Some(east), 0), (Some(west), 1)]);
+    assert_eq!(inventory.stocked_cells(1), vec![cell]);
+
+    // Chaque référence se prélève sur sa face, le storage reste servable tant qu'une face
+    // a du stock
+    assert_eq!(inventory.pick(cell, Some(1)), Pick::Picked(1));
+    assert_eq!(inventory.slot(cell, Some(west)).unwrap().quantity, 0);
+    assert!(inventory.is_stocked(cell));
+    assert_eq!(inventory.cell_fill(cell), Some(0.5));
+    assert_eq!(inventory.pick(cell, Some(1)), Pick::Empty);
+    assert_eq!(inventory.pick(cell, None), Pick::Picked(0));
+    assert!(!inventory.is_stocked(cell));
+
+    let slot = |face, sku| Slot { cell, face, sku, quantity: 1, capacity: 1 };
+    let config = |slots| InventoryConfig { slots: Some(slots), ..Default::default() };
+    let inventory = config(vec![slot(None, 0), slot(Some(west), 1)]).build(&zones, 2).unwrap();
+    assert_eq!(inventory.slot(cell, Some(east)).unwrap().sku, 0);
+    assert_eq!(inventory.slot(cell, Some(west)).unwrap().sku, 1);
+    assert_eq!(
+        config(vec![slot(None, 0), slot(Some(east), 1)]).build(&zones, 2),
+        Err(InventoryError::DuplicateSlot { cell, face: Some(east) })
+    );
+}
+
+/// Un débit démesuré publie au plus une commande par tick au lieu de boucler sans fin
+#[test]
+fn extreme_order_rates_publish_one_order_per_tick() {
+    let (_, zones) = WarehouseLayout::from_ascii(LAYOUT).unwrap().build();
+    let mut world = World::new();
+    world.insert_resource(InventoryConfig::default().build(&zones, 1).unwrap());
+    world.insert_resource(zones);
+    world.insert_resource(OrderConfig {
+        source: OrderSource::Poisson { rate: 1e9, max_lines: 1 },
+        ..Default::default()
+    });
+    world.insert_resource(SimRng::new(3));
+    world.init_resource::<OrderBook>();
+    world.init_resource::<SimMetrics>();
+    world.init_resource::<SpaceTimeTable>();
+
+    for _ in 0..10 {
+        world.resource_mut::<SpaceTimeTable>().advance_tick();
+        world.run_system_once(order_arrival_system).unwrap();
+    }
+    // Premier tirage au tick 1, première arrivée au tick 2
+    assert_eq!(world.resource::<SimMetrics>().orders_received, 9);
+}
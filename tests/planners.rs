@@ -0,0 +1,298 @@
+//! Régressions des solveurs, appelés directement sur un problème construit à la main.
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use warehouse_sim::components::*;
+use warehouse_sim::constants::PIBT_DISTANCE_CACHE;
+use warehouse_sim::core::{CellType, GridPos, HighwayGraph, SpaceTimeTable, WarehouseGrid};
+use warehouse_sim::systems::cbs::ConflictBasedSearch;
+use warehouse_sim::systems::events::SimEvent;
+use warehouse_sim::systems::metrics::SimMetrics;
+use warehouse_sim::systems::pbs::{Path, PbsConfig, PbsPlanner, PriorityBasedSearch, StaticObstacles};
+use warehouse_sim::systems::pibt::Pibt;
+use warehouse_sim::systems::planner::{
+    planning_system, MultiAgentPlanner, PlannerRegistry, PlanningAgent, PlanningProblem,
+};
+use warehouse_sim::systems::sipp::SippPlanner;
+
+mod common;
+
+use common::entity;
+
+/// Grille vide, circulation dans les deux sens
+fn open_grid(width: u32, height: u32) -> (WarehouseGrid, HighwayGraph) {
+    (WarehouseGrid::new(width, height), HighwayGraph::new(width, height))
+}
+
+/// Monde minimal pour faire tourner `planning_system`
+fn planning_world(grid: WarehouseGrid, highways: HighwayGraph) -> World {
+    let mut world = World::new();
+    world.insert_resource(grid);
+    world.insert_resource(highways);
+    world.insert_resource(SpaceTimeTable::default());
+    world.insert_resource(PbsConfig::default());
+    world.insert_resource(PlannerRegistry::default());
+    world.init_resource::<SimMetrics>();
+    world.init_resource::<Messages<SimEvent>>();
+    world
+}
+
+/// Position sur le chemin à `tick`
+fn at(path: &Path, tick: u64) -> GridPos {
+    path.iter().rev().find(|&&(_, at)| at <= tick).unwrap().0
+}
+
+/// Cellule occupée à chaque tick jusqu'à `until` inclus, le robot restant sur la fin de
+/// son chemin
+fn occupancy(path: &Path, until: u64) -> impl Iterator<Item = (GridPos, u64)> + '_ {
+    (path[0].1..=until).map(move |tick| (at(path, tick), tick))
+}
+
+/// Robot à l'arrêt sur `pos` pendant tout l'horizon, comme le prépare la planification
+fn park(space_time: &mut SpaceTimeTable, obstacles: &mut StaticObstacles, pos: GridPos, robot: Entity, config: &PbsConfig) {
+    obstacles.insert(pos, robot);
+    for tick in 0..config.horizon {
+        space_time.reserve(pos, tick, robot);
+    }
+}
+
+/// But occupé par un robot à l'arrêt : les deux planificateurs bas niveau s'en approchent
+/// au plus près au lieu de ne rien renvoyer
+#[test]
+fn blocked_goal_gives_a_partial_path() {
+    let (grid, highways) = open_grid(8, 2);
+    let config = PbsConfig::default();
+    let mut space_time = SpaceTimeTable::default();
+    let mut obstacles = StaticObstacles::default();
+    let goal = GridPos::new(0, 0);
+    park(&mut space_time, &mut obstacles, goal, entity(1), &config);
+
+    let robot = entity(2);
+    let start = GridPos::new(7, 0);
+    let astar = PbsPlanner::new(&grid, &highways, &space_time, &obstacles, &config).plan_path(start, goal, 0, robot);
+    let sipp = SippPlanner::new(&grid, &highways, &space_time, &obstacles, &config).plan_path(start, goal, 0, robot);
+    for (name, path) in [("astar", astar), ("sipp", sipp)] {
+        let path = path.unwrap_or_else(|| panic!("{name}: no path towards a blocked goal"));
+        assert!(path.iter().all(|&(pos, _)| pos != goal), "{name} enters the blocked goal");
+        assert_eq!(path.last().unwrap().0.manhattan_distance(&goal), 1, "{name}");
+    }
+}
+
+/// Un robot arrivé à son but s'arrête au tick suivant : PIBT ne doit pas le pousser plus
+/// loin, même pour un robot plus prioritaire
+#[test]
+fn pibt_does_not_push_arrived_robots() {
+    let (grid, highways) = open_grid(6, 1);
+    let config = PbsConfig::default();
+    let space_time = SpaceTimeTable::default();
+    let obstacles = StaticObstacles::default();
+    let (mover, arrived) = (entity(1), entity(2));
+    let problem = PlanningProblem {
+        grid: &grid,
+        highways: &highways,
+        reservations: &space_time,
+        static_obstacles: &obstacles,
+        config: &config,
+        start_tick: 0,
+        agents: vec![
+            PlanningAgent { entity: mover, start: GridPos::new(1, 0), goal: GridPos::new(5, 0), priority: 0 },
+            PlanningAgent { entity: arrived, start: GridPos::new(2, 0), goal: GridPos::new(2, 0), priority: 30 },
+        ],
+    };
+
+    let outcome = Pibt::default().plan(&problem);
+    let mover_path = outcome.paths[0].as_ref().unwrap();
+    let arrived_path = outcome.paths[1].as_ref().unwrap();
+    assert_eq!(at(arrived_path, 1), GridPos::new(2, 0));
+    assert_eq!(at(mover_path, 1), GridPos::new(1, 0));
+}
+
+/// Un robot qui tombe en panne avec un pas déjà engagé termine ce pas : c'est la cellule
+/// d'arrivée qui doit être réservée pour tout l'horizon, pas celle qu'il quitte
+#[test]
+fn stationary_robots_block_their_committed_cell() {
+    let (grid, highways) = open_grid(6, 2);
+    let mut world = planning_world(grid, highways);
+    let tick = PbsConfig::default().replan_interval;
+    world.resource_mut::<SpaceTimeTable>().set_current_tick(tick);
+
+    let (from, to) = (GridPos::new(1, 0), GridPos::new(2, 0));
+    let robot = world
+        .spawn((
+            Robot,
+            GridPosition(from),
+            Destination(GridPos::new(5, 0)),
+            State(RobotState::Fault),
+            PlannedPath::new(vec![(to, tick), (GridPos::new(3, 0), tick + 1)]),
+        ))
+        .id();
+    world.run_system_once(planning_system).unwrap();
+
+    let later = tick + 20;
+    let table = world.resource::<SpaceTimeTable>();
+    assert_eq!(table.occupant(to, later), Some(robot));
+    assert_eq!(table.occupant(from, later), None);
+    assert_eq!(world.get::<PlannedPath>(robot).unwrap().remaining(), &[(to, tick)]);
+}
+
+/// Un chemin partiel qui s'arrête avant la replanification garde sa dernière cellule
+/// jusqu'à celle-ci incluse : le robot y est encore quand les chemins sont recalculés
+fn assert_ends_held_through_replan(name: &str, planner: &mut dyn MultiAgentPlanner) {
+    let config = PbsConfig::default();
+    let next_replan = config.replan_interval;
+
+    // Cellule d'arrêt réservée par un autre robot exactement au tick de replanification
+    let (grid, highways) = open_grid(5, 1);
+    let mut space_time = SpaceTimeTable::default();
+    let mut obstacles = StaticObstacles::default();
+    park(&mut space_time, &mut obstacles, GridPos::new(3, 0), entity(1), &config);
+    space_time.reserve(GridPos::new(2, 0), next_replan, entity(2));
+    let robot = entity(3);
+    let problem = PlanningProblem {
+        grid: &grid,
+        highways: &highways,
+        reservations: &space_time,
+        static_obstacles: &obstacles,
+        config: &config,
+        start_tick: 0,
+        agents: vec![PlanningAgent { entity: robot, start: GridPos::new(0, 0), goal: GridPos::new(4, 0), priority: 0 }],
+    };
+    let outcome = planner.plan(&problem);
+    let path = outcome.paths[0].as_ref().unwrap_or_else(|| panic!("{name}: no path"));
+    for (pos, tick) in occupancy(path, next_replan) {
+        assert!(space_time.is_free(pos, tick, Some(robot)), "{name} stops on {pos:?}, taken at tick {tick}");
+    }
+
+    // Chemin d'un robot prioritaire bloqué devant son but, croisé par un autre robot
+    let (grid, highways) = open_grid(3, 6);
+    let mut space_time = SpaceTimeTable::default();
+    let mut obstacles = StaticObstacles::default();
+    park(&mut space_time, &mut obstacles, GridPos::new(2, 2), entity(1), &config);
+    let problem = PlanningProblem {
+        grid: &grid,
+        highways: &highways,
+        reservations: &space_time,
+        static_obstacles: &obstacles,
+        config: &config,
+        start_tick: 0,
+        agents: vec![
+            PlanningAgent { entity: entity(2), start: GridPos::new(0, 2), goal: GridPos::new(2, 2), priority: 30 },
+            PlanningAgent { entity: entity(3), start: GridPos::new(1, 5), goal: GridPos::new(1, 0), priority: 0 },
+        ],
+    };
+    let outcome = planner.plan(&problem);
+    let blocked = outcome.paths[0].as_ref().unwrap_or_else(|| panic!("{name}: no path for the blocked robot"));
+    let crossing = outcome.paths[1].as_ref().unwrap_or_else(|| panic!("{name}: no path for the crossing robot"));
+    for (a, b) in occupancy(blocked, next_replan).zip(occupancy(crossing, next_replan)) {
+        assert_ne!(a, b, "{name}: both robots share a cell");
+    }
+}
+
+#[test]
+fn pbs_holds_partial_path_ends_through_the_next_replan() {
+    assert_ends_held_through_replan("pbs", &mut PriorityBasedSearch);
+}
+
+#[test]
+fn cbs_holds_partial_path_ends_through_the_next_replan() {
+    assert_ends_held_through_replan("cbs", &mut ConflictBasedSearch::optimal());
+    assert_ends_held_through_replan("ecbs", &mut ConflictBasedSearch::bounded());
+}
+
+/// Un robot en route dont le pas du tick courant est dû repart de la cellule où ce pas
+/// l'amène : le replanifier depuis sa cellule actuelle le ferait reculer
+#[test]
+fn moving_robots_plan_from_their_committed_cell() {
+    let (grid, highways) = open_grid(6, 2);
+    let mut world = planning_world(grid, highways);
+    let tick = PbsConfig::default().replan_interval;
+    world.resource_mut::<SpaceTimeTable>().set_current_tick(tick);
+
+    let (from, to) = (GridPos::new(1, 0), GridPos::new(2, 0));
+    let robot = world
+        .spawn((
+            Robot,
+            GridPosition(from),
+            Destination(GridPos::new(5, 0)),
+            State(RobotState::Moving),
+            PlannedPath::new(vec![(to, tick), (GridPos::new(3, 0), tick + 1)]),
+        ))
+        .id();
+    world.run_system_once(planning_system).unwrap();
+
+    let path = world.get::<PlannedPath>(robot).unwrap().remaining().to_vec();
+    assert_eq!(path[0], (to, tick));
+    assert!(path.iter().all(|&(pos, _)| pos != from), "replanned path goes back to {from:?}");
+    assert_eq!(world.resource::<SpaceTimeTable>().occupant(to, tick), Some(robot));
+}
+
+/// Les champs de distance de PIBT suivent les obstacles : une grille de mêmes dimensions
+/// mais avec un mur ne réutilise pas les champs calculés sur la grille vide
+#[test]
+fn pibt_recomputes_distances_when_obstacles_change() {
+    let config = PbsConfig::default();
+    let space_time = SpaceTimeTable::default();
+    let obstacles = StaticObstacles::default();
+    let agents = vec![PlanningAgent { entity: entity(1), start: GridPos::new(0, 1), goal: GridPos::new(4, 1), priority: 0 }];
+    let mut pibt = Pibt::default();
+
+    let (grid, highways) = open_grid(5, 3);
+    let problem = PlanningProblem {
+        grid: &grid,
+        highways: &highways,
+        reservations: &space_time,
+        static_obstacles: &obstacles,
+        config: &config,
+        start_tick: 0,
+        agents: agents.clone(),
+    };
+    pibt.plan(&problem);
+
+    // Mur en x = 1, ouvert seulement en haut : il faut d'abord s'écarter du but
+    let (mut grid, highways) = open_grid(5, 3);
+    grid.set(GridPos::new(1, 1), CellType::Blocked);
+    grid.set(GridPos::new(1, 2), CellType::Blocked);
+    let problem = PlanningProblem {
+        grid: &grid,
+        highways: &highways,
+        reservations: &space_time,
+        static_obstacles: &obstacles,
+        config: &config,
+        start_tick: 0,
+        agents,
+    };
+    let outcome = pibt.plan(&problem);
+    assert_eq!(at(outcome.paths[0].as_ref().unwrap(), 1), GridPos::new(0, 0));
+}
+
+/// Le cache de champs de PIBT reste borné quand la flotte visite sans cesse de nouveaux
+/// buts, sans jamais évincer ceux du tick courant
+#[test]
+fn pibt_distance_cache_is_bounded() {
+    let config = PbsConfig::default();
+    let space_time = SpaceTimeTable::default();
+    let obstacles = StaticObstacles::default();
+    let (grid, highways) = open_grid(20, 20);
+    let mut pibt = Pibt::default();
+
+    let cells: Vec<GridPos> = (0..20).flat_map(|y| (0..20).map(move |x| GridPos::new(x, y))).collect();
+    for (i, goals) in cells.chunks(4).enumerate() {
+        let agents = goals
+            .iter()
+            .enumerate()
+            .map(|(j, &goal)| PlanningAgent { entity: entity(j as u32 + 1), start: cells[j], goal, priority: 0 })
+            .collect();
+        let problem = PlanningProblem {
+            grid: &grid,
+            highways: &highways,
+            reservations: &space_time,
+            static_obstacles: &obstacles,
+            config: &config,
+            start_tick: i as u64,
+            agents,
+        };
+        let outcome = pibt.plan(&problem);
+        assert!(outcome.paths.iter().all(Option::is_some));
+        assert_eq!(pibt.cached_fields(), (4 * (i + 1)).min(PIBT_DISTANCE_CACHE));
+    }
+}
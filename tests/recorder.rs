@@ -0,0 +1,130 @@
+//! L'enregistrement d'une exécution : CSV indexés par tick et format binaire colonnaire.
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use warehouse_sim::components::Robot;
+use warehouse_sim::core::SpaceTimeTable;
+use warehouse_sim::plugins::recorder::RecorderPlugin;
+use warehouse_sim::plugins::simulation::{HeadlessPlugin, SimulationCorePlugin};
+use warehouse_sim::systems::recorder::Recorder;
+
+mod common;
+
+const TICKS: u64 = 120;
+
+fn run_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("warehouse_sim_recorder_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn csv(path: &Path) -> (String, Vec<Vec<String>>) {
+    let text = std::fs::read_to_string(path).unwrap();
+    let mut lines = text.lines();
+    let header = lines.next().unwrap().to_string();
+    let rows = lines.map(|line| line.split(',').map(str::to_string).collect()).collect();
+    (header, rows)
+}
+
+/// Lecture d'un fichier colonnaire : nombre de lignes, puis nom, type et valeurs brutes
+/// de chaque colonne
+fn columnar(path: &Path) -> (u64, Vec<(String, u8, Vec<u8>)>) {
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[..4], b"WTSC");
+    assert_eq!(u16::from_le_bytes(bytes[4..6].try_into().unwrap()), 1);
+    let rows = u64::from_le_bytes(bytes[6..14].try_into().unwrap());
+    let count = u16::from_le_bytes(bytes[14..16].try_into().unwrap());
+
+    let mut at = 16;
+    let mut columns = Vec::new();
+    for _ in 0..count {
+        let len = bytes[at] as usize;
+        let name = String::from_utf8(bytes[at + 1..at + 1 + len].to_vec()).unwrap();
+        let kind = bytes[at + 1 + len];
+        at += len + 2;
+        let size = match kind {
+            0 => 1,
+            1 | 2 | 4 => 4,
+            3 => 8,
+            other => panic!("unknown column type {other}"),
+        };
+        let end = at + size * rows as usize;
+        columns.push((name, kind, bytes[at..end].to_vec()));
+        at = end;
+    }
+    assert_eq!(at, bytes.len(), "trailing bytes in {}", path.display());
+    (rows, columns)
+}
+
+fn u64_column(values: &[u8]) -> Vec<u64> {
+    values.chunks(8).map(|v| u64::from_le_bytes(v.try_into().unwrap())).collect()
+}
+
+#[test]
+fn runs_are_recorded_per_tick_in_csv_and_columnar_files() {
+    let dir = run_dir();
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(SimulationCorePlugin { settings: common::fleet(6) })
+        .add_plugins(RecorderPlugin::new(&dir).with_binary(true))
+        .add_plugins(HeadlessPlugin { ticks: TICKS });
+    app.finish();
+    app.cleanup();
+
+    // Robots présents à chaque tick, dans l'horloge de la simulation
+    let mut expected = Vec::new();
+    while app.world().resource::<SpaceTimeTable>().current_tick() < TICKS {
+        app.update();
+        let world = app.world_mut();
+        let tick = world.resource::<SpaceTimeTable>().current_tick();
+        let robots = world.query_filtered::<(), With<Robot>>().iter(world).count();
+        if robots > 0 && expected.last().is_none_or(|&(last, _)| last != tick) {
+            expected.push((tick, robots));
+        }
+    }
+    // Une ligne par tick dès le premier robot, jusqu'au dernier tick
+    assert_eq!(expected.last().map(|&(tick, _)| tick), Some(TICKS));
+    assert!(expected.windows(2).all(|w| w[1].0 == w[0].0 + 1));
+    // Tout est sur disque dès `finish`, sans attendre la destruction du recorder
+    app.world_mut().resource_mut::<Recorder>().finish().unwrap();
+
+    let (header, trajectories) = csv(&dir.join("trajectories.csv"));
+    assert_eq!(header, "tick,entity,x,y,state,loaded,battery");
+    let mut recorded: Vec<(u64, usize)> = Vec::new();
+    for row in &trajectories {
+        assert_eq!(row.len(), 7);
+        let tick: u64 = row[0].parse().unwrap();
+        match recorded.last_mut() {
+            Some((last, count)) if *last == tick => *count += 1,
+            _ => recorded.push((tick, 1)),
+        }
+    }
+    assert_eq!(recorded, expected);
+
+    let (header, events) = csv(&dir.join("events.csv"));
+    assert_eq!(header, "tick,entity,event,x,y");
+    assert!(events.iter().filter(|row| row[2] == "mission_assigned").count() >= 6);
+    let ticks: Vec<u64> = events.iter().map(|row| row[0].parse().unwrap()).collect();
+    assert!(ticks.windows(2).all(|w| w[0] <= w[1]));
+    assert!(ticks.iter().all(|&tick| (1..=TICKS).contains(&tick)));
+
+    let (rows, columns) = columnar(&dir.join("trajectories.bin"));
+    assert_eq!(rows as usize, trajectories.len());
+    let layout: Vec<(&str, u8)> = columns.iter().map(|(name, kind, _)| (name.as_str(), *kind)).collect();
+    assert_eq!(
+        layout,
+        [("tick", 3), ("entity", 1), ("x", 2), ("y", 2), ("state", 0), ("loaded", 0), ("battery", 4)]
+    );
+    let csv_ticks: Vec<u64> = trajectories.iter().map(|row| row[0].parse().unwrap()).collect();
+    assert_eq!(u64_column(&columns[0].2), csv_ticks);
+
+    let (rows, columns) = columnar(&dir.join("events.bin"));
+    assert_eq!(rows as usize, events.len());
+    let layout: Vec<(&str, u8)> = columns.iter().map(|(name, kind, _)| (name.as_str(), *kind)).collect();
+    assert_eq!(layout, [("tick", 3), ("entity", 1), ("event", 0), ("x", 2), ("y", 2)]);
+    assert_eq!(u64_column(&columns[0].2), ticks);
+
+    drop(app);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,74 @@
+//! Un enregistrement rejoué redonne l'état de la simulation à chaque tick.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+use warehouse_sim::components::{GridPosition, Loaded, PlannedPath, Robot, RobotState, State};
+use warehouse_sim::core::{GridPos, SpaceTimeTable};
+use warehouse_sim::plugins::simulation::SimulationSettings;
+use warehouse_sim::systems::replay::{export_replay, Replay, REPLAY_FILE};
+
+mod common;
+
+const TICKS: u64 = 400;
+
+fn run_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("warehouse_sim_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Position, état, chargement et reste du chemin planifié de chaque robot
+type Snapshot = FxHashMap<u32, (GridPos, RobotState, bool, Vec<(GridPos, u64)>)>;
+
+fn snapshot(app: &mut App) -> Snapshot {
+    let world = app.world_mut();
+    let mut robots = world
+        .query_filtered::<(Entity, &GridPosition, &State, &Loaded, &PlannedPath), With<Robot>>();
+    robots
+        .iter(world)
+        .map(|(entity, pos, state, loaded, path)| {
+            (entity.index(), (pos.0, state.0, loaded.0, path.remaining().to_vec()))
+        })
+        .collect()
+}
+
+#[test]
+fn replay_matches_recorded_run() {
+    let dir = run_dir("replay");
+    let settings = SimulationSettings { record: Some(dir.clone()), ..common::fleet(12) };
+    let mut app = common::headless_app(settings, TICKS);
+
+    let mut expected = Vec::new();
+    while app.world().resource::<SpaceTimeTable>().current_tick() < TICKS {
+        app.update();
+        let tick = app.world().resource::<SpaceTimeTable>().current_tick();
+        expected.push((tick, snapshot(&mut app)));
+    }
+    // Écrite à la sortie, sans attendre la destruction de l'app
+    export_replay(app.world_mut());
+
+    let replay = Replay::load(dir.join(REPLAY_FILE)).unwrap();
+    assert_eq!(replay.last_tick, TICKS);
+    assert_eq!(replay.robots.len(), 12);
+
+    for (tick, robots) in expected {
+        for track in &replay.robots {
+            let Some((pos, state, loaded, remaining)) = robots.get(&track.id) else {
+                assert!(track.sample_at(tick).is_none(), "robot {} replayed before its spawn", track.id);
+                continue;
+            };
+            let sample = track.sample_at(tick).unwrap();
+            assert_eq!((sample.pos, sample.state, sample.loaded), (*pos, *state, *loaded), "robot {} at tick {tick}", track.id);
+
+            let replayed = track.path_at(tick).map_or(&[][..], |p| {
+                &p.waypoints[p.waypoints.partition_point(|(_, at)| *at <= tick)..]
+            });
+            assert_eq!(replayed, &remaining[..], "path of robot {} at tick {tick}", track.id);
+        }
+    }
+
+    drop(app);
+    let _ = std::fs::remove_dir_all(&dir);
+}
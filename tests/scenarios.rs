@@ -0,0 +1,788 @@
+//! Scénarios de référence : une app headless sur un petit entrepôt, avec des invariants
+//! vérifiés à chaque tick (pas de collision, réservations uniques et libérées).
+
+use std::path::PathBuf;
+
+use bevy::ecs::message::MessageCursor;
+use bevy::prelude::*;
+use rustc_hash::FxHashSet;
+use warehouse_sim::components::*;
+use warehouse_sim::core::{GridPos, HighwayGraph, SpaceTimeTable, WarehouseGrid, WarehouseLayout, WarehouseZones};
+use warehouse_sim::plugins::simulation::SimulationSettings;
+use warehouse_sim::systems::battery::RobotModels;
+use warehouse_sim::systems::events::{SimEvent, SimEventKind};
+use warehouse_sim::constants::BATTERY_SWAP_SECS;
+use warehouse_sim::systems::faults::{FaultConfig, FaultRequests, ScriptedFault};
+use warehouse_sim::systems::inventory::{Inventory, InventoryConfig};
+use warehouse_sim::systems::metrics::SimMetrics;
+use warehouse_sim::systems::orders::{OrderBook, OrderConfig, OrderSource, OrderSpec};
+use warehouse_sim::systems::pbs::PbsConfig;
+use warehouse_sim::systems::planner::PlannerRegistry;
+use warehouse_sim::systems::spawner::{ChargingConfig, MissionTimings};
+use warehouse_sim::systems::validation::MotionValidation;
+
+mod common;
+
+const PLANNERS: [&str; 5] = ["pbs", "sipp", "cbs", "ecbs", "pibt"];
+
+const WAREHOUSE: &str = "
+    S...............C
+    E..A.RR.A.RR.A..C
+    ...A.RR.A.RR.A...
+    S..A.RR.A.RR.A..C
+    E...............C
+    S..A.RR.A.RR.A...
+    ...A.RR.A.RR.A..C
+    E..A.RR.A.RR.A..C
+    S...............C
+";
+
+fn settings(planner: &str) -> SimulationSettings {
+    let mut settings = SimulationSettings {
+        robots: 0,
+        seed: Some(7),
+        timings: MissionTimings { pickup: 0.5, dropoff: 0.5 },
+        validate: true,
+        ..default()
+    };
+    match planner {
+        "sipp" => {
+            settings.planner = Some("pbs".to_string());
+            settings.pbs.low_level = warehouse_sim::systems::pbs::LowLevelPlanner::Sipp;
+        }
+        name => settings.planner = Some(name.to_string()),
+    }
+    settings
+}
+
+/// Cellules que la mission d'un robot doit réserver dans son état courant ; un robot en
+/// panne ne garde que la cellule qu'il bloque
+fn held(mission: &Mission, state: RobotState, pos: GridPos, breakdown: Option<&Breakdown>) -> [Option<GridPos>; 3] {
+    let blocked = breakdown.map_or(Some(pos), |breakdown| breakdown.blocked);
+    let cells = match mission.phase {
+        MissionPhase::GoingToStorage | MissionPhase::PickingUp => {
+            [Some(mission.storage_target), Some(mission.cargo_target), None]
+        }
+        MissionPhase::GoingToCargo | MissionPhase::DroppingOff => [None, Some(mission.cargo_target), None],
+        MissionPhase::GoingToCharger | MissionPhase::Charging => [None, None, mission.charger],
+    };
+    match state {
+        RobotState::Idle => [None; 3],
+        RobotState::Fault => cells.map(|cell| cell.filter(|&cell| blocked == Some(cell))),
+        _ => cells,
+    }
+}
+
+/// App headless sur un layout ASCII, avancée tick par tick
+struct Scenario {
+    name: String,
+    app: App,
+    cursor: MessageCursor<SimEvent>,
+    events: Vec<SimEvent>,
+}
+
+impl Scenario {
+    fn new(name: &str, layout: &str, settings: SimulationSettings) -> Self {
+        let path = layout_file(name);
+        WarehouseLayout::from_ascii(layout).unwrap().save(&path).unwrap();
+
+        let app = common::headless_app(SimulationSettings { layout: Some(path.clone()), ..settings }, 0);
+        std::fs::remove_file(path).ok();
+
+        let cursor = app.world().resource::<Messages<SimEvent>>().get_cursor();
+        Self { name: name.to_string(), app, cursor, events: Vec::new() }
+    }
+
+    /// Circulation dans les deux sens partout, pour les couloirs d'une seule cellule
+    fn two_way(mut self) -> Self {
+        let grid = self.app.world().resource::<WarehouseGrid>();
+        let highways = HighwayGraph::new(grid.width(), grid.height());
+        self.app.insert_resource(highways);
+        self
+    }
+
+    fn world(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    fn tick(&self) -> u64 {
+        self.app.world().resource::<SpaceTimeTable>().current_tick()
+    }
+
+    /// Robot déjà en route vers `storage`, avec ses cellules réservées
+    fn spawn_on_mission(&mut self, pos: GridPos, storage: GridPos, cargo: GridPos) -> Entity {
+        let tick = self.tick();
+        let world = self.world();
+        let mut zones = world.resource_mut::<WarehouseZones>();
+        let (mut storages, mut cargos) = (zones.reserved_storage(), zones.reserved_cargo());
+        storages.push(storage);
+        cargos.push(cargo);
+        let chargers = zones.reserved_chargers();
+        zones.set_reservations(&storages, &cargos, &chargers);
+
+        world
+            .spawn((
+                Robot,
+                GridPosition(pos),
+                Destination(storage),
+                State(RobotState::Moving),
+                Mission::new(storage, cargo, tick),
+            ))
+            .id()
+    }
+
+    /// Avance d'un tick et vérifie les invariants
+    fn step(&mut self) {
+        let before = self.tick();
+        while self.tick() == before {
+            self.app.update();
+        }
+
+        let messages = self.app.world().resource::<Messages<SimEvent>>();
+        self.events.extend(self.cursor.read(messages).copied());
+        self.check_motion();
+        self.check_reservations();
+    }
+
+    fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.step();
+        }
+    }
+
+    fn check_motion(&self) {
+        let validation = self.app.world().resource::<MotionValidation>();
+        if let Some(violation) = validation.last {
+            panic!("{}: {violation}", self.name);
+        }
+    }
+
+    /// Chaque cellule réservée l'est par un seul robot, et rien d'autre n'est réservé
+    fn check_reservations(&mut self) {
+        let tick = self.tick();
+        let name = self.name.clone();
+        let world = self.world();
+        let mut held_cells: [Vec<GridPos>; 3] = Default::default();
+        let mut robots =
+            world.query::<(&Mission, &State, &GridPosition, &PlannedPath, Option<&Breakdown>, Option<&NextTask>)>();
+        for (mission, state, pos, path, breakdown, next) in robots.iter(world) {
+            // Une fois arrêté, le robot en panne est sur la cellule qu'il bloque
+            if let Some(blocked) = breakdown.and_then(|b| b.blocked).filter(|_| path.is_complete()) {
+                assert_eq!(blocked, pos.0, "{name}: broken robot away from its blocked cell at tick {tick}");
+            }
+            let cells = held(mission, state.0, pos.0, breakdown);
+            for (cells, cell) in held_cells.iter_mut().zip(cells) {
+                cells.extend(cell);
+            }
+            // Tâche affectée à l'avance : storage et cargo déjà réservés
+            if let Some(next) = next {
+                held_cells[0].push(next.storage);
+                held_cells[1].push(next.cargo);
+            }
+        }
+
+        let zones = world.resource::<WarehouseZones>();
+        let reserved = [zones.reserved_storage(), zones.reserved_cargo(), zones.reserved_chargers()];
+        for ((mut cells, reserved), kind) in held_cells.into_iter().zip(reserved).zip(["storage", "cargo", "charger"]) {
+            let unique: FxHashSet<GridPos> = cells.iter().copied().collect();
+            assert_eq!(unique.len(), cells.len(), "{name}: {kind} reserved twice at tick {tick}: {cells:?}");
+            cells.sort_by_key(|p| (p.x, p.y));
+            assert_eq!(cells, reserved, "{name}: {kind} reservations out of sync at tick {tick}");
+        }
+    }
+
+    /// Chaque ligne de commande encore ouverte est en attente, portée par un robot ou
+    /// affectée à l'avance, une seule fois
+    fn check_order_lines(&mut self) {
+        let name = self.name.clone();
+        let world = self.world();
+        let mut robots = world.query::<(&Mission, &State, Option<&NextTask>)>();
+        let mut carried = 0;
+        for (mission, state, next) in robots.iter(world) {
+            let active = match mission.phase {
+                MissionPhase::GoingToCargo | MissionPhase::DroppingOff => true,
+                MissionPhase::GoingToStorage | MissionPhase::PickingUp => {
+                    !matches!(state.0, RobotState::Idle | RobotState::Fault)
+                }
+                _ => false,
+            };
+            carried += (active && mission.order.is_some()) as usize;
+            carried += next.is_some_and(|next| next.order.is_some()) as usize;
+        }
+        let book = world.resource::<OrderBook>();
+        let remaining: u32 = book.open.iter().map(|o| o.remaining).sum();
+        assert_eq!(remaining as usize, book.pending.len() + carried, "{name}: order lines lost");
+    }
+
+    fn count(&self, entity: Entity, kind: SimEventKind) -> usize {
+        self.events.iter().filter(|e| e.entity == entity && e.kind == kind).count()
+    }
+
+    fn robots(&mut self) -> Vec<Entity> {
+        let world = self.world();
+        let mut robots: Vec<Entity> = world.query_filtered::<Entity, With<Robot>>().iter(world).collect();
+        robots.sort_by_key(|e| e.index());
+        robots
+    }
+
+    fn position(&self, entity: Entity) -> GridPos {
+        self.app.world().get::<GridPosition>(entity).unwrap().0
+    }
+
+    fn state(&self, entity: Entity) -> RobotState {
+        self.app.world().get::<State>(entity).unwrap().0
+    }
+}
+
+fn layout_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("warehouse_sim_scenario_{name}_{}.ron", std::process::id()))
+}
+
+/// Flotte complète : chaque robot enchaîne les missions sans collision
+#[test]
+fn warehouse_fleet() {
+    for planner in PLANNERS {
+        let mut scenario = Scenario::new(
+            &format!("fleet_{planner}"),
+            WAREHOUSE,
+            SimulationSettings { robots: 6, ..settings(planner) },
+        );
+        scenario.run(2400);
+
+        let robots = scenario.robots();
+        assert_eq!(robots.len(), 6, "{planner}");
+        for robot in robots {
+            let missions = scenario.count(robot, SimEventKind::DropoffDone);
+            assert!(missions >= 3, "{planner}: robot #{} finished {missions} missions", robot.index());
+        }
+    }
+}
+
+/// Batteries basses : passage au chargeur, recharge, puis reprise des missions
+#[test]
+fn robots_recharge_between_missions() {
+    for planner in PLANNERS {
+        let mut settings = SimulationSettings { robots: 4, ..settings(planner) };
+        settings.charging = ChargingConfig { threshold: 0.999, resume_level: 1.0 };
+        // Petite batterie et chargeur rapide : une recharge après chaque mission
+        let model = BatteryModel { capacity_wh: 10.0, wh_per_cell: 0.001, standby_w: 1.0, charge_power_w: 1800.0, ..default() };
+        settings.robot_models = RobotModels { models: vec![model] };
+        let mut scenario = Scenario::new(&format!("charging_{planner}"), WAREHOUSE, settings);
+        scenario.run(2400);
+
+        for robot in scenario.robots() {
+            let charges = scenario.count(robot, SimEventKind::ChargingDone);
+            let missions = scenario.count(robot, SimEventKind::DropoffDone);
+            assert!(charges >= 1, "{planner}: robot #{} never charged", robot.index());
+            assert!(missions >= 2, "{planner}: robot #{} finished {missions} missions", robot.index());
+        }
+    }
+}
+
+/// Batterie vide en route : panne sur place, réservations rendues, les autres continuent
+#[test]
+fn dead_battery_blocks_its_cell() {
+    for planner in PLANNERS {
+        let mut scenario = Scenario::new(
+            &format!("dead_battery_{planner}"),
+            WAREHOUSE,
+            SimulationSettings { robots: 4, ..settings(planner) },
+        );
+        scenario.run(300);
+
+        let robots = scenario.robots();
+        let dead = robots[0];
+        scenario.world().get_mut::<Battery>(dead).unwrap().0 = 1e-6;
+        // Le robot finit le pas engagé, au plus jusqu'à la replanification suivante
+        scenario.run(10);
+        assert_eq!(scenario.state(dead), RobotState::Fault, "{planner}");
+        assert_eq!(scenario.count(dead, SimEventKind::BatteryDepleted), 1, "{planner}");
+        // La panne a pu survenir juste avant la fin : on laisse passer une replanification
+        let replan_interval = scenario.world().resource::<PbsConfig>().replan_interval;
+        scenario.run(replan_interval);
+
+        let parked = scenario.position(dead);
+        let before: Vec<usize> = robots.iter().map(|&r| scenario.count(r, SimEventKind::DropoffDone)).collect();
+        scenario.run(1200);
+        assert_eq!(scenario.position(dead), parked, "{planner}: faulted robot moved");
+        for (i, &robot) in robots.iter().enumerate().skip(1) {
+            assert!(
+                scenario.count(robot, SimEventKind::DropoffDone) > before[i],
+                "{planner}: robot #{} stopped working",
+                robot.index()
+            );
+        }
+    }
+}
+
+/// Batterie vide : panne réparable, la réparation remplace la batterie et le robot reprend
+#[test]
+fn depleted_battery_is_swapped_at_repair() {
+    let mut scenario = Scenario::new("battery_swap", WAREHOUSE, SimulationSettings { robots: 2, ..settings("pbs") });
+    scenario.run(300);
+    let dead = scenario.robots()[0];
+    scenario.world().get_mut::<Battery>(dead).unwrap().0 = 1e-6;
+    scenario.run(10);
+    let breakdown = *scenario.world().get::<Breakdown>(dead).unwrap();
+    assert_eq!(breakdown.cause, FaultCause::BatteryDepleted);
+    assert_eq!(breakdown.repair_at, breakdown.since + (BATTERY_SWAP_SECS * 60.0) as u64);
+
+    // Avance la fin du dépannage plutôt que de simuler toute sa durée
+    let tick = scenario.tick();
+    scenario.world().get_mut::<Breakdown>(dead).unwrap().repair_at = tick + 1;
+    scenario.run(2);
+    assert_eq!(scenario.count(dead, SimEventKind::Repaired), 1);
+    assert!(scenario.world().get::<Breakdown>(dead).is_none());
+    assert!(scenario.world().get::<Battery>(dead).unwrap().0 > 0.99);
+    let missions = scenario.count(dead, SimEventKind::DropoffDone);
+    scenario.run(1200);
+    assert!(scenario.count(dead, SimEventKind::DropoffDone) > missions, "not back to work");
+}
+
+/// MTBF : l'instant de panne est tiré une fois par robot en service, puis de nouveau après
+/// la réparation
+#[test]
+fn random_failures_are_drawn_once_per_robot() {
+    let mut settings = SimulationSettings { robots: 2, ..settings("pbs") };
+    settings.faults = FaultConfig { mtbf: Some(20.0), mttr: 1.0, ..default() };
+    let mut scenario = Scenario::new("failure_draws", WAREHOUSE, settings);
+    scenario.run(200);
+    let robot = scenario.robots()[0];
+    let failure = |scenario: &mut Scenario| scenario.world().get::<NextFailure>(robot).map(|f| f.0);
+
+    // Tant qu'il n'est pas en panne, le tirage ne change pas
+    while scenario.world().get::<Breakdown>(robot).is_some() {
+        scenario.run(1);
+    }
+    scenario.run(1);
+    let at = failure(&mut scenario).unwrap();
+    while scenario.tick() < at {
+        assert_eq!(failure(&mut scenario), Some(at));
+        assert!(scenario.world().get::<Breakdown>(robot).is_none());
+        scenario.run(1);
+    }
+    let breakdown = *scenario.world().get::<Breakdown>(robot).unwrap();
+    assert_eq!((breakdown.cause, breakdown.since), (FaultCause::Random, at));
+
+    scenario.run(breakdown.repair_at - scenario.tick() + 1);
+    assert!(failure(&mut scenario).is_some_and(|next| next > breakdown.repair_at));
+}
+
+/// Panne scriptée : arrêt sur place pendant la réparation, puis reprise des missions
+#[test]
+fn scripted_breakdown_is_repaired() {
+    for planner in PLANNERS {
+        let mut scenario = Scenario::new(
+            &format!("breakdown_{planner}"),
+            WAREHOUSE,
+            SimulationSettings { robots: 4, ..settings(planner) },
+        );
+        scenario.run(300);
+
+        let robots = scenario.robots();
+        let broken = robots[0];
+        let tick = scenario.tick() + 1;
+        let number = scenario.world().get::<RobotId>(broken).unwrap().0;
+        scenario.world().resource_mut::<FaultConfig>().script =
+            vec![ScriptedFault { tick, robot: number, repair_secs: Some(10.0) }];
+        scenario.run(10);
+        assert_eq!(scenario.state(broken), RobotState::Fault, "{planner}");
+        assert_eq!(scenario.count(broken, SimEventKind::Breakdown), 1, "{planner}");
+        let breakdown = *scenario.world().get::<Breakdown>(broken).unwrap();
+        assert_eq!((breakdown.cause, breakdown.since), (FaultCause::Scripted, tick), "{planner}");
+        assert_eq!(breakdown.repair_at, tick + 600, "{planner}");
+
+        // Immobile jusqu'à la réparation, pendant que les autres travaillent
+        let parked = scenario.position(broken);
+        let before: Vec<usize> = robots.iter().map(|&r| scenario.count(r, SimEventKind::DropoffDone)).collect();
+        scenario.run(breakdown.repair_at - scenario.tick() - 1);
+        assert_eq!(scenario.position(broken), parked, "{planner}: broken robot moved");
+        assert_eq!(scenario.state(broken), RobotState::Fault, "{planner}: repaired too early");
+        assert!(robots[1..].iter().zip(&before[1..]).any(|(&r, &n)| scenario.count(r, SimEventKind::DropoffDone) > n));
+
+        scenario.run(1);
+        assert_eq!(scenario.count(broken, SimEventKind::Repaired), 1, "{planner}");
+        assert!(scenario.world().get::<Breakdown>(broken).is_none(), "{planner}");
+        let missions = scenario.count(broken, SimEventKind::DropoffDone);
+        scenario.run(1200);
+        assert!(scenario.count(broken, SimEventKind::DropoffDone) > missions, "{planner}: not back to work");
+    }
+}
+
+/// Pannes aléatoires : aucune collision ni réservation orpheline, et la flotte produit encore
+#[test]
+fn random_breakdowns_keep_invariants() {
+    for planner in PLANNERS {
+        let mut settings = SimulationSettings { robots: 6, ..settings(planner) };
+        settings.faults = FaultConfig { mtbf: Some(20.0), mttr: 5.0, ..default() };
+        let mut scenario = Scenario::new(&format!("random_faults_{planner}"), WAREHOUSE, settings);
+        scenario.run(1800);
+
+        let robots = scenario.robots();
+        let breakdowns: usize = robots.iter().map(|&r| scenario.count(r, SimEventKind::Breakdown)).sum();
+        let repairs: usize = robots.iter().map(|&r| scenario.count(r, SimEventKind::Repaired)).sum();
+        let missions: usize = robots.iter().map(|&r| scenario.count(r, SimEventKind::DropoffDone)).sum();
+        let metrics = scenario.world().resource::<SimMetrics>();
+        assert_eq!(metrics.breakdowns, breakdowns as u64, "{planner}");
+        assert!(breakdowns >= 3 && repairs >= 1, "{planner}: {breakdowns} breakdowns, {repairs} repairs");
+        assert!(metrics.fault_ticks > 0 && metrics.availability() < 1.0, "{planner}");
+        assert!(missions >= 6, "{planner}: only {missions} missions");
+    }
+}
+
+/// Panne et réparation demandées depuis l'interface
+#[test]
+fn manual_breakdown_and_repair() {
+    let mut scenario = Scenario::new("manual_fault", WAREHOUSE, SimulationSettings { robots: 2, ..settings("pbs") });
+    scenario.run(200);
+    let robot = scenario.robots()[1];
+
+    scenario.world().resource_mut::<FaultRequests>().breakdowns.push(robot);
+    scenario.run(1);
+    assert_eq!(scenario.world().get::<Breakdown>(robot).unwrap().cause, FaultCause::Manual);
+    assert_eq!(scenario.state(robot), RobotState::Fault);
+
+    scenario.run(20);
+    scenario.world().resource_mut::<FaultRequests>().repairs.push(robot);
+    scenario.run(1);
+    assert_eq!(scenario.count(robot, SimEventKind::Repaired), 1);
+    assert_ne!(scenario.state(robot), RobotState::Fault);
+}
+
+/// Commandes d'un fichier : chaque ligne part d'un storage de sa référence vers le quai de
+/// sa commande, et chaque commande est close une fois toutes ses lignes déposées
+#[test]
+fn scripted_orders_go_to_their_dock() {
+    let order = |tick, lines: &[u32], dock| OrderSpec { tick, lines: lines.to_vec(), dock: Some(dock) };
+    for planner in PLANNERS {
+        let mut settings = SimulationSettings { robots: 3, ..settings(planner) };
+        settings.orders = OrderConfig {
+            source: OrderSource::script(vec![
+                order(30, &[0, 5], 0),
+                order(30, &[1], 2),
+                order(600, &[3, 3, 3], 1),
+            ]),
+            skus: Some(6),
+            ..default()
+        };
+        let mut scenario = Scenario::new(&format!("orders_{planner}"), WAREHOUSE, settings);
+        scenario.run(2400);
+
+        let inventory = scenario.world().resource::<Inventory>();
+        let stocked: Vec<GridPos> =
+            [0, 5, 1, 3].iter().flat_map(|&sku| inventory.slots_of(sku).map(|slot| slot.cell)).collect();
+        let zones = scenario.world().resource::<WarehouseZones>();
+        let docks = zones.docks();
+        assert_eq!(docks.len(), 3);
+        let mut delivered = [0; 3];
+        for event in &scenario.events {
+            match event.kind {
+                SimEventKind::ArrivedAtStorage => assert!(stocked.contains(&event.cell), "{planner}: picked at {:?}", event.cell),
+                SimEventKind::DropoffDone => delivered[docks.iter().position(|d| d.contains(&event.cell)).unwrap()] += 1,
+                _ => {}
+            }
+        }
+        assert_eq!(delivered, [2, 3, 1], "{planner}");
+
+        let metrics = scenario.world().resource::<SimMetrics>();
+        assert_eq!((metrics.orders_received, metrics.order_lead_times.len()), (3, 3), "{planner}");
+        let book = scenario.world().resource::<OrderBook>();
+        assert!(book.pending.is_empty() && book.open.is_empty(), "{planner}: {book:?}");
+        for robot in scenario.robots() {
+            assert_eq!(scenario.state(robot), RobotState::Idle, "{planner}");
+        }
+    }
+}
+
+/// Stock d'une unité par emplacement : chaque prélèvement vide son storage, les lignes en
+/// trop sont closes en rupture et aucun robot ne repasse par un storage vide
+#[test]
+fn stockouts_close_orders_short() {
+    let mut settings = SimulationSettings { robots: 3, ..settings("pbs") };
+    settings.orders = OrderConfig {
+        source: OrderSource::script(vec![OrderSpec { tick: 30, lines: vec![2; 12], dock: Some(0) }]),
+        skus: Some(6),
+        ..default()
+    };
+    settings.inventory = InventoryConfig { capacity: 1, ..default() };
+    let mut scenario = Scenario::new("stockouts", WAREHOUSE, settings);
+    let slots = scenario.world().resource::<Inventory>().slots_of(2).count();
+    assert!(slots < 12);
+    scenario.run(2400);
+
+    let picked: Vec<GridPos> =
+        scenario.events.iter().filter(|e| e.kind == SimEventKind::PickupDone).map(|e| e.cell).collect();
+    let distinct: FxHashSet<GridPos> = picked.iter().copied().collect();
+    assert_eq!((picked.len(), distinct.len()), (slots, slots));
+
+    let world = scenario.world();
+    assert_eq!(world.resource::<Inventory>().stock(2), 0);
+    let metrics = world.resource::<SimMetrics>();
+    assert_eq!(metrics.stockouts as usize, 12 - slots);
+    assert_eq!(metrics.order_lead_times.len(), 1);
+    let book = world.resource::<OrderBook>();
+    assert!(book.pending.is_empty() && book.open.is_empty(), "{book:?}");
+}
+
+/// Sans flux de commandes, la rupture est détectée au prélèvement de la dernière unité de
+/// chaque référence, une fois par référence
+#[test]
+fn continuous_missions_detect_stockouts() {
+    let mut settings = SimulationSettings { robots: 3, ..settings("pbs") };
+    settings.orders.skus = Some(3);
+    settings.inventory = InventoryConfig { capacity: 1, ..default() };
+    let mut scenario = Scenario::new("continuous_stockouts", WAREHOUSE, settings);
+    let slots = scenario.world().resource::<Inventory>().slots().len();
+    scenario.run(3000);
+
+    let picked: FxHashSet<GridPos> =
+        scenario.events.iter().filter(|e| e.kind == SimEventKind::PickupDone).map(|e| e.cell).collect();
+    assert_eq!(picked.len(), slots);
+    let world = scenario.world();
+    assert_eq!(world.resource::<Inventory>().fill(), 0.0);
+    assert_eq!(world.resource::<SimMetrics>().stockouts, 3);
+}
+
+/// Emplacement vidé entre l'attribution et le prélèvement : la ligne est close en rupture,
+/// le robot repart à vide et rien n'est livré
+#[test]
+fn failed_picks_close_the_line_short() {
+    let mut settings = settings("pbs");
+    settings.orders = OrderConfig {
+        source: OrderSource::script(vec![OrderSpec { tick: 1, lines: vec![2], dock: Some(0) }]),
+        skus: Some(6),
+        ..default()
+    };
+    let mut scenario = Scenario::new("failed_pick", WAREHOUSE, settings);
+    scenario.run(2);
+
+    let line = scenario.world().resource_mut::<OrderBook>().pending.pop_front().unwrap();
+    let mut inventory = scenario.world().resource_mut::<Inventory>();
+    let storage = inventory.stocked_cells(2)[0];
+    let face = inventory.slots_of(2).find(|slot| slot.cell == storage).unwrap().face;
+    inventory.slot_mut(storage, face).unwrap().quantity = 0;
+    let cargo = scenario.world().resource::<WarehouseZones>().docks()[0][0];
+    let robot = scenario.spawn_on_mission(GridPos::new(0, 0), storage, cargo);
+    scenario.world().get_mut::<Mission>(robot).unwrap().order = Some(line);
+    scenario.run(600);
+
+    assert_eq!(scenario.count(robot, SimEventKind::ArrivedAtStorage), 1);
+    assert_eq!(scenario.count(robot, SimEventKind::PickupDone), 0);
+    assert_eq!(scenario.count(robot, SimEventKind::DropoffDone), 0);
+    assert_eq!(scenario.state(robot), RobotState::Idle);
+    let world = scenario.world();
+    assert!(!world.get::<Loaded>(robot).unwrap().0);
+    let metrics = world.resource::<SimMetrics>();
+    assert_eq!((metrics.stockouts, metrics.completed_missions), (1, 0));
+    assert_eq!(metrics.order_lead_times.len(), 1);
+    let book = world.resource::<OrderBook>();
+    assert!(book.pending.is_empty() && book.open.is_empty(), "{book:?}");
+}
+
+/// Commandes de Poisson et pannes : aucune ligne perdue ni servie deux fois
+#[test]
+fn order_lines_survive_breakdowns() {
+    for planner in ["pbs", "pibt"] {
+        let mut settings = SimulationSettings { robots: 4, ..settings(planner) };
+        settings.orders = OrderConfig {
+            source: OrderSource::Poisson { rate: 900.0, max_lines: 2 },
+            skus: Some(6),
+            sku_skew: 1.5,
+        };
+        settings.faults = FaultConfig { mtbf: Some(20.0), mttr: 4.0, ..default() };
+        let mut scenario = Scenario::new(&format!("orders_faults_{planner}"), WAREHOUSE, settings);
+
+        for _ in 0..12 {
+            scenario.run(250);
+            scenario.check_order_lines();
+        }
+
+        let metrics = scenario.world().resource::<SimMetrics>();
+        assert!(metrics.breakdowns > 0, "{planner}");
+        assert!(metrics.orders_received >= 5, "{planner}: {} orders", metrics.orders_received);
+        assert!(!metrics.order_lead_times.is_empty(), "{planner}: no order completed");
+    }
+}
+
+/// Affectation groupée par chaque allocateur, avec commandes et pannes : les robots qui
+/// terminent leur dépôt repartent aussitôt sur la tâche affectée à l'avance, et aucune ligne
+/// n'est perdue
+#[test]
+fn allocators_serve_orders_through_breakdowns() {
+    for allocator in ["nearest", "hungarian", "auction"] {
+        let mut settings = SimulationSettings { robots: 4, allocator: Some(allocator.to_string()), ..settings("pbs") };
+        settings.allocation.interval = 20;
+        settings.orders = OrderConfig {
+            source: OrderSource::Poisson { rate: 3600.0, max_lines: 2 },
+            skus: Some(6),
+            sku_skew: 1.5,
+        };
+        settings.faults = FaultConfig { mtbf: Some(30.0), mttr: 4.0, ..default() };
+        let mut scenario = Scenario::new(&format!("allocation_{allocator}"), WAREHOUSE, settings);
+
+        for _ in 0..12 {
+            scenario.run(250);
+            scenario.check_order_lines();
+        }
+
+        // Dépôt terminé et nouvelle mission au même tick : tâche affectée à l'avance
+        let chained = scenario
+            .events
+            .windows(2)
+            .filter(|w| {
+                w[0].kind == SimEventKind::DropoffDone
+                    && w[1].kind == SimEventKind::MissionAssigned
+                    && (w[0].entity, w[0].tick) == (w[1].entity, w[1].tick)
+            })
+            .count();
+        assert!(chained > 0, "{allocator}: no task assigned ahead");
+
+        let metrics = scenario.world().resource::<SimMetrics>();
+        assert!(metrics.breakdowns > 0, "{allocator}");
+        assert!(metrics.assignments >= 10, "{allocator}: {} assignments", metrics.assignments);
+        assert!(!metrics.order_lead_times.is_empty(), "{allocator}: no order completed");
+    }
+}
+
+/// Sans flux de commandes, l'allocateur répartit les storages libres entre les robots
+#[test]
+fn allocators_replace_random_dispatch() {
+    for allocator in ["nearest", "hungarian", "auction"] {
+        let settings = SimulationSettings { robots: 5, allocator: Some(allocator.to_string()), ..settings("pibt") };
+        let mut scenario = Scenario::new(&format!("allocation_continuous_{allocator}"), WAREHOUSE, settings);
+        scenario.run(1500);
+
+        // Toutes les missions viennent de l'allocateur ; les dernières tâches peuvent attendre
+        let assigned = scenario.events.iter().filter(|e| e.kind == SimEventKind::MissionAssigned).count() as u64;
+        let metrics = scenario.world().resource::<SimMetrics>();
+        assert!((assigned..=assigned + 5).contains(&metrics.assignments), "{allocator}: {assigned} missions assigned");
+        assert!(metrics.completed_missions >= 10, "{allocator}: {} missions", metrics.completed_missions);
+        assert!(metrics.mean_assignment_steps().is_some(), "{allocator}");
+    }
+}
+
+/// Chaque solveur enregistré passe par les scénarios de référence
+#[test]
+fn golden_scenarios_cover_every_planner() {
+    for name in PlannerRegistry::default().names() {
+        assert!(PLANNERS.contains(&name), "planner `{name}` is not covered");
+    }
+}
+
+/// Chaque robot a livré au moins une fois, sauf pour les solveurs de `stuck`, connus pour
+/// rester bloqués sans collision : ceux-là ne doivent rien livrer, et un solveur qui
+/// progresse doit sortir de la liste
+fn assert_delivered(scenario: &Scenario, planner: &str, robots: &[Entity], stuck: &[&str]) {
+    for &robot in robots {
+        let delivered = scenario.count(robot, SimEventKind::DropoffDone);
+        if stuck.contains(&planner) {
+            assert_eq!(delivered, 0, "{planner}: robot #{} is no longer stuck", robot.index());
+        } else {
+            assert!(delivered >= 1, "{planner}: robot #{} never delivered", robot.index());
+        }
+    }
+}
+
+/// Deux robots face à face dans un couloir d'une cellule, avec une seule niche pour se croiser.
+/// Tous les planificateurs restent sans collision ; seuls ceux qui raisonnent sur toute la
+/// traversée (CBS, ECBS) la mènent à bien, les autres s'arrêtent à l'horizon glissant.
+#[test]
+fn corridor_head_on() {
+    for planner in PLANNERS {
+        let mut scenario = Scenario::new(
+            &format!("corridor_{planner}"),
+            "
+            C@@@@.@@@@C
+            A.........A
+            @@@@@S@@@@@
+            ",
+            settings(planner),
+        )
+        .two_way();
+        let east = scenario.spawn_on_mission(GridPos::new(1, 1), GridPos::new(10, 1), GridPos::new(10, 0));
+        let west = scenario.spawn_on_mission(GridPos::new(9, 1), GridPos::new(0, 1), GridPos::new(0, 0));
+        scenario.run(400);
+        assert_delivered(&scenario, planner, &[east, west], &["pbs", "sipp", "pibt"]);
+    }
+}
+
+/// Deux robots voisins dans une allée étroite doivent échanger leurs côtés, par la niche
+/// du haut. Seul PIBT, qui replanifie à chaque tick, y parvient ; les autres attendent sans
+/// collision.
+#[test]
+fn narrow_aisle_swap() {
+    for planner in PLANNERS {
+        let mut scenario = Scenario::new(
+            &format!("aisle_{planner}"),
+            "
+            @@@.@@@
+            CA...AC
+            @@@S@@@
+            ",
+            settings(planner),
+        )
+        .two_way();
+        let right = scenario.spawn_on_mission(GridPos::new(2, 1), GridPos::new(5, 1), GridPos::new(6, 1));
+        let left = scenario.spawn_on_mission(GridPos::new(3, 1), GridPos::new(1, 1), GridPos::new(0, 1));
+        scenario.run(400);
+        assert_delivered(&scenario, planner, &[right, left], &["pbs", "sipp", "cbs", "ecbs"]);
+    }
+}
+
+/// But occupé par un robot en panne : le robot s'en approche au plus près au lieu de rester
+/// sans chemin
+#[test]
+fn blocked_goal_cell() {
+    for planner in PLANNERS {
+        let mut scenario = Scenario::new(
+            &format!("blocked_goal_{planner}"),
+            "
+            A.........C
+            ..........S
+            ",
+            settings(planner),
+        )
+        .two_way();
+        let goal = GridPos::new(0, 0);
+        scenario.world().spawn((
+            Robot,
+            GridPosition(goal),
+            Destination(goal),
+            State(RobotState::Fault),
+            Battery(0.0),
+        ));
+        let robot = scenario.spawn_on_mission(GridPos::new(10, 1), goal, GridPos::new(10, 0));
+        scenario.run(120);
+
+        assert_eq!(scenario.position(robot).manhattan_distance(&goal), 1, "{planner}");
+        let metrics = scenario.world().resource::<SimMetrics>();
+        assert_eq!(metrics.planner_failures, 0, "{planner}: robot left without a path");
+    }
+}
+
+/// Robot resté sans mission faute de storage libre : il réessaie à chaque tick et repart dès
+/// que la cellule se libère
+#[test]
+fn idle_robot_retries_for_a_mission() {
+    let mut scenario = Scenario::new(
+        "idle_retry",
+        "
+        A.........C
+        S.........C
+        ",
+        settings("pbs"),
+    )
+    .two_way();
+    let busy = scenario.spawn_on_mission(GridPos::new(5, 0), GridPos::new(0, 0), GridPos::new(10, 0));
+    let tick = scenario.tick();
+    let waiting = GridPos::new(5, 1);
+    let idle = scenario
+        .world()
+        .spawn((Robot, GridPosition(waiting), Destination(waiting), State(RobotState::Idle), Mission::new(waiting, waiting, tick)))
+        .id();
+    scenario.run(300);
+
+    assert!(scenario.count(busy, SimEventKind::PickupDone) >= 1);
+    assert!(scenario.count(idle, SimEventKind::MissionAssigned) >= 1, "idle robot never got a mission");
+    assert!(scenario.count(idle, SimEventKind::DropoffDone) >= 1, "idle robot never finished its mission");
+}
@@ -0,0 +1,170 @@
+//! Un snapshot rechargé reprend la simulation exactement là où elle s'était arrêtée.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use warehouse_sim::components::*;
+use warehouse_sim::core::{SimRng, SpaceTimeTable};
+use warehouse_sim::plugins::simulation::SimulationSettings;
+use warehouse_sim::systems::faults::{FaultConfig, ScriptedFault};
+use warehouse_sim::systems::snapshot::SimulationSnapshot;
+
+mod common;
+
+const SPLIT: u64 = 300;
+const TICKS: u64 = 600;
+
+fn settings(planner: &str) -> SimulationSettings {
+    SimulationSettings { seed: Some(11), planner: Some(planner.to_string()), ..common::fleet(30) }
+}
+
+fn app(settings: SimulationSettings) -> App {
+    common::headless_app(settings, TICKS)
+}
+
+/// Fait avancer jusqu'à `end` ; l'état des robots est journalisé trié, l'ordre d'itération
+/// de la requête n'étant pas conservé par une reprise
+fn run_until(app: &mut App, end: u64, log: &mut String) {
+    loop {
+        let before = app.world().resource::<SpaceTimeTable>().current_tick();
+        if before >= end {
+            break;
+        }
+        app.update();
+
+        let world = app.world_mut();
+        let tick = world.resource::<SpaceTimeTable>().current_tick();
+        // La première mise à jour d'une app n'avance pas l'horloge
+        if tick == before {
+            continue;
+        }
+        let word_pos = world.resource::<SimRng>().word_pos();
+        writeln!(log, "tick {tick} rng {word_pos}").unwrap();
+        let mut robots = world.query_filtered::<(
+            &RobotId,
+            &GridPosition,
+            &State,
+            &Mission,
+            &Battery,
+            &PlannedPath,
+            Option<&ActionTimer>,
+        ), With<Robot>>();
+        let mut rows: Vec<String> = robots
+            .iter(world)
+            .map(|(id, pos, state, mission, battery, path, timer)| {
+                format!(
+                    "#{} {:?} {:?} {:?} {:?} {:?} {:08x} {:?} {:?}",
+                    id.0,
+                    pos.0,
+                    state.0,
+                    mission.phase,
+                    mission.storage_target,
+                    mission.cargo_target,
+                    battery.0.to_bits(),
+                    path.remaining(),
+                    timer.map(|t| t.remaining),
+                )
+            })
+            .collect();
+        rows.sort_unstable();
+        for row in rows {
+            writeln!(log, "{row}").unwrap();
+        }
+    }
+}
+
+fn snapshot_path(planner: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("warehouse_sim_snapshot_{planner}_{}.ron", std::process::id()))
+}
+
+fn resume_matches_uninterrupted_run(planner: &str) {
+    let mut straight = app(settings(planner));
+    run_until(&mut straight, SPLIT, &mut String::new());
+    let mut expected = String::new();
+    run_until(&mut straight, TICKS, &mut expected);
+
+    let mut first = app(settings(planner));
+    run_until(&mut first, SPLIT, &mut String::new());
+    let snapshot = SimulationSnapshot::capture(first.world_mut()).unwrap();
+    let path = snapshot_path(planner);
+    snapshot.save(&path).unwrap();
+    assert_eq!(SimulationSnapshot::load(&path).unwrap(), snapshot);
+
+    let mut resumed = app(SimulationSettings {
+        resume: Some(path.clone()),
+        seed: None,
+        ..settings(planner)
+    });
+    assert_eq!(resumed.world().resource::<SpaceTimeTable>().current_tick(), SPLIT);
+    let mut actual = String::new();
+    run_until(&mut resumed, TICKS, &mut actual);
+    let _ = std::fs::remove_file(&path);
+
+    assert!(!expected.is_empty());
+    if let Some((line, (e, a))) = expected.lines().zip(actual.lines()).enumerate().find(|(_, (e, a))| e != a) {
+        panic!("{planner}: resumed run diverged at line {line}\n expected {e}\n actual   {a}");
+    }
+    assert_eq!(expected.len(), actual.len());
+}
+
+#[test]
+fn resume_pbs() {
+    resume_matches_uninterrupted_run("pbs");
+}
+
+#[test]
+fn resume_pibt() {
+    resume_matches_uninterrupted_run("pibt");
+}
+
+#[test]
+fn resume_cbs() {
+    resume_matches_uninterrupted_run("cbs");
+}
+
+/// La position du générateur tient sur plus de 64 bits
+#[test]
+fn rng_position_is_not_truncated() {
+    let mut first = app(settings("pbs"));
+    first.update();
+    let word_pos = (1u128 << 66) + 5;
+    first.world_mut().insert_resource(SimRng::resume(11, word_pos));
+    let snapshot = SimulationSnapshot::capture(first.world_mut()).unwrap();
+    assert_eq!(snapshot.rng.word_pos, (4, 5));
+
+    let path = snapshot_path("rng");
+    snapshot.save(&path).unwrap();
+    let resumed = app(SimulationSettings { resume: Some(path.clone()), seed: None, ..settings("pbs") });
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(resumed.world().resource::<SimRng>().word_pos(), word_pos);
+}
+
+/// Une panne scriptée après une reprise vise le même robot, bien que son index d'entité change
+#[test]
+fn scripted_faults_follow_the_robot_across_a_resume() {
+    let mut sim = app(settings("pbs"));
+    run_until(&mut sim, SPLIT, &mut String::new());
+    let snapshot = SimulationSnapshot::capture(sim.world_mut()).unwrap();
+    // Reprise dans le même monde : les index libérés sont réutilisés dans un autre ordre
+    snapshot.restore(sim.world_mut()).unwrap();
+
+    let world = sim.world_mut();
+    let mut robots = world.query::<(Entity, &RobotId, &GridPosition)>();
+    let (moved, number) = robots
+        .iter(world)
+        .find_map(|(entity, id, pos)| {
+            let before = snapshot.robots.iter().find(|r| r.number == Some(id.0)).unwrap();
+            assert_eq!(before.pos, pos.0);
+            (before.id != entity.index()).then_some((entity, id.0))
+        })
+        .expect("every robot kept its entity index");
+    assert_ne!(moved.index(), number);
+
+    world.resource_mut::<FaultConfig>().script =
+        vec![ScriptedFault { tick: SPLIT + 1, robot: number, repair_secs: Some(10.0) }];
+    run_until(&mut sim, SPLIT + 2, &mut String::new());
+    let world = sim.world_mut();
+    let mut broken = world.query_filtered::<Entity, With<Breakdown>>();
+    assert_eq!(broken.iter(world).collect::<Vec<_>>(), vec![moved]);
+}
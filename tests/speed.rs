@@ -0,0 +1,79 @@
+//! Le pas à pas de la fenêtre exécute les mêmes ticks que la simulation normale.
+
+use bevy::prelude::*;
+use bevy::time::TimeSystems;
+use warehouse_sim::components::{Battery, GridPosition, Robot};
+use warehouse_sim::core::SpaceTimeTable;
+use warehouse_sim::plugins::simulation::SimulationSettings;
+use warehouse_sim::systems::speed::{apply_sim_speed_system, manual_ticks_system, SimSpeed};
+
+mod common;
+
+const WARMUP: u64 = 200;
+const STEPS: u32 = 45;
+
+/// App headless avec le contrôle de vitesse branché comme dans `WarehousePlugins`
+fn app() -> App {
+    let settings = SimulationSettings { seed: Some(5), ..common::fleet(12) };
+    let mut app = common::headless_app(settings, 0);
+    app.init_resource::<SimSpeed>()
+        .add_systems(First, apply_sim_speed_system.before(TimeSystems))
+        .add_systems(
+            RunFixedMainLoop,
+            manual_ticks_system.in_set(RunFixedMainLoopSystems::FixedMainLoop),
+        );
+    app
+}
+
+fn tick(app: &App) -> u64 {
+    app.world().resource::<SpaceTimeTable>().current_tick()
+}
+
+fn run_until(app: &mut App, end: u64) {
+    while tick(app) < end {
+        app.update();
+    }
+}
+
+/// Position et niveau de batterie de chaque robot, bit à bit
+fn fleet_state(app: &mut App) -> Vec<(Entity, GridPosition, u32)> {
+    let world = app.world_mut();
+    let mut robots = world.query_filtered::<(Entity, &GridPosition, &Battery), With<Robot>>();
+    let mut state: Vec<_> = robots.iter(world).map(|(e, pos, battery)| (e, *pos, battery.0.to_bits())).collect();
+    state.sort_by_key(|(e, ..)| *e);
+    state
+}
+
+#[test]
+fn steps_run_the_same_ticks_as_normal_play() {
+    let mut stepped = app();
+    run_until(&mut stepped, WARMUP);
+    let before = fleet_state(&mut stepped);
+    stepped.world_mut().resource_mut::<SimSpeed>().step(STEPS);
+
+    let mut frames = 0;
+    while stepped.world().resource::<SimSpeed>().pending_steps > 0 {
+        stepped.update();
+        frames += 1;
+        assert!(frames < 1000, "steps never drained");
+    }
+    assert_eq!(tick(&stepped), WARMUP + STEPS as u64);
+
+    // En pause, plus rien n'avance une fois les pas consommés
+    for _ in 0..10 {
+        stepped.update();
+    }
+    assert_eq!(tick(&stepped), WARMUP + STEPS as u64);
+
+    let mut played = app();
+    run_until(&mut played, WARMUP + STEPS as u64);
+    let (stepped, played) = (fleet_state(&mut stepped), fleet_state(&mut played));
+    assert!(!played.is_empty());
+    assert_eq!(stepped.len(), played.len());
+    // Les pas ont bien consommé de la batterie
+    assert!(before.iter().zip(&stepped).any(|((_, _, b), (_, _, a))| f32::from_bits(*a) < f32::from_bits(*b)));
+    for ((_, stepped_pos, stepped_battery), (_, played_pos, played_battery)) in stepped.iter().zip(&played) {
+        assert_eq!(stepped_pos.0, played_pos.0);
+        assert_eq!(stepped_battery, played_battery);
+    }
+}
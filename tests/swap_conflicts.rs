@@ -0,0 +1,176 @@
+//! Réservations d'arêtes orientées : deux robots ne doivent jamais échanger leurs cellules.
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use warehouse_sim::components::*;
+use warehouse_sim::core::*;
+use warehouse_sim::systems::events::SimEvent;
+use warehouse_sim::systems::metrics::SimMetrics;
+use warehouse_sim::systems::navigation::path_execution_system;
+use warehouse_sim::systems::pbs::{LowLevelPlanner, PbsConfig};
+use warehouse_sim::systems::planner::{planning_system, PlannerRegistry};
+
+mod common;
+
+use common::entity;
+
+#[test]
+fn reverse_edge_is_a_swap() {
+    let (a, b) = (entity(1), entity(2));
+    let (left, right) = (GridPos::new(0, 0), GridPos::new(1, 0));
+    let mut table = SpaceTimeTable::default();
+
+    assert!(table.reserve_path(&[(left, 0), (right, 1)], a));
+    assert_eq!(table.edge_occupant(left, right, 0), Some(a));
+
+    // Le déplacement inverse au même tick est un échange
+    assert!(!table.is_edge_free(right, left, 0, Some(b)));
+    assert!(!table.reserve_edge(right, left, 0, b));
+    // Le robot qui a réservé l'arête n'est pas en conflit avec lui-même
+    assert!(table.is_edge_free(right, left, 0, Some(a)));
+    // Un tick plus tard, l'arête inverse est libre
+    assert!(table.is_edge_free(right, left, 1, Some(b)));
+}
+
+#[test]
+fn following_move_is_not_a_swap() {
+    let (a, b) = (entity(1), entity(2));
+    let cells = [GridPos::new(0, 0), GridPos::new(1, 0), GridPos::new(2, 0)];
+    let mut table = SpaceTimeTable::default();
+
+    // `b` avance de 1 vers 2 pendant que `a` entre dans la cellule qu'il libère
+    assert!(table.reserve_path(&[(cells[1], 0), (cells[2], 1)], b));
+    assert!(table.is_edge_free(cells[0], cells[1], 0, Some(a)));
+    assert!(table.reserve_path(&[(cells[0], 0), (cells[1], 1)], a));
+}
+
+#[test]
+fn reserve_path_rejects_swap() {
+    let (a, b) = (entity(1), entity(2));
+    let (left, right) = (GridPos::new(0, 0), GridPos::new(1, 0));
+    let mut table = SpaceTimeTable::default();
+
+    assert!(table.reserve_path(&[(left, 0), (right, 1)], a));
+    assert!(!table.reserve_path(&[(right, 0), (left, 1)], b));
+    // Rien n'a été réservé pour `b`
+    assert_eq!(table.occupant(right, 0), None);
+    assert_eq!(table.edge_occupant(right, left, 0), None);
+}
+
+#[test]
+fn clearing_entity_releases_edges() {
+    let (a, b) = (entity(1), entity(2));
+    let (left, right) = (GridPos::new(0, 0), GridPos::new(1, 0));
+    let mut table = SpaceTimeTable::default();
+
+    table.reserve_path(&[(left, 0), (right, 1), (right, 2)], a);
+    table.clear_entity_except_pos(a, right, 1, 3);
+    assert_eq!(table.occupant(right, 2), Some(a));
+    assert!(table.is_edge_free(right, left, 0, Some(b)));
+
+    table.reserve_path(&[(right, 5), (left, 6)], a);
+    table.cleanup(7);
+    assert_eq!(table.edge_occupant(right, left, 5), None);
+
+    table.reserve_edge(left, right, 8, a);
+    table.clear_entity(a);
+    assert!(table.is_edge_free(right, left, 8, Some(b)));
+}
+
+/// Monde minimal pour faire tourner la planification et l'exécution des chemins
+fn world(grid: WarehouseGrid, zones: WarehouseZones, highways: HighwayGraph, solver: &str) -> World {
+    let mut world = World::new();
+    let low_level = if solver == "sipp" {
+        LowLevelPlanner::Sipp
+    } else {
+        LowLevelPlanner::SpaceTimeAStar
+    };
+    let mut registry = PlannerRegistry::default();
+    assert!(registry.select(if solver == "sipp" { "pbs" } else { solver }));
+
+    world.insert_resource(grid);
+    world.insert_resource(zones);
+    world.insert_resource(highways);
+    world.insert_resource(SpaceTimeTable::default());
+    world.insert_resource(PbsConfig { low_level, ..default() });
+    world.insert_resource(registry);
+    world.init_resource::<SimMetrics>();
+    world.init_resource::<Messages<SimEvent>>();
+    world
+}
+
+fn spawn_robot(world: &mut World, from: GridPos, to: GridPos) -> Entity {
+    world
+        .spawn((Robot, GridPosition(from), Destination(to), State(RobotState::Moving)))
+        .id()
+}
+
+fn positions(world: &World, robots: &[Entity]) -> Vec<GridPos> {
+    robots.iter().map(|&e| world.get::<GridPosition>(e).unwrap().0).collect()
+}
+
+/// Avance de `ticks` ticks en vérifiant qu'aucun robot ne saute, ne partage ni n'échange de cellule
+fn run_checked(world: &mut World, robots: &[Entity], ticks: usize, solver: &str) {
+    let mut previous = positions(world, robots);
+    for _ in 0..ticks {
+        world.resource_mut::<SpaceTimeTable>().advance_tick();
+        world.run_system_once(planning_system).unwrap();
+        world.run_system_once(path_execution_system).unwrap();
+
+        let tick = world.resource::<SpaceTimeTable>().current_tick();
+        let current = positions(world, robots);
+        for i in 0..robots.len() {
+            assert!(
+                previous[i].manhattan_distance(&current[i]) <= 1,
+                "{solver}: robot {i} jumped at tick {tick}"
+            );
+            for j in i + 1..robots.len() {
+                assert_ne!(current[i], current[j], "{solver}: robots {i} and {j} collide at tick {tick}");
+                let swapped = previous[i] != current[i]
+                    && previous[i] == current[j]
+                    && previous[j] == current[i];
+                assert!(!swapped, "{solver}: robots {i} and {j} swap cells at tick {tick}");
+            }
+        }
+        previous = current;
+    }
+}
+
+#[test]
+fn head_on_corridor_never_swaps() {
+    for solver in ["pbs", "sipp", "cbs", "ecbs", "pibt"] {
+        let layout = WarehouseLayout::from_ascii(
+            "
+            S..........A
+            .@@@@@@@@@@.
+            C..........A
+            ",
+        )
+        .unwrap();
+        let (grid, zones) = layout.build();
+        let highways = HighwayGraph::new(grid.width(), grid.height());
+        let mut world = world(grid, zones, highways, solver);
+
+        let (west, east) = (GridPos::new(0, 0), GridPos::new(11, 0));
+        let robots = [
+            spawn_robot(&mut world, west, east),
+            spawn_robot(&mut world, east, west),
+        ];
+        run_checked(&mut world, &robots, 80, solver);
+    }
+}
+
+#[test]
+fn fleet_never_swaps() {
+    for solver in ["pbs", "sipp", "cbs", "ecbs", "pibt"] {
+        let (grid, zones) = WarehouseLayout::default().build();
+        let highways = HighwayGraph::alternating(&grid, &zones, HighwayMode::default());
+        let (spawns, storage) = (zones.spawn_points.clone(), zones.storage_cells.clone());
+        let mut world = world(grid, zones, highways, solver);
+
+        let robots: Vec<Entity> = (0..40)
+            .map(|i| spawn_robot(&mut world, spawns[i % spawns.len()], storage[(i * 37) % storage.len()]))
+            .collect();
+        run_checked(&mut world, &robots, 150, solver);
+    }
+}
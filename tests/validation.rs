@@ -0,0 +1,142 @@
+//! Le validateur de mouvement repère chaque type de violation, et seulement celles-ci.
+
+use bevy::prelude::*;
+use warehouse_sim::core::{CellType, GridPos, WarehouseGrid, WarehouseLayout};
+use warehouse_sim::systems::validation::{validate_paths, validate_positions, MotionValidator, ViolationKind};
+
+mod common;
+
+use common::entity;
+
+fn grid() -> WarehouseGrid {
+    let layout = WarehouseLayout::from_ascii(
+        "
+        S.RA
+        ....
+        C@..
+        ",
+    )
+    .unwrap();
+    layout.build().0
+}
+
+/// Violations relevées sur des positions données tick par tick
+fn kinds(ticks: &[(u64, Vec<(Entity, GridPos)>)]) -> Vec<ViolationKind> {
+    validate_positions(&grid(), ticks.iter().map(|(tick, positions)| (*tick, &positions[..])))
+        .into_iter()
+        .map(|v| v.kind)
+        .collect()
+}
+
+fn at(x: i32, y: i32) -> GridPos {
+    GridPos::new(x, y)
+}
+
+#[test]
+fn legal_moves_pass() {
+    let (a, b) = (entity(1), entity(2));
+    let ticks = vec![
+        (0, vec![(a, at(0, 1)), (b, at(1, 1))]),
+        // `a` entre dans la cellule que `b` libère
+        (1, vec![(a, at(1, 1)), (b, at(2, 1))]),
+        // `b` disparaît deux ticks et revient à portée
+        (2, vec![(a, at(1, 1))]),
+        (4, vec![(a, at(1, 0)), (b, at(3, 2))]),
+    ];
+    assert!(kinds(&ticks).is_empty());
+}
+
+#[test]
+fn vertex_and_swap_conflicts() {
+    let (a, b) = (entity(1), entity(2));
+    let vertex = vec![
+        (0, vec![(a, at(0, 1)), (b, at(1, 1))]),
+        (1, vec![(a, at(1, 1)), (b, at(1, 1))]),
+    ];
+    assert_eq!(kinds(&vertex), [ViolationKind::Vertex { other: a }]);
+
+    let swap = vec![
+        (0, vec![(a, at(0, 1)), (b, at(1, 1))]),
+        (1, vec![(b, at(0, 1)), (a, at(1, 1))]),
+    ];
+    assert_eq!(kinds(&swap), [ViolationKind::Swap { other: b }]);
+}
+
+#[test]
+fn obstacles_and_grid_bounds() {
+    let a = entity(1);
+    let ticks = vec![
+        (0, vec![(a, at(2, 0))]),
+        (1, vec![(a, at(2, 1))]),
+        (2, vec![(a, at(1, 1))]),
+        (3, vec![(a, at(1, 2))]),
+        (4, vec![(a, at(1, 3))]),
+    ];
+    assert_eq!(
+        kinds(&ticks),
+        [
+            ViolationKind::Obstacle(CellType::Rack),
+            ViolationKind::Obstacle(CellType::Blocked),
+            ViolationKind::OutOfGrid,
+        ]
+    );
+}
+
+#[test]
+fn jumps_and_teleports() {
+    let a = entity(1);
+    let straight = vec![(0, vec![(a, at(0, 1))]), (1, vec![(a, at(2, 1))])];
+    assert_eq!(kinds(&straight), [ViolationKind::Jump { from: at(0, 1) }]);
+
+    let diagonal = vec![(0, vec![(a, at(0, 1))]), (1, vec![(a, at(1, 0))])];
+    assert_eq!(kinds(&diagonal), [ViolationKind::Jump { from: at(0, 1) }]);
+
+    let teleport = vec![(0, vec![(a, at(0, 1))]), (2, vec![(a, at(3, 0))])];
+    assert_eq!(kinds(&teleport), [ViolationKind::Teleport { from: at(0, 1), ticks: 2 }]);
+}
+
+#[test]
+fn planned_paths_are_replayed() {
+    let grid = grid();
+    let (a, b) = (entity(1), entity(2));
+
+    // Face à face dans le couloir du milieu
+    let east = [(at(1, 1), 11), (at(2, 1), 12), (at(3, 1), 13)];
+    let west = [(at(2, 1), 11), (at(1, 1), 12), (at(0, 1), 13)];
+    let head_on = validate_paths(&grid, 10, &[(a, at(0, 1), &east), (b, at(3, 1), &west)]);
+    assert!(head_on
+        .iter()
+        .any(|v| matches!(v.kind, ViolationKind::Vertex { .. } | ViolationKind::Swap { .. })));
+
+    // `b` s'écarte en (3, 0) pour laisser passer `a`
+    let aside = [(at(3, 0), 11)];
+    assert!(validate_paths(&grid, 10, &[(a, at(0, 1), &east), (b, at(3, 1), &aside)]).is_empty());
+
+    // Un robot arrivé reste sur sa cellule : la traverser ensuite est une collision
+    let parked = [(at(2, 1), 11)];
+    let late = [(at(1, 1), 14), (at(2, 1), 15), (at(3, 1), 16)];
+    let violations = validate_paths(&grid, 10, &[(a, at(0, 1), &late), (b, at(3, 1), &parked)]);
+    assert_eq!(violations.len(), 1);
+    assert_eq!((violations[0].tick, violations[0].kind), (15, ViolationKind::Vertex { other: a }));
+}
+
+/// Contrôle au fil de l'eau, tel que l'appellent les systèmes : chaque tick est comparé au
+/// précédent et la violation se lit telle quelle dans un message d'erreur
+#[test]
+fn ticks_are_checked_as_they_come() {
+    let grid = grid();
+    let (a, b) = (entity(1), entity(2));
+    let mut validator = MotionValidator::default();
+
+    assert!(validator.check_tick(&grid, 0, &[(a, at(0, 1)), (b, at(2, 1))]).is_empty());
+    assert!(validator.check_tick(&grid, 1, &[(a, at(1, 1)), (b, at(2, 1))]).is_empty());
+    let swap = validator.check_tick(&grid, 2, &[(a, at(2, 1)), (b, at(1, 1))]);
+    assert_eq!(swap.len(), 1);
+    assert_eq!(swap[0].to_string(), "tick 2: robot #1 at (2, 1) swaps cells with robot #2");
+
+    // Les positions fautives deviennent la référence du tick suivant
+    assert!(validator.check_tick(&grid, 3, &[(a, at(3, 1)), (b, at(0, 1))]).is_empty());
+    let jump = validator.check_tick(&grid, 4, &[(a, at(3, 1)), (b, at(2, 1))]);
+    assert_eq!(jump.len(), 1);
+    assert_eq!(jump[0].to_string(), "tick 4: robot #2 at (2, 1) jumped from (0, 1)");
+}